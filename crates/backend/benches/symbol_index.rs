@@ -0,0 +1,63 @@
+//! Compares the resolution of call targets by scanning `.symtab`, as done before
+//! [`SymbolIndex`] was introduced, with lookups in the index.
+//!
+//! The analysed binary is read from `SYMBOL_INDEX_BENCH_ELF`, which should point to a large
+//! statically linked binary (e.g., a static build of `ffmpeg` or `busybox`). When unset, the
+//! benchmark analyses its own executable.
+//!
+//! ```bash
+//! SYMBOL_INDEX_BENCH_ELF=/path/to/ffmpeg cargo bench -p manifest-producer-backend
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use goblin::elf::{sym, Elf};
+use manifest_producer_backend::{
+    detect::function_detection,
+    inspect::{parse_elf, read_elf},
+    symbols::SymbolIndex,
+};
+
+fn linear_lookup<'a>(elf: &'a Elf, address: u64) -> Option<&'a str> {
+    elf.syms
+        .iter()
+        .find(|symbol| symbol.st_type() == sym::STT_FUNC && symbol.st_value == address)
+        .and_then(|symbol| elf.strtab.get_at(symbol.st_name))
+}
+
+fn symbol_lookup(c: &mut Criterion) {
+    let path = std::env::var("SYMBOL_INDEX_BENCH_ELF").unwrap_or_else(|_| {
+        std::env::current_exe()
+            .expect("benchmark executable")
+            .to_string_lossy()
+            .into_owned()
+    });
+    let buffer = read_elf(&path).expect("readable ELF binary");
+    let elf = parse_elf(&buffer).expect("valid ELF binary");
+    let functions = function_detection(&elf, &buffer, "C").expect("function detection");
+    // Every function start stands for a call target.
+    let targets: Vec<u64> = functions.values().map(|func| func.start_addr).collect();
+
+    let mut group = c.benchmark_group(format!("resolve {} call targets", targets.len()));
+    group.sample_size(10);
+    group.bench_function("symtab scan", |b| {
+        b.iter(|| {
+            for &target in &targets {
+                black_box(linear_lookup(&elf, target));
+            }
+        });
+    });
+    group.bench_function("symbol index", |b| {
+        b.iter(|| {
+            let index = SymbolIndex::new(&elf, &functions, "C").expect("symbol index");
+            for &target in &targets {
+                black_box(index.get(target));
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, symbol_lookup);
+criterion_main!(benches);
@@ -1,35 +1,57 @@
 use crate::{
-    detect::demangle_function_name,
+    arch::{Arch, RegisterValues},
     entry::calculate_invocation_count,
-    error::{Error, Result},
-    inspect::{find_text_section, get_name_addr},
-    syscall::detect_syscalls,
-    FunctionNode,
+    error::Result,
+    imports::Imports,
+    inspect::{code_slice, Pointers},
+    instruction::{disassemble, Instruction},
+    symbols::SymbolIndex,
+    syscall::{detect_syscalls, SyscallTable},
+    threads::detect_thread_spawns,
+    wrappers::{detect_implied_syscalls, Wrappers},
+    FunctionId, FunctionNode,
 };
 
-use capstone::{
-    arch::{self, BuildsCapstone, BuildsCapstoneSyntax},
-    Capstone,
-};
+use capstone::Capstone;
 use goblin::elf::Elf;
+use rayon::prelude::*;
 
 use std::fmt::Write;
-use std::{collections::HashMap, fs::File};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+};
 
 /// Disassembles and analyses functions in an ELF binary.
 ///
 /// # Overview
 ///
 /// Iterates through all detected functions from [`crate::detect::function_detection`], disassembles their machine code,
-/// and updates their [`FunctionNode`] structures with details like child functions and disassembly results.
+/// and updates their [`FunctionNode`] structures with details like child functions, instructions and disassembly results.
+/// Jumps to the start of another function are tail calls: their targets are children as well,
+/// and are counted in [`FunctionNode::jmp`], while the jumps within the function are left out
+/// of the call graph.
+/// Calls to functions imported from shared libraries are resolved through their PLT stubs and
+/// GOT slots (see [`Imports`]), and the called imports are added to `functions` as nodes of
+/// their own. The other call targets are looked up in a [`SymbolIndex`] built once for the
+/// binary. The system calls of every function are then detected, both the ones it issues
+/// directly and the ones implied by the wrapper functions it calls (see [`Wrappers`]), and
+/// so are the threads it starts (see [`detect_thread_spawns`]).
 /// Results are also saved as JSON.
 ///
+/// Functions are disassembled in parallel: every worker thread reuses its own Capstone
+/// handles, and the call targets are resolved through read-only tables shared by all threads,
+/// so that the results do not depend on the order in which the functions are analysed.
+///
 /// # Arguments
 ///
 /// - `elf`: A reference to an [`Elf`] structure.
 /// - `buffer`: Byte buffer of the ELF binary.
-/// - `functions`: A mutable `HashMap` of detected functions as `FunctionNode` objects.
+/// - `functions`: A mutable `HashMap` of detected functions as `FunctionNode` objects, keyed by
+///   [`FunctionId`].
 /// - `language`: The programming language of the binary.
+/// - `syscall_table`: The table naming the system calls, or `None` to use the one embedded for
+///   the architecture of the binary (see [`SyscallTable::bundled`]).
 /// - `output_path`: Directory to save the JSON file with analysis results.
 ///
 /// # Returns
@@ -38,6 +60,8 @@ use std::{collections::HashMap, fs::File};
 ///
 /// # Errors
 ///
+/// - Returns [`Error::InvalidFormat`](crate::error::Error::InvalidFormat) if the architecture
+///   of the binary is not supported (see [`Arch`]).
 /// - Possible errors related to the disassembly of machine code.
 ///
 /// # Feature Flags
@@ -47,13 +71,15 @@ use std::{collections::HashMap, fs::File};
 pub fn analyse_functions(
     elf: &Elf,
     buffer: &[u8],
-    functions: &mut HashMap<String, FunctionNode>,
+    functions: &mut HashMap<FunctionId, FunctionNode>,
     language: &str,
+    syscall_table: Option<&SyscallTable>,
     output_path: &str,
 ) -> Result<()> {
     if functions.is_empty() {
         return Ok(());
     }
+    let arch = Arch::from_elf(elf)?;
 
     #[cfg(feature = "progress_bar")]
     let pb = {
@@ -66,22 +92,70 @@ pub fn analyse_functions(
         pb
     };
 
-    // I know, it's not elegant. At the moment I have no alternative but to clone, because
-    // otherwise rust would not allow me to borrow functions as mutable more than once.
-    let mut func_clone = functions.clone();
-    for func in functions.values_mut() {
-        let (nodes, disassembly, flag) =
-            disassemble_function(elf, func, buffer, &mut func_clone, language)?;
-        func.children = nodes;
-        func.set_disassembly(disassembly);
-        func.syscall = flag;
-
-        #[cfg(feature = "progress_bar")]
-        pb.inc(1);
+    let targets = CallTargets {
+        imports: Imports::new(elf, buffer, language)?,
+        symbols: SymbolIndex::new(elf, functions, language)?,
+        functions: functions.keys().copied().collect(),
+        pointers: Pointers::new(elf, buffer),
+    };
+    functions.par_iter_mut().try_for_each_init(
+        || Disassembler::new(arch),
+        |disassembler, (_, func)| -> Result<()> {
+            let (nodes, tail_calls, instructions, disassembly, flag) =
+                disassemble_function(elf, func, buffer, &targets, disassembler)?;
+            func.jmp = tail_calls.len();
+            func.tail_calls = nodes
+                .iter()
+                .copied()
+                .filter(|id| tail_calls.contains(id))
+                .filter(|&id| {
+                    // Children also called are drawn as calls.
+                    !instructions
+                        .iter()
+                        .any(|insn| insn.is_call() && insn.callee == Some(id))
+                })
+                .collect();
+            func.children = nodes;
+            func.instructions = instructions;
+            func.set_disassembly(disassembly);
+            func.syscall = flag;
+
+            #[cfg(feature = "progress_bar")]
+            pb.inc(1);
+            Ok(())
+        },
+    )?;
+
+    let called_imports: Vec<FunctionId> = functions
+        .values()
+        .flat_map(|func| func.children.iter())
+        .filter(|child| !functions.contains_key(*child))
+        .copied()
+        .collect();
+    for id in called_imports {
+        if let Some(node) = targets.imports.node(id) {
+            functions.insert(id, node);
+        }
     }
 
     calculate_invocation_count(functions);
-    detect_syscalls(functions)?;
+    let bundled;
+    let syscall_table = if let Some(table) = syscall_table {
+        table
+    } else {
+        bundled = SyscallTable::bundled(arch)?;
+        &bundled
+    };
+    detect_syscalls(elf, buffer, functions, arch, syscall_table)?;
+    detect_implied_syscalls(
+        elf,
+        buffer,
+        functions,
+        arch,
+        syscall_table,
+        &Wrappers::bundled()?,
+    );
+    detect_thread_spawns(elf, buffer, functions, arch);
 
     let file = File::create(format!("{output_path}/json/functions_list.json"))?;
     serde_json::to_writer_pretty(file, &functions)?;
@@ -95,114 +169,289 @@ fn disassemble_function(
     elf: &Elf,
     func: &FunctionNode,
     buffer: &[u8],
-    functions: &mut HashMap<String, FunctionNode>,
-    language: &str,
-) -> Result<(Vec<String>, String, bool)> {
-    let start_address = func.start_addr;
-    analyse_code_slice(elf, buffer, func, start_address, functions, language)
+    targets: &CallTargets<'_>,
+    disassembler: &mut Disassembler,
+) -> Result<AnalysedCode> {
+    let arch = disassembler.arch;
+    let cs = disassembler.get(func.thumb)?;
+    analyse_code_slice(elf, arch, cs, buffer, func, targets)
 }
 
 fn analyse_code_slice(
     elf: &Elf,
+    arch: Arch,
+    cs: &Capstone,
     buffer: &[u8],
     function: &FunctionNode,
-    start_address: u64,
-    functions: &mut HashMap<String, FunctionNode>,
-    language: &str,
-) -> Result<(Vec<String>, String, bool)> {
-    let cs = cs_init()?;
-    let code_slice = init_disassembly(elf, function, buffer)?;
+    targets: &CallTargets<'_>,
+) -> Result<AnalysedCode> {
+    let code_slice = init_disassembly(elf, function, buffer);
 
     let mut nodes = Vec::new();
+    let mut tail_calls = Vec::new();
     let mut disassembly_output = String::new();
     let mut flag = false;
+    let mut values = RegisterValues::new(arch, function.thumb);
 
     // Skip if there is no code to disassemble
     if code_slice.is_empty() {
-        return Ok((nodes, String::new(), flag));
+        return Ok((nodes, tail_calls, Vec::new(), String::new(), flag));
     }
 
-    let instruction = cs.disasm_all(code_slice, start_address)?;
+    let mut instructions = disassemble(cs, arch, code_slice, function.start_addr)?;
 
-    for insn in instruction.iter() {
-        let insn_name = cs.insn_name(insn.id()).unwrap_or_default();
-        let op_str = insn.op_str().unwrap_or_default();
+    for insn in &mut instructions {
+        let insn_name = &insn.mnemonic;
+        let op_str = &insn.op_str;
+        let is_syscall = insn.is_syscall(arch);
+        // On 32-bit x86 system calls may be entered by calling the vDSO.
+        let is_call = !is_syscall && insn.is_call();
+        let is_jump = !is_syscall && insn.is_jump();
+        let target = if is_call || is_jump {
+            values.call_target(insn)
+        } else {
+            None
+        };
+        let value = values.update(&targets.pointers, insn);
+        let tail_call = is_jump
+            .then(|| targets.tail_call(target, insn, function))
+            .flatten();
 
-        if insn_name == "call" {
-            let called_func_name = call_insn(elf, op_str, language);
-            if let Some(func_name) = called_func_name {
-                if functions.contains_key(&func_name) && !nodes.contains(&func_name) {
-                    nodes.push(func_name.clone());
+        if is_call {
+            let called_func = targets.resolve(target, insn);
+            if let Some((id, func_name)) = called_func {
+                if targets.functions.contains(&id) || targets.imports.id(&func_name) == Some(id) {
+                    insn.callee = Some(id);
+                    if !nodes.contains(&id) {
+                        nodes.push(id);
+                    }
                 }
                 writeln!(
                     disassembly_output,
                     "0x{:x}:\t{}\t{}\t<{}>\n",
-                    insn.address(),
-                    insn_name,
-                    op_str,
-                    func_name
+                    insn.address, insn_name, op_str, func_name
+                )?;
+            } else if let Some((func_name, offset)) = target.and_then(|addr| {
+                let (name, start) = targets.symbols.containing(addr)?;
+                Some((name, addr - start))
+            }) {
+                // Calls into the middle of a function (e.g., to get the program counter on
+                // i386) do not add a child.
+                writeln!(
+                    disassembly_output,
+                    "0x{:x}:\t{}\t{}\t<{}+0x{:x}>\n",
+                    insn.address, insn_name, op_str, func_name, offset
                 )?;
             } else {
                 writeln!(
                     disassembly_output,
                     "0x{:x}:\t{}\t{}\t(Register Offset-GOT)\n",
-                    insn.address(),
-                    insn_name,
-                    op_str
+                    insn.address, insn_name, op_str
                 )?;
             }
-        } else if insn_name == "syscall" {
+        } else if let Some((id, func_name)) = tail_call {
+            insn.callee = Some(id);
+            tail_calls.push(id);
+            if !nodes.contains(&id) {
+                nodes.push(id);
+            }
+            writeln!(
+                disassembly_output,
+                "0x{:x}:\t{}\t{}\t<{}>\t(Tail Call)\n",
+                insn.address, insn_name, op_str, func_name
+            )?;
+        } else if is_syscall {
             flag = true;
             writeln!(
                 disassembly_output,
-                "0x{:x}:\t{}\t\t(System Call Invoked)\n",
-                insn.address(),
-                insn_name
+                "0x{:x}:\t{}\t{}\t(System Call Invoked)\n",
+                insn.address, insn_name, op_str
+            )?;
+        } else if let Some(value) = value {
+            writeln!(
+                disassembly_output,
+                "0x{:x}:\t{}\t{}\t(=0x{:x})\n",
+                insn.address, insn_name, op_str, value
             )?;
         } else {
             writeln!(
                 disassembly_output,
                 "0x{:x}:\t{}\t{}\n",
-                insn.address(),
-                insn_name,
-                op_str
+                insn.address, insn_name, op_str
             )?;
         }
+        insn.value = value;
     }
 
-    Ok((nodes, disassembly_output, flag))
+    Ok((nodes, tail_calls, instructions, disassembly_output, flag))
 }
 
-fn call_insn(elf: &Elf, op_str: &str, language: &str) -> Option<String> {
-    op_str
-        .strip_prefix("0x")
-        .and_then(|addr_str| u64::from_str_radix(addr_str, 16).ok())
-        .and_then(|addr| get_name_addr(elf, addr))
-        .and_then(|name| demangle_function_name(name, language).ok())
-        .map(|name| name.to_string())
+/// The children, tail calls (one for each jump), instructions, disassembly and system call
+/// flag of an analysed function.
+type AnalysedCode = (
+    Vec<FunctionId>,
+    Vec<FunctionId>,
+    Vec<Instruction>,
+    String,
+    bool,
+);
+
+/// The functions that the calls of the binary can be resolved to, shared read-only by the
+/// threads of the analysis.
+struct CallTargets<'a> {
+    imports: Imports,
+    symbols: SymbolIndex,
+    /// The detected functions, which become children of the functions calling them.
+    functions: HashSet<FunctionId>,
+    /// The pointers loaded into the registers holding the targets of indirect calls.
+    pointers: Pointers<'a>,
 }
 
-fn init_disassembly<'a>(elf: &'a Elf, api: &'a FunctionNode, buffer: &'a [u8]) -> Result<&'a [u8]> {
-    let text_section = find_text_section(elf).ok_or(Error::TextSectionNotFound)?;
-    let text_start_index = usize::try_from(text_section.sh_offset).unwrap(); // TODO: Try to remove unwrap()!
+/// The Capstone handles of a worker thread, built on first use for each instruction set.
+struct Disassembler {
+    arch: Arch,
+    /// ARM and Thumb handles; only the first one is used on the other architectures.
+    handles: [Option<Capstone>; 2],
+}
 
-    if api.start_addr > text_section.sh_addr {
-        let func_start_offset = usize::try_from(api.start_addr - text_section.sh_addr).unwrap(); // TODO: Try to remove unwrap()!
-        let func_end_offset = usize::try_from(api.end_addr - text_section.sh_addr).unwrap(); // TODO: Try to remove unwrap()!
-        let code_slice =
-            &buffer[text_start_index + func_start_offset..text_start_index + func_end_offset];
-        Ok(code_slice)
-    } else {
-        Ok(&[])
+impl Disassembler {
+    fn new(arch: Arch) -> Self {
+        Self {
+            arch,
+            handles: [None, None],
+        }
+    }
+
+    fn get(&mut self, thumb: bool) -> Result<&Capstone> {
+        let thumb = thumb && self.arch == Arch::Arm;
+        let handle = &mut self.handles[usize::from(thumb)];
+        Ok(match handle {
+            Some(cs) => cs,
+            None => handle.insert(self.arch.capstone(thumb)?),
+        })
+    }
+}
+
+impl CallTargets<'_> {
+    /// Returns the identifier and the name of the function called by an instruction whose
+    /// target is `target`, or which reads its target from a GOT slot when `target` is `None`.
+    fn resolve(&self, target: Option<u64>, insn: &Instruction) -> Option<(FunctionId, String)> {
+        let import = match target {
+            None => self.imports.indirect_call(insn),
+            Some(addr) => self.imports.stub(addr),
+        };
+        if let Some(name) = import {
+            return Some((self.imports.id(name).or(target)?, name.to_string()));
+        }
+        let addr = target?;
+        self.symbols.get(addr).map(|name| (addr, name.to_string()))
+    }
+
+    /// Returns the identifier and the name of the function a jump of `function` tail-calls,
+    /// or `None` if the jump stays within `function` or does not land at the start of a
+    /// function of the call graph.
+    fn tail_call(
+        &self,
+        target: Option<u64>,
+        insn: &Instruction,
+        function: &FunctionNode,
+    ) -> Option<(FunctionId, String)> {
+        if target.is_some_and(|addr| (function.start_addr..function.end_addr).contains(&addr)) {
+            return None;
+        }
+        self.resolve(target, insn).filter(|(id, name)| {
+            *id != function.start_addr
+                && (self.functions.contains(id) || self.imports.id(name) == Some(*id))
+        })
     }
 }
 
-fn cs_init() -> Result<Capstone> {
-    Capstone::new()
-        .x86()
-        .mode(arch::x86::ArchMode::Mode64)
-        .syntax(arch::x86::ArchSyntax::Att)
-        .detail(true)
-        .build()
-        .map_err(Into::into)
+fn init_disassembly<'a>(elf: &'a Elf, api: &'a FunctionNode, buffer: &'a [u8]) -> &'a [u8] {
+    code_slice(elf, buffer, api.start_addr, api.end_addr).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goblin::elf::section_header::{SHF_ALLOC, SHF_EXECINSTR, SHT_PROGBITS};
+
+    #[test]
+    fn test_analyse_tail_calls() {
+        let mut code = vec![0xcc; 0x40];
+        let mut place = |addr: usize, bytes: &[u8]| {
+            code[addr - 0x1000..addr - 0x1000 + bytes.len()].copy_from_slice(bytes);
+        };
+        place(
+            0x1000,
+            &[
+                0x85, 0xff, // test %edi, %edi
+                0x74, 0x07, // je 0x100b
+                0xe8, 0x17, 0x00, 0x00, 0x00, // call 0x1020 <called>
+                0xeb, 0x15, // jmp 0x1020 <called>
+                0x0f, 0x85, 0x1f, 0x00, 0x00, 0x00, // jne 0x1030 <jumped>
+                0xe9, 0x1a, 0x00, 0x00, 0x00, // jmp 0x1030 <jumped>
+            ],
+        );
+        place(0x1020, &[0x31, 0xc0, 0xc3]); // xor %eax, %eax; ret
+        place(0x1030, &[0xeb, 0xfe]); // jmp 0x1030
+
+        let mut header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        header.e_machine = goblin::elf::header::EM_X86_64;
+        let mut elf = Elf::lazy_parse(header).unwrap();
+        elf.section_headers.push(goblin::elf::SectionHeader {
+            sh_type: SHT_PROGBITS,
+            sh_flags: u64::from(SHF_ALLOC | SHF_EXECINSTR),
+            sh_addr: 0x1000,
+            sh_size: 0x40,
+            ..Default::default()
+        });
+        let mut functions: HashMap<FunctionId, FunctionNode> = [
+            FunctionNode::new("dispatch".to_string(), 0x1000, 0x1020),
+            FunctionNode::new("called".to_string(), 0x1020, 0x1030),
+            FunctionNode::new("jumped".to_string(), 0x1030, 0x1040),
+        ]
+        .into_iter()
+        .map(|node| (node.start_addr, node))
+        .collect();
+        let output_path = std::env::temp_dir().join("analyse_tail_calls");
+        std::fs::create_dir_all(output_path.join("json")).unwrap();
+
+        analyse_functions(
+            &elf,
+            &code,
+            &mut functions,
+            "C",
+            None,
+            output_path.to_str().unwrap(),
+        )
+        .unwrap();
+
+        // The conditional jump within `dispatch` adds no edge, and `called` is drawn as a
+        // call since it is called as well.
+        let dispatch = &functions[&0x1000];
+        assert_eq!(dispatch.children, vec![0x1020, 0x1030]);
+        assert_eq!(dispatch.tail_calls, vec![0x1030]);
+        assert_eq!(dispatch.jmp, 3);
+        let callees: Vec<_> = dispatch
+            .instructions
+            .iter()
+            .take(6)
+            .map(|insn| insn.callee)
+            .collect();
+        assert_eq!(
+            callees,
+            vec![
+                None,
+                None,
+                Some(0x1020),
+                Some(0x1020),
+                Some(0x1030),
+                Some(0x1030)
+            ]
+        );
+        // A jump to the start of the function itself is a loop, not a tail call.
+        let jumped = &functions[&0x1030];
+        assert!(jumped.children.is_empty());
+        assert_eq!(jumped.jmp, 0);
+    }
 }
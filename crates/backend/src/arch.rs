@@ -0,0 +1,646 @@
+use std::collections::HashMap;
+
+use capstone::{
+    arch::{self, BuildsCapstone, BuildsCapstoneExtraMode, BuildsCapstoneSyntax},
+    Capstone, Insn,
+};
+use goblin::elf::Elf;
+
+use crate::{
+    error::{Error, Result},
+    inspect::Pointers,
+    instruction::{strip_compressed, Instruction, Operand},
+};
+
+/// Instruction set architectures supported by the analysis.
+///
+/// # Overview
+///
+/// `Arch` gathers every architecture-specific detail of the pipeline: the Capstone backend used
+/// to disassemble the code, the register holding the system call number, the register carrying
+/// the first argument of a call (used by [`find_main`](crate::entry::find_main)) and the
+/// syscall table mapping numbers to names. The rest of the analysis works on the operands of
+/// the instructions (see [`Instruction`]) and is unchanged across architectures.
+///
+/// 32-bit ARM code mixes two instruction sets: ARM and Thumb. Following the ARM ELF ABI, the
+/// address of a Thumb function has its lowest bit set (see [`Arch::is_thumb`] and
+/// [`Arch::code_address`]).
+///
+/// On 32-bit x86 the arguments of a call are pushed on the stack rather than passed in
+/// registers, and system calls are entered with `int $0x80`, `sysenter` or through the vDSO
+/// entry point stored at `%gs:0x10`.
+///
+/// RISC-V code is disassembled with the compressed (`C`) extension enabled; the `c.` prefix
+/// of compressed instructions is kept in the disassembly and ignored by the analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    X86,
+    X86_64,
+    AArch64,
+    Arm,
+    RiscV64,
+}
+
+impl Arch {
+    /// Returns the architecture of an ELF binary.
+    ///
+    /// # Arguments
+    ///
+    /// - `elf`: A reference to an [`Elf`] structure.
+    ///
+    /// # Returns
+    ///
+    /// - A `Result` containing the [`Arch`] of the binary.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::InvalidFormat`] if the machine type of the binary is not supported.
+    pub fn from_elf(elf: &Elf) -> Result<Self> {
+        match elf.header.e_machine {
+            goblin::elf::header::EM_386 => Ok(Arch::X86),
+            goblin::elf::header::EM_X86_64 => Ok(Arch::X86_64),
+            goblin::elf::header::EM_AARCH64 => Ok(Arch::AArch64),
+            goblin::elf::header::EM_ARM => Ok(Arch::Arm),
+            goblin::elf::header::EM_RISCV if elf.is_64 => Ok(Arch::RiscV64),
+            _ => Err(Error::InvalidFormat(goblin::error::Error::Malformed(
+                "Architecture currently not supported.".to_string(),
+            ))),
+        }
+    }
+
+    /// Returns the name of the architecture, as reported in [`BasicInfo`](crate::BasicInfo).
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Arch::X86 => "x86",
+            Arch::X86_64 => "x86_64",
+            Arch::AArch64 => "AArch64",
+            Arch::Arm => "ARM",
+            Arch::RiscV64 => "RISC-V",
+        }
+    }
+
+    /// Returns the register holding the system call number.
+    #[must_use]
+    pub fn syscall_register(self) -> &'static str {
+        match self {
+            Arch::X86 | Arch::X86_64 => "eax",
+            Arch::AArch64 => "x8",
+            Arch::Arm => "r7",
+            Arch::RiscV64 => "a7",
+        }
+    }
+
+    /// Returns the registers carrying the arguments of a system call, in order.
+    #[must_use]
+    pub fn syscall_argument_registers(self) -> &'static [&'static str] {
+        match self {
+            Arch::X86 => &["ebx", "ecx", "edx", "esi", "edi", "ebp"],
+            Arch::X86_64 => &["rdi", "rsi", "rdx", "r10", "r8", "r9"],
+            Arch::AArch64 => &["x0", "x1", "x2", "x3", "x4", "x5"],
+            Arch::Arm => &["r0", "r1", "r2", "r3", "r4", "r5"],
+            Arch::RiscV64 => &["a0", "a1", "a2", "a3", "a4", "a5"],
+        }
+    }
+
+    /// Returns the register the kernel writes the result of a system call to.
+    #[must_use]
+    pub fn syscall_return_register(self) -> &'static str {
+        match self {
+            Arch::X86 | Arch::X86_64 => "eax",
+            Arch::AArch64 => "x0",
+            Arch::Arm => "r0",
+            Arch::RiscV64 => "a0",
+        }
+    }
+
+    /// Returns the registers carrying the arguments of a call, in order. On 32-bit x86, where
+    /// the arguments are on the stack, no register is returned.
+    #[must_use]
+    pub fn call_argument_registers(self) -> &'static [&'static str] {
+        match self {
+            Arch::X86 => &[],
+            Arch::X86_64 => &["rdi", "rsi", "rdx", "rcx", "r8", "r9"],
+            Arch::AArch64 => &["x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7"],
+            Arch::Arm => &["r0", "r1", "r2", "r3"],
+            Arch::RiscV64 => &["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"],
+        }
+    }
+
+    /// Returns the register carrying the first argument of a call. On 32-bit x86, where the
+    /// arguments are on the stack, the stack pointer is returned.
+    #[must_use]
+    pub fn argument_register(self) -> &'static str {
+        match self {
+            Arch::X86 => "esp",
+            Arch::X86_64 => "rdi",
+            Arch::AArch64 => "x0",
+            Arch::Arm => "r0",
+            Arch::RiscV64 => "a0",
+        }
+    }
+
+    /// Returns the JSON table mapping system call numbers to names, embedded in the crate at
+    /// compile time (see [`SyscallTable::bundled`](crate::syscall::SyscallTable::bundled)).
+    #[must_use]
+    pub fn syscall_table(self) -> &'static str {
+        match self {
+            Arch::X86 => include_str!("../data/syscall_tab_i386.json"),
+            Arch::X86_64 => include_str!("../data/syscall_tab.json"),
+            Arch::AArch64 => include_str!("../data/syscall_tab_aarch64.json"),
+            Arch::Arm => include_str!("../data/syscall_tab_arm.json"),
+            Arch::RiscV64 => include_str!("../data/syscall_tab_riscv64.json"),
+        }
+    }
+
+    /// Whether the code at `addr` is made of Thumb instructions. Always false except on ARM.
+    #[must_use]
+    pub fn is_thumb(self, addr: u64) -> bool {
+        self == Arch::Arm && addr & 1 == 1
+    }
+
+    /// Returns the address of the first instruction of the code pointed to by `addr`, i.e.,
+    /// `addr` without the Thumb bit.
+    #[must_use]
+    pub fn code_address(self, addr: u64) -> u64 {
+        match self {
+            Arch::Arm => addr & !1,
+            _ => addr,
+        }
+    }
+
+    /// Returns the possible layouts of the `.plt` section, as pairs of header and stub sizes.
+    /// Empty on x86, where the stubs are decoded instead (see
+    /// [`Imports`](crate::imports::Imports)).
+    #[must_use]
+    pub fn plt_layouts(self) -> &'static [(u64, u64)] {
+        match self {
+            Arch::X86 | Arch::X86_64 => &[],
+            // Stubs grow to 24 bytes with BTI.
+            Arch::AArch64 => &[(32, 16), (32, 24)],
+            // GNU ld emits 12-byte stubs, or 16-byte stubs for large GOTs; LLD 16-byte stubs
+            // after a 32-byte header.
+            Arch::Arm => &[(20, 12), (20, 16), (32, 16)],
+            Arch::RiscV64 => &[(32, 16)],
+        }
+    }
+
+    /// Builds the disassembler for the architecture. `thumb` selects the Thumb instruction set
+    /// on ARM and is ignored elsewhere.
+    pub(crate) fn capstone(self, thumb: bool) -> Result<Capstone> {
+        match self {
+            Arch::X86 | Arch::X86_64 => Capstone::new()
+                .x86()
+                .mode(if self == Arch::X86 {
+                    arch::x86::ArchMode::Mode32
+                } else {
+                    arch::x86::ArchMode::Mode64
+                })
+                .syntax(arch::x86::ArchSyntax::Att)
+                .detail(true)
+                .build(),
+            Arch::AArch64 => Capstone::new()
+                .arm64()
+                .mode(arch::arm64::ArchMode::Arm)
+                .detail(true)
+                .build(),
+            Arch::Arm => Capstone::new()
+                .arm()
+                .mode(if thumb {
+                    arch::arm::ArchMode::Thumb
+                } else {
+                    arch::arm::ArchMode::Arm
+                })
+                .detail(true)
+                .build(),
+            Arch::RiscV64 => Capstone::new()
+                .riscv()
+                .mode(arch::riscv::ArchMode::RiscV64)
+                .extra_mode([arch::riscv::ArchExtraMode::RiscVC].iter().copied())
+                .detail(true)
+                .build(),
+        }
+        .map_err(Into::into)
+    }
+
+    /// Returns the canonical names of the stack and frame pointers, relative to which the
+    /// local variables of a function are addressed.
+    pub(crate) fn frame_registers(self) -> &'static [&'static str] {
+        match self {
+            Arch::X86 | Arch::X86_64 => &["rsp", "rbp"],
+            Arch::AArch64 => &["sp", "x29", "fp"],
+            Arch::Arm => &["sp", "r7", "r11", "fp"],
+            Arch::RiscV64 => &["sp", "s0", "fp"],
+        }
+    }
+
+    /// Returns the mnemonic of an instruction as written in the disassembly.
+    ///
+    /// Capstone names RISC-V instructions after their encoding, so that e.g. a plain jump
+    /// (`j`) and a call (`jal`) share the same name: the printed mnemonic is used instead.
+    pub(crate) fn mnemonic(self, cs: &Capstone, insn: &Insn) -> String {
+        match self {
+            Arch::RiscV64 => insn.mnemonic().unwrap_or_default().to_string(),
+            _ => cs.insn_name(insn.id()).unwrap_or_default(),
+        }
+    }
+
+    /// Returns the canonical name of a register, so that the views of a register (`w0` and
+    /// `x0`, or `al`, `ax`, `eax` and `rax`) are tracked together.
+    pub(crate) fn canonical_register(self, register: &str) -> String {
+        self.register_view(register).register
+    }
+
+    /// Returns the register a register name refers to, and the bits of it that the name
+    /// covers (e.g., `ah` covers bits 8 to 15 of `rax`).
+    pub(crate) fn register_view(self, register: &str) -> RegisterView {
+        let view = |register: String, shift, bits| RegisterView {
+            register,
+            shift,
+            bits,
+        };
+        match self {
+            Arch::AArch64 => match register.strip_prefix('w') {
+                Some(number) if number.parse::<u8>().is_ok() => view(format!("x{number}"), 0, 32),
+                _ => view(register.to_string(), 0, 64),
+            },
+            Arch::X86 | Arch::X86_64 => {
+                x86_register_view(register).unwrap_or_else(|| view(register.to_string(), 0, 64))
+            }
+            Arch::Arm => view(register.to_string(), 0, 32),
+            Arch::RiscV64 => view(register.to_string(), 0, 64),
+        }
+    }
+}
+
+/// A view of a register: the bits `shift..shift + bits` of `register`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RegisterView {
+    /// The canonical name of the register (e.g., `rax` for `eax`).
+    pub(crate) register: String,
+    pub(crate) shift: u32,
+    pub(crate) bits: u32,
+}
+
+impl RegisterView {
+    /// Returns the mask of the bits covered by the view, shifted into place.
+    pub(crate) fn mask(&self) -> u64 {
+        (u64::MAX >> (64 - self.bits)) << self.shift
+    }
+}
+
+/// Returns the view of an x86 general-purpose register, or `None` for the other registers.
+fn x86_register_view(register: &str) -> Option<RegisterView> {
+    let view = |register: String, shift, bits| {
+        Some(RegisterView {
+            register,
+            shift,
+            bits,
+        })
+    };
+    // `r8` to `r15`, with the `d`, `w` and `b` suffixes of their 32, 16 and 8-bit views.
+    if let Some(rest) = register.strip_prefix('r') {
+        let digits = rest.trim_end_matches(['d', 'w', 'b']);
+        if let Ok(number @ 8..=15) = digits.parse::<u8>() {
+            let bits = match &rest[digits.len()..] {
+                "" => 64,
+                "d" => 32,
+                "w" => 16,
+                "b" => 8,
+                _ => return None,
+            };
+            return view(format!("r{number}"), 0, bits);
+        }
+    }
+    for name in ["a", "b", "c", "d"] {
+        let (shift, bits) = match register {
+            _ if register == format!("r{name}x") => (0, 64),
+            _ if register == format!("e{name}x") => (0, 32),
+            _ if register == format!("{name}x") => (0, 16),
+            _ if register == format!("{name}l") => (0, 8),
+            _ if register == format!("{name}h") => (8, 8),
+            _ => continue,
+        };
+        return view(format!("r{name}x"), shift, bits);
+    }
+    for name in ["si", "di", "bp", "sp"] {
+        let bits = match register {
+            _ if register == format!("r{name}") => 64,
+            _ if register == format!("e{name}") => 32,
+            _ if register == name => 16,
+            _ if register == format!("{name}l") => 8,
+            _ => continue,
+        };
+        return view(format!("r{name}"), 0, bits);
+    }
+    None
+}
+
+/// Constants loaded into the registers of a function, followed while it is disassembled.
+///
+/// On RISC-style architectures addresses are built with several instructions (e.g., `adrp` +
+/// `add`, `movw` + `movt`, `auipc` + `addi`) or loaded from memory (GOT slots, literal pools).
+/// Following these instructions allows the disassembly to be annotated with the resulting
+/// values, which are then used to locate the main function. On x86 addresses are encoded in
+/// the instructions themselves and nothing is tracked.
+#[derive(Debug)]
+pub(crate) struct RegisterValues {
+    arch: Arch,
+    thumb: bool,
+    values: HashMap<String, u64>,
+}
+
+impl RegisterValues {
+    /// Creates an empty register state for a function of the given architecture and mode.
+    pub(crate) fn new(arch: Arch, thumb: bool) -> Self {
+        Self {
+            arch,
+            thumb,
+            values: HashMap::new(),
+        }
+    }
+
+    /// Returns the target of a call, or `None` when it is held in a register whose value is
+    /// unknown. Must be called before [`RegisterValues::update`], which forgets every value at
+    /// a call.
+    pub(crate) fn call_target(&self, insn: &Instruction) -> Option<u64> {
+        if let Some(target) = insn.target() {
+            return Some(target);
+        }
+        if self.arch != Arch::RiscV64 {
+            return None;
+        }
+        let register = |op: &str| self.register(op);
+        match insn.operands.as_slice() {
+            [Operand::Register(base)] => register(base),
+            [_, Operand::Register(base), Operand::Immediate(offset)] => {
+                Some(register(base)?.wrapping_add(offset.cast_unsigned()))
+            }
+            [_, Operand::Memory(memory)] => memory.address(register),
+            _ => None,
+        }
+    }
+
+    /// Updates the known registers with the effect of an instruction and returns the value
+    /// written to its destination register, if known.
+    pub(crate) fn update(&mut self, pointers: &Pointers, insn: &Instruction) -> Option<u64> {
+        let arch = self.arch;
+        if matches!(arch, Arch::X86 | Arch::X86_64) {
+            return None;
+        }
+        if insn.is_call() {
+            // Argument and temporary registers do not survive a call.
+            self.values.clear();
+            return None;
+        }
+        if insn.is_syscall(arch) {
+            // The kernel overwrites the register with the result of the system call.
+            self.values
+                .remove(&arch.canonical_register(arch.syscall_return_register()));
+            return None;
+        }
+
+        let destination = arch.canonical_register(insn.destination.as_deref()?);
+        let mnemonic = strip_compressed(&insn.mnemonic);
+        let operands = insn.operands.as_slice();
+        if arch == Arch::RiscV64 {
+            let value = self.update_riscv(pointers, insn.address, mnemonic, operands);
+            return self.set(destination, value);
+        }
+
+        // Reading `pc` yields the address of the instruction plus 8 in ARM mode and plus 4 in
+        // Thumb mode.
+        let pc = insn.address + if self.thumb { 4 } else { 8 };
+        let register = |op: &str| match op {
+            "pc" => Some(pc),
+            _ => self.register(op),
+        };
+        let operand = |index: usize| match operands.get(index)? {
+            Operand::Immediate(value) => Some(value.cast_unsigned()),
+            Operand::Register(op) => register(op),
+            Operand::Memory(_) => None,
+        };
+
+        let value = match (arch, mnemonic) {
+            (Arch::AArch64, "adr" | "adrp") => insn.immediate().map(i64::cast_unsigned),
+            (Arch::Arm, "adr") => operand(1).map(|offset| (pc & !3).wrapping_add(offset)),
+            (_, "mov") => operand(1),
+            // The immediate is shifted into place: the bits it replaces are guessed from its
+            // lowest set bit.
+            (Arch::AArch64, "movk") => {
+                let chunk = insn
+                    .immediate()
+                    .filter(|&chunk| chunk != 0)?
+                    .cast_unsigned();
+                let shift = chunk.trailing_zeros() / 16 * 16;
+                self.values
+                    .get(&destination)
+                    .map(|old| (old & !(0xffff << shift)) | chunk)
+            }
+            (Arch::Arm, "movt") => self
+                .values
+                .get(&destination)
+                .zip(operand(1))
+                .map(|(old, high)| (old & 0xffff) | (high << 16)),
+            // Two-operand form (`add r3, pc`) adds to the destination itself.
+            (_, "add") if operands.len() == 2 => register(&destination)
+                .zip(operand(1))
+                .map(|(base, offset)| base.wrapping_add(offset) & self.mask()),
+            (_, "add") => operand(1)
+                .zip(operand(2))
+                .map(|(base, offset)| base.wrapping_add(offset) & self.mask()),
+            (_, "ldr") => {
+                // Literal pools are addressed from `pc` rounded down to a word boundary.
+                let base = |op: &str| match op {
+                    "pc" => Some(pc & !3),
+                    _ => register(op),
+                };
+                insn.memory()
+                    .and_then(|memory| memory.address(base))
+                    .and_then(|addr| pointers.read(addr))
+            }
+            _ => None,
+        };
+
+        self.set(destination, value)
+    }
+
+    fn update_riscv(
+        &self,
+        pointers: &Pointers,
+        address: u64,
+        mnemonic: &str,
+        operands: &[Operand],
+    ) -> Option<u64> {
+        let register = |op: &str| self.register(op);
+        let operand = |op: &Operand| match op {
+            Operand::Immediate(value) => Some(value.cast_unsigned()),
+            Operand::Register(op) => register(op),
+            Operand::Memory(_) => None,
+        };
+
+        match (mnemonic, operands) {
+            ("auipc", [_, Operand::Immediate(upper)]) => {
+                Some(address.wrapping_add(upper_immediate(*upper)))
+            }
+            ("lui", [_, Operand::Immediate(upper)]) => Some(upper_immediate(*upper)),
+            ("li" | "mv", [_, source]) => operand(source),
+            // Two-operand form (`c.addi sp, -0x10`) adds to the destination itself.
+            ("addi" | "addiw" | "add", [destination, source]) => {
+                Some(operand(destination)?.wrapping_add(operand(source)?))
+            }
+            ("addi" | "addiw" | "add", [_, base, offset]) => {
+                Some(operand(base)?.wrapping_add(operand(offset)?))
+            }
+            ("ld" | "lw" | "lwu", [_, Operand::Memory(memory)]) => memory
+                .address(register)
+                .and_then(|addr| pointers.read(addr)),
+            _ => None,
+        }
+    }
+
+    fn register(&self, register: &str) -> Option<u64> {
+        match register {
+            "zero" if self.arch == Arch::RiscV64 => Some(0),
+            _ => self
+                .values
+                .get(&self.arch.canonical_register(register))
+                .copied(),
+        }
+    }
+
+    fn set(&mut self, destination: String, value: Option<u64>) -> Option<u64> {
+        match value {
+            Some(value) => self.values.insert(destination, value),
+            None => self.values.remove(&destination),
+        };
+        value
+    }
+
+    fn mask(&self) -> u64 {
+        match self.arch {
+            Arch::Arm => u64::from(u32::MAX),
+            _ => u64::MAX,
+        }
+    }
+}
+
+/// Returns the value of the 20-bit upper immediate of `auipc` and `lui`, shifted and
+/// sign-extended from bit 31.
+fn upper_immediate(upper: i64) -> u64 {
+    let value = (upper.cast_unsigned() & 0xf_ffff) << 12;
+    if value & 0x8000_0000 == 0 {
+        value
+    } else {
+        value | 0xffff_ffff_0000_0000
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::disassemble;
+
+    fn instructions(arch: Arch, thumb: bool, code: &[u8], address: u64) -> Vec<Instruction> {
+        disassemble(&arch.capstone(thumb).unwrap(), arch, code, address).unwrap()
+    }
+
+    #[test]
+    fn test_aarch64_address_tracking() {
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = Elf::lazy_parse(header).unwrap();
+        let pointers = Pointers::new(&elf, &[]);
+        let mut values = RegisterValues::new(Arch::AArch64, false);
+        let code = [
+            0xe0, 0x00, 0x00, 0xf0, // adrp x0, #0x20000
+            0x00, 0xa0, 0x0d, 0x91, // add x0, x0, #0x368
+            0xc8, 0x0b, 0x80, 0x52, // mov w8, #0x5e
+            0xe1, 0x03, 0x08, 0xaa, // mov x1, x8
+            0x01, 0x08, 0xa0, 0xf2, // movk x1, #0x40, lsl #16
+            0x8b, 0x3c, 0x00, 0x94, // bl #0x10240
+            0x00, 0x20, 0x00, 0x91, // add x0, x0, #8
+        ];
+        let updates: Vec<_> = instructions(Arch::AArch64, false, &code, 0x1000)
+            .iter()
+            .map(|insn| values.update(&pointers, insn))
+            .collect();
+
+        assert_eq!(
+            updates,
+            vec![
+                Some(0x20000),
+                Some(0x20368),
+                Some(0x5e),
+                Some(0x5e),
+                Some(0x40_005e),
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arm_address_tracking() {
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = Elf::lazy_parse(header).unwrap();
+        let pointers = Pointers::new(&elf, &[]);
+        let mut values = RegisterValues::new(Arch::Arm, true);
+        let code = [
+            0x41, 0xf2, 0x34, 0x23, // movw r3, #0x1234
+            0xc0, 0xf2, 0x02, 0x03, // movt r3, #2
+            0x7b, 0x44, // add r3, pc
+            0x10, 0xb5, // push {r4, lr}
+            0x01, 0x48, // ldr r0, [pc, #4]
+        ];
+        let updates: Vec<_> = instructions(Arch::Arm, true, &code, 0x1000)
+            .iter()
+            .map(|insn| values.update(&pointers, insn))
+            .collect();
+
+        assert_eq!(
+            updates,
+            vec![Some(0x1234), Some(0x2_1234), Some(0x2_2240), None, None]
+        );
+    }
+
+    #[test]
+    fn test_riscv_address_tracking() {
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = Elf::lazy_parse(header).unwrap();
+        let pointers = Pointers::new(&elf, &[]);
+        let mut values = RegisterValues::new(Arch::RiscV64, false);
+        let code = [
+            0x17, 0x15, 0x00, 0x00, // auipc a0, 1
+            0x13, 0x05, 0xe5, 0xfd, // addi a0, a0, -0x22
+            0x93, 0x08, 0xe0, 0x05, // addi a7, zero, 0x5e
+            0xc6, 0x82, // c.mv t0, a7
+            0xb7, 0x05, 0x00, 0x80, // lui a1, 0x80000
+            0x97, 0x00, 0x00, 0x00, // auipc ra, 0
+            0xe7, 0x80, 0x60, 0xfe, // jalr ra, ra, -0x1a
+            0xef, 0xf0, 0x9f, 0xfe, // jal -0x18
+            0x02, 0x95, // c.jalr a0
+            0x21, 0x05, // c.addi a0, 8
+            0x67, 0x00, 0xe3, 0xfe, // jalr zero, t1, -0x12
+            0xf5, 0xbf, // c.j -4
+        ];
+        let insns = instructions(Arch::RiscV64, false, &code, 0x1_1122);
+        let mut update = |insn| values.update(&pointers, insn);
+
+        assert_eq!(update(&insns[0]), Some(0x1_2122));
+        assert_eq!(update(&insns[1]), Some(0x1_2100));
+        assert_eq!(update(&insns[2]), Some(0x5e));
+        assert_eq!(update(&insns[3]), Some(0x5e));
+        assert_eq!(update(&insns[4]), Some(0xffff_ffff_8000_0000));
+        update(&insns[5]);
+
+        assert_eq!(values.call_target(&insns[6]), Some(0x1_111a));
+        assert_eq!(values.call_target(&insns[7]), Some(0x1_1124));
+        assert_eq!(values.call_target(&insns[8]), Some(0x1_2100));
+        assert!(insns[6..9].iter().all(Instruction::is_call));
+        assert!(!insns[10].is_call());
+        assert!(!insns[11].is_call());
+
+        assert_eq!(values.update(&pointers, &insns[8]), None);
+        assert_eq!(values.update(&pointers, &insns[9]), None);
+    }
+}
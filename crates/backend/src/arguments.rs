@@ -0,0 +1,753 @@
+use goblin::elf::Elf;
+
+use crate::{arch::Arch, inspect::read_string, SyscallArgument};
+
+/// How the value of an argument is decoded.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// A number shown as is (e.g., a file descriptor or a length).
+    Integer,
+    /// A directory file descriptor, which may be `AT_FDCWD`.
+    Dirfd,
+    /// The address of a NUL-terminated string (e.g., a path).
+    String,
+    /// File permission bits, shown in octal.
+    Mode,
+    /// The address family of `socket`.
+    Domain,
+    /// The type of `socket` and its `SOCK_NONBLOCK` and `SOCK_CLOEXEC` flags.
+    SocketType,
+    /// The protocol of `socket`, decoded for the Internet address families only.
+    Protocol,
+    OpenFlags,
+    Prot,
+    MapFlags,
+    IoctlRequest,
+    MountFlags,
+    UmountFlags,
+    CloneFlags,
+    PrctlOption,
+    PtraceRequest,
+    SocketLevel,
+}
+
+/// Returns the parameters of a system call or of a C library function, in order.
+///
+/// Only the system calls relevant to the assessment of a binary are described: sockets,
+/// files, program execution, memory protection, devices, mounts, namespaces and privileges.
+/// The C library functions whose parameters differ from the ones of the system call they
+/// perform (e.g., `open`, implemented with `openat`) are described on their own, in
+/// [`wrapper_parameters`] when they are named after the system call.
+fn parameters(function: &str) -> &'static [(&'static str, Kind)] {
+    use Kind::{
+        CloneFlags, Dirfd, Domain, Integer, IoctlRequest, MapFlags, Mode, MountFlags, OpenFlags,
+        PrctlOption, Prot, Protocol, PtraceRequest, SocketLevel, SocketType, UmountFlags,
+    };
+
+    match function {
+        "socket" | "socketpair" => &[
+            ("domain", Domain),
+            ("type", SocketType),
+            ("protocol", Protocol),
+        ],
+        "bind" | "connect" => &[("sockfd", Integer)],
+        "setsockopt" | "getsockopt" => &[
+            ("sockfd", Integer),
+            ("level", SocketLevel),
+            ("optname", Integer),
+        ],
+        "open" | "open64" => &[
+            ("pathname", Kind::String),
+            ("flags", OpenFlags),
+            ("mode", Mode),
+        ],
+        "openat" | "openat64" => &[
+            ("dirfd", Dirfd),
+            ("pathname", Kind::String),
+            ("flags", OpenFlags),
+            ("mode", Mode),
+        ],
+        "creat" | "creat64" | "mkdir" | "chmod" | "mknod" => {
+            &[("pathname", Kind::String), ("mode", Mode)]
+        }
+        "mkdirat" | "fchmodat" | "mknodat" => {
+            &[("dirfd", Dirfd), ("pathname", Kind::String), ("mode", Mode)]
+        }
+        "unlinkat" => &[
+            ("dirfd", Dirfd),
+            ("pathname", Kind::String),
+            ("flags", Integer),
+        ],
+        "fopen" | "fopen64" => &[("pathname", Kind::String), ("mode", Kind::String)],
+        "execve" | "execv" | "execvp" | "execvpe" | "execl" | "execlp" | "execle" | "chroot"
+        | "chdir" | "unlink" | "rmdir" | "truncate" | "truncate64" | "umount" => {
+            &[("pathname", Kind::String)]
+        }
+        "execveat" => &[("dirfd", Dirfd), ("pathname", Kind::String)],
+        "system" => &[("command", Kind::String)],
+        "popen" => &[("command", Kind::String), ("type", Kind::String)],
+        "mprotect" | "pkey_mprotect" => &[("addr", Integer), ("len", Integer), ("prot", Prot)],
+        "mmap" | "mmap2" | "mmap64" => &[
+            ("addr", Integer),
+            ("length", Integer),
+            ("prot", Prot),
+            ("flags", MapFlags),
+            ("fd", Integer),
+            ("offset", Integer),
+        ],
+        "ioctl" => &[("fd", Integer), ("request", IoctlRequest)],
+        "mount" => &[
+            ("source", Kind::String),
+            ("target", Kind::String),
+            ("filesystemtype", Kind::String),
+            ("mountflags", MountFlags),
+        ],
+        "umount2" => &[("target", Kind::String), ("flags", UmountFlags)],
+        "clone" | "unshare" => &[("flags", CloneFlags)],
+        "setns" => &[("fd", Integer), ("nstype", CloneFlags)],
+        "prctl" => &[("option", PrctlOption), ("arg2", Integer)],
+        "ptrace" => &[("request", PtraceRequest), ("pid", Integer)],
+        "setuid" | "setuid32" | "seteuid" => &[("uid", Integer)],
+        "setgid" | "setgid32" | "setegid" => &[("gid", Integer)],
+        _ => &[],
+    }
+}
+
+/// Returns the parameters of the C library functions named after a system call whose
+/// parameters they do not share, in order. The other functions are described by
+/// [`parameters`].
+fn wrapper_parameters(function: &str) -> Option<&'static [(&'static str, Kind)]> {
+    use Kind::{CloneFlags, Integer};
+
+    match function {
+        // The wrapper takes the function run by the child and its stack before the flags.
+        "clone" | "__clone" => Some(&[("fn", Integer), ("stack", Integer), ("flags", CloneFlags)]),
+        _ => None,
+    }
+}
+
+/// Decodes the arguments of a call to a system call or to a C library function.
+///
+/// # Overview
+///
+/// `values` holds the values of the registers carrying the arguments at the call, `None` when
+/// they are unknown (see [`Arch::syscall_argument_registers`] and
+/// [`Arch::call_argument_registers`]). The arguments whose value is known are returned, named
+/// after the parameters of `function`, with a readable form of the value when one exists:
+///
+/// - the names of the constants and flags (e.g., `AF_INET`, `SOCK_RAW`, `O_RDWR|O_CREAT`,
+///   `PROT_READ|PROT_EXEC` or `TIOCSTI`), any unknown bit being shown in hexadecimal;
+/// - the strings stored in the read-only sections of the binary (e.g., the path opened);
+/// - the permission bits, in octal.
+///
+/// Nothing is returned for the functions whose arguments are not relevant to the assessment
+/// of the binary.
+///
+/// # Arguments
+///
+/// - `elf`: A reference to the parsed ELF structure.
+/// - `buffer`: A byte slice containing the binary data.
+/// - `arch`: The architecture of the binary, which selects the values of some flags.
+/// - `function`: The name of the system call or of the C library function.
+/// - `values`: The values of the arguments, in order.
+///
+/// # Returns
+///
+/// - The recovered arguments, in order.
+///
+/// # Example
+///
+/// ```
+/// use goblin::elf::{Elf, Header};
+/// use manifest_producer_backend::{arch::Arch, arguments::decode_arguments};
+///
+/// let elf = Elf::lazy_parse(Header::new(goblin::container::Ctx::default())).unwrap();
+/// let arguments = decode_arguments(&elf, &[], Arch::X86_64, "socket", &[Some(2), Some(3), None]);
+/// assert_eq!(arguments.len(), 2);
+/// assert_eq!(arguments[1].decoded.as_deref(), Some("SOCK_RAW"));
+/// ```
+#[must_use]
+pub fn decode_arguments(
+    elf: &Elf,
+    buffer: &[u8],
+    arch: Arch,
+    function: &str,
+    values: &[Option<u64>],
+) -> Vec<SyscallArgument> {
+    decode_parameters(elf, buffer, arch, parameters(function), values)
+}
+
+/// Decodes the arguments of a call to a C library function performing system calls (see
+/// [`Wrappers`](crate::wrappers::Wrappers)).
+///
+/// # Overview
+///
+/// Works like [`decode_arguments`], except for the wrappers named after a system call whose
+/// parameters differ from the ones of the system call: the C library `clone` takes the
+/// function run by the child first, and its flags third, while the `clone` system call takes
+/// its flags first.
+///
+/// # Arguments
+///
+/// - `elf`: A reference to the parsed ELF structure.
+/// - `buffer`: A byte slice containing the binary data.
+/// - `arch`: The architecture of the binary, which selects the values of some flags.
+/// - `function`: The name of the C library function, without its version.
+/// - `values`: The values of the arguments, in order.
+///
+/// # Returns
+///
+/// - The recovered arguments, in order.
+#[must_use]
+pub fn decode_wrapper_arguments(
+    elf: &Elf,
+    buffer: &[u8],
+    arch: Arch,
+    function: &str,
+    values: &[Option<u64>],
+) -> Vec<SyscallArgument> {
+    let parameters = wrapper_parameters(function).unwrap_or_else(|| parameters(function));
+    decode_parameters(elf, buffer, arch, parameters, values)
+}
+
+fn decode_parameters(
+    elf: &Elf,
+    buffer: &[u8],
+    arch: Arch,
+    parameters: &[(&str, Kind)],
+    values: &[Option<u64>],
+) -> Vec<SyscallArgument> {
+    parameters
+        .iter()
+        .zip(values)
+        .filter_map(|(&(name, kind), &value)| {
+            let value = value?;
+            let decoded = match kind {
+                Kind::String => read_string(elf, buffer, value).map(|string| format!("{string:?}")),
+                Kind::Protocol => protocol(values[0], value),
+                kind => decode(kind, arch, value),
+            };
+            Some(SyscallArgument {
+                name: name.to_string(),
+                value,
+                decoded,
+            })
+        })
+        .collect()
+}
+
+/// Number of bytes of a socket address read by [`decode_socket_address`]: the address family
+/// and the port.
+pub(crate) const SOCKET_ADDRESS_PREFIX: usize = 4;
+
+/// Decodes the Internet socket address passed to `bind` or `connect` from the first bytes of
+/// the local variable holding it (see [`SOCKET_ADDRESS_PREFIX`]).
+///
+/// The address is not held by a register, so that it is described by its port, the `value` of
+/// the returned `addr` argument, and decoded like `strace` does (e.g.,
+/// `{sa_family=AF_INET, sin_port=htons(80)}`). The family is stored in the byte order of the
+/// architecture, little endian on every supported one, and the port in network byte order.
+pub(crate) fn decode_socket_address(
+    function: &str,
+    bytes: Option<&[u8]>,
+) -> Option<SyscallArgument> {
+    if !matches!(function, "bind" | "connect") {
+        return None;
+    }
+    let &[family_low, family_high, port_high, port_low] = bytes? else {
+        return None;
+    };
+    let (family, field) = match u64::from(u16::from_le_bytes([family_low, family_high])) {
+        AF_INET => ("AF_INET", "sin_port"),
+        AF_INET6 => ("AF_INET6", "sin6_port"),
+        _ => return None,
+    };
+    let port = u16::from_be_bytes([port_high, port_low]);
+    Some(SyscallArgument {
+        name: "addr".to_string(),
+        value: u64::from(port),
+        decoded: Some(format!("{{sa_family={family}, {field}=htons({port})}}")),
+    })
+}
+
+/// Decodes the numeric arguments. The flags and constants are 32-bit integers, so that
+/// the upper half of the registers is ignored.
+fn decode(kind: Kind, arch: Arch, value: u64) -> Option<String> {
+    let int = value & 0xffff_ffff;
+    match kind {
+        Kind::Integer | Kind::String | Kind::Protocol => None,
+        Kind::Dirfd => (int == AT_FDCWD).then(|| "AT_FDCWD".to_string()),
+        Kind::Mode => Some(format!("{int:#o}")),
+        Kind::Domain => constant(int, ADDRESS_FAMILIES),
+        Kind::SocketType => {
+            let socket_type = constant(int & 0xf, SOCKET_TYPES)?;
+            Some(with_flags(socket_type, int & !0xf, SOCKET_FLAGS))
+        }
+        Kind::OpenFlags => {
+            let access = constant(int & 0b11, ACCESS_MODES)?;
+            let open_flags = match arch {
+                Arch::Arm | Arch::AArch64 => ARM_OPEN_FLAGS,
+                _ => OPEN_FLAGS,
+            };
+            Some(with_flags(access, int & !0b11, open_flags))
+        }
+        Kind::Prot if int == 0 => Some("PROT_NONE".to_string()),
+        Kind::Prot => Some(flags(int, PROT_FLAGS)),
+        Kind::MapFlags => Some(flags(int, MAP_FLAGS)),
+        Kind::IoctlRequest => constant(int, IOCTL_REQUESTS),
+        Kind::MountFlags => Some(flags(int, MOUNT_FLAGS)),
+        Kind::UmountFlags => Some(flags(int, UMOUNT_FLAGS)),
+        Kind::CloneFlags => Some(flags(int, CLONE_FLAGS)),
+        Kind::PrctlOption => constant(int, PRCTL_OPTIONS),
+        Kind::PtraceRequest => constant(int, PTRACE_REQUESTS),
+        Kind::SocketLevel => constant(int, SOCKET_LEVELS),
+    }
+}
+
+/// Decodes the protocol of `socket`, whose meaning depends on the address family.
+fn protocol(domain: Option<u64>, value: u64) -> Option<String> {
+    match domain? & 0xffff_ffff {
+        AF_INET | AF_INET6 => constant(value & 0xffff_ffff, IP_PROTOCOLS),
+        _ => None,
+    }
+}
+
+/// Returns the name of a constant.
+fn constant(value: u64, names: &[(u64, &str)]) -> Option<String> {
+    names
+        .iter()
+        .find(|&&(constant, _)| constant == value)
+        .map(|(_, name)| (*name).to_string())
+}
+
+/// Returns the names of the flags set in `value`, separated by `|`, followed by the unknown
+/// bits in hexadecimal.
+fn flags(value: u64, names: &[(u64, &str)]) -> String {
+    let mut rest = value;
+    let mut set = Vec::new();
+    for &(flag, name) in names {
+        if rest & flag == flag {
+            set.push(name.to_string());
+            rest &= !flag;
+        }
+    }
+    if rest != 0 || set.is_empty() {
+        set.push(format!("{rest:#x}"));
+    }
+    set.join("|")
+}
+
+/// Returns a constant followed by the names of the flags set in `value`, if any.
+fn with_flags(constant: String, value: u64, names: &[(u64, &str)]) -> String {
+    if value == 0 {
+        constant
+    } else {
+        format!("{constant}|{}", flags(value, names))
+    }
+}
+
+/// `AT_FDCWD` (-100) as a 32-bit integer.
+const AT_FDCWD: u64 = 0xffff_ff9c;
+
+const AF_INET: u64 = 2;
+const AF_INET6: u64 = 10;
+
+const ADDRESS_FAMILIES: &[(u64, &str)] = &[
+    (0, "AF_UNSPEC"),
+    (1, "AF_UNIX"),
+    (AF_INET, "AF_INET"),
+    (3, "AF_AX25"),
+    (4, "AF_IPX"),
+    (5, "AF_APPLETALK"),
+    (AF_INET6, "AF_INET6"),
+    (15, "AF_KEY"),
+    (16, "AF_NETLINK"),
+    (17, "AF_PACKET"),
+    (29, "AF_CAN"),
+    (31, "AF_BLUETOOTH"),
+    (38, "AF_ALG"),
+    (40, "AF_VSOCK"),
+    (44, "AF_XDP"),
+];
+
+const SOCKET_TYPES: &[(u64, &str)] = &[
+    (1, "SOCK_STREAM"),
+    (2, "SOCK_DGRAM"),
+    (3, "SOCK_RAW"),
+    (4, "SOCK_RDM"),
+    (5, "SOCK_SEQPACKET"),
+    (6, "SOCK_DCCP"),
+    (10, "SOCK_PACKET"),
+];
+
+const SOCKET_FLAGS: &[(u64, &str)] = &[(0o4000, "SOCK_NONBLOCK"), (0o2_000_000, "SOCK_CLOEXEC")];
+
+const IP_PROTOCOLS: &[(u64, &str)] = &[
+    (0, "IPPROTO_IP"),
+    (1, "IPPROTO_ICMP"),
+    (2, "IPPROTO_IGMP"),
+    (6, "IPPROTO_TCP"),
+    (17, "IPPROTO_UDP"),
+    (41, "IPPROTO_IPV6"),
+    (58, "IPPROTO_ICMPV6"),
+    (132, "IPPROTO_SCTP"),
+    (136, "IPPROTO_UDPLITE"),
+    (255, "IPPROTO_RAW"),
+];
+
+const SOCKET_LEVELS: &[(u64, &str)] = &[
+    (0, "IPPROTO_IP"),
+    (1, "SOL_SOCKET"),
+    (6, "IPPROTO_TCP"),
+    (17, "IPPROTO_UDP"),
+    (41, "IPPROTO_IPV6"),
+    (255, "SOL_RAW"),
+    (263, "SOL_PACKET"),
+    (270, "SOL_NETLINK"),
+];
+
+const ACCESS_MODES: &[(u64, &str)] = &[(0, "O_RDONLY"), (1, "O_WRONLY"), (2, "O_RDWR")];
+
+/// Flags of `open` on x86 and RISC-V. `O_SYNC` includes `O_DSYNC` and is listed first.
+const OPEN_FLAGS: &[(u64, &str)] = &[
+    (0o4_010_000, "O_SYNC"),
+    (0o100, "O_CREAT"),
+    (0o200, "O_EXCL"),
+    (0o400, "O_NOCTTY"),
+    (0o1000, "O_TRUNC"),
+    (0o2000, "O_APPEND"),
+    (0o4000, "O_NONBLOCK"),
+    (0o10_000, "O_DSYNC"),
+    (0o20_000, "O_ASYNC"),
+    (0o40_000, "O_DIRECT"),
+    (0o100_000, "O_LARGEFILE"),
+    (0o200_000, "O_DIRECTORY"),
+    (0o400_000, "O_NOFOLLOW"),
+    (0o1_000_000, "O_NOATIME"),
+    (0o2_000_000, "O_CLOEXEC"),
+    (0o10_000_000, "O_PATH"),
+    (0o20_000_000, "O_TMPFILE"),
+];
+
+/// Flags of `open` on 32-bit ARM and `AArch64`, where `O_DIRECTORY`, `O_NOFOLLOW`, `O_DIRECT`
+/// and `O_LARGEFILE` take other values.
+const ARM_OPEN_FLAGS: &[(u64, &str)] = &[
+    (0o4_010_000, "O_SYNC"),
+    (0o100, "O_CREAT"),
+    (0o200, "O_EXCL"),
+    (0o400, "O_NOCTTY"),
+    (0o1000, "O_TRUNC"),
+    (0o2000, "O_APPEND"),
+    (0o4000, "O_NONBLOCK"),
+    (0o10_000, "O_DSYNC"),
+    (0o20_000, "O_ASYNC"),
+    (0o40_000, "O_DIRECTORY"),
+    (0o100_000, "O_NOFOLLOW"),
+    (0o200_000, "O_DIRECT"),
+    (0o400_000, "O_LARGEFILE"),
+    (0o1_000_000, "O_NOATIME"),
+    (0o2_000_000, "O_CLOEXEC"),
+    (0o10_000_000, "O_PATH"),
+    (0o20_000_000, "O_TMPFILE"),
+];
+
+const PROT_FLAGS: &[(u64, &str)] = &[
+    (0x1, "PROT_READ"),
+    (0x2, "PROT_WRITE"),
+    (0x4, "PROT_EXEC"),
+    (0x8, "PROT_SEM"),
+    (0x0100_0000, "PROT_GROWSDOWN"),
+    (0x0200_0000, "PROT_GROWSUP"),
+];
+
+/// Flags of `mmap`. `MAP_SHARED_VALIDATE` includes `MAP_SHARED` and `MAP_PRIVATE` and is
+/// listed first.
+const MAP_FLAGS: &[(u64, &str)] = &[
+    (0x3, "MAP_SHARED_VALIDATE"),
+    (0x1, "MAP_SHARED"),
+    (0x2, "MAP_PRIVATE"),
+    (0x10, "MAP_FIXED"),
+    (0x20, "MAP_ANONYMOUS"),
+    (0x100, "MAP_GROWSDOWN"),
+    (0x800, "MAP_DENYWRITE"),
+    (0x1000, "MAP_EXECUTABLE"),
+    (0x2000, "MAP_LOCKED"),
+    (0x4000, "MAP_NORESERVE"),
+    (0x8000, "MAP_POPULATE"),
+    (0x1_0000, "MAP_NONBLOCK"),
+    (0x2_0000, "MAP_STACK"),
+    (0x4_0000, "MAP_HUGETLB"),
+    (0x8_0000, "MAP_SYNC"),
+    (0x10_0000, "MAP_FIXED_NOREPLACE"),
+];
+
+/// Requests of `ioctl` on terminals, sockets and network interfaces, shared by the supported
+/// architectures.
+const IOCTL_REQUESTS: &[(u64, &str)] = &[
+    (0x5401, "TCGETS"),
+    (0x5402, "TCSETS"),
+    (0x5403, "TCSETSW"),
+    (0x5404, "TCSETSF"),
+    (0x540e, "TIOCSCTTY"),
+    (0x540f, "TIOCGPGRP"),
+    (0x5410, "TIOCSPGRP"),
+    (0x5412, "TIOCSTI"),
+    (0x5413, "TIOCGWINSZ"),
+    (0x5414, "TIOCSWINSZ"),
+    (0x541b, "FIONREAD"),
+    (0x541c, "TIOCLINUX"),
+    (0x5421, "FIONBIO"),
+    (0x5422, "TIOCNOTTY"),
+    (0x5450, "FIONCLEX"),
+    (0x5451, "FIOCLEX"),
+    (0x5452, "FIOASYNC"),
+    (0x4b3a, "KDSETMODE"),
+    (0x5606, "VT_ACTIVATE"),
+    (0x890b, "SIOCADDRT"),
+    (0x890c, "SIOCDELRT"),
+    (0x8910, "SIOCGIFNAME"),
+    (0x8912, "SIOCGIFCONF"),
+    (0x8913, "SIOCGIFFLAGS"),
+    (0x8914, "SIOCSIFFLAGS"),
+    (0x8915, "SIOCGIFADDR"),
+    (0x8916, "SIOCSIFADDR"),
+    (0x8927, "SIOCGIFHWADDR"),
+    (0x8933, "SIOCGIFINDEX"),
+    (0x4004_54ca, "TUNSETIFF"),
+];
+
+/// Flags of `mount`. The magic number once required in the upper half is listed first.
+const MOUNT_FLAGS: &[(u64, &str)] = &[
+    (0xc0ed_0000, "MS_MGC_VAL"),
+    (1, "MS_RDONLY"),
+    (1 << 1, "MS_NOSUID"),
+    (1 << 2, "MS_NODEV"),
+    (1 << 3, "MS_NOEXEC"),
+    (1 << 4, "MS_SYNCHRONOUS"),
+    (1 << 5, "MS_REMOUNT"),
+    (1 << 6, "MS_MANDLOCK"),
+    (1 << 7, "MS_DIRSYNC"),
+    (1 << 8, "MS_NOSYMFOLLOW"),
+    (1 << 10, "MS_NOATIME"),
+    (1 << 11, "MS_NODIRATIME"),
+    (1 << 12, "MS_BIND"),
+    (1 << 13, "MS_MOVE"),
+    (1 << 14, "MS_REC"),
+    (1 << 15, "MS_SILENT"),
+    (1 << 16, "MS_POSIXACL"),
+    (1 << 17, "MS_UNBINDABLE"),
+    (1 << 18, "MS_PRIVATE"),
+    (1 << 19, "MS_SLAVE"),
+    (1 << 20, "MS_SHARED"),
+    (1 << 21, "MS_RELATIME"),
+    (1 << 24, "MS_STRICTATIME"),
+    (1 << 25, "MS_LAZYTIME"),
+];
+
+const UMOUNT_FLAGS: &[(u64, &str)] = &[
+    (1, "MNT_FORCE"),
+    (2, "MNT_DETACH"),
+    (4, "MNT_EXPIRE"),
+    (8, "UMOUNT_NOFOLLOW"),
+];
+
+/// Flags of `clone`, `unshare` and `setns`. The lowest byte of the flags of `clone`, the
+/// signal sent to the parent when the child exits, is shown in hexadecimal.
+const CLONE_FLAGS: &[(u64, &str)] = &[
+    (0x100, "CLONE_VM"),
+    (0x200, "CLONE_FS"),
+    (0x400, "CLONE_FILES"),
+    (0x800, "CLONE_SIGHAND"),
+    (0x1000, "CLONE_PIDFD"),
+    (0x2000, "CLONE_PTRACE"),
+    (0x4000, "CLONE_VFORK"),
+    (0x8000, "CLONE_PARENT"),
+    (0x1_0000, "CLONE_THREAD"),
+    (0x2_0000, "CLONE_NEWNS"),
+    (0x4_0000, "CLONE_SYSVSEM"),
+    (0x8_0000, "CLONE_SETTLS"),
+    (0x10_0000, "CLONE_PARENT_SETTID"),
+    (0x20_0000, "CLONE_CHILD_CLEARTID"),
+    (0x40_0000, "CLONE_DETACHED"),
+    (0x80_0000, "CLONE_UNTRACED"),
+    (0x100_0000, "CLONE_CHILD_SETTID"),
+    (0x200_0000, "CLONE_NEWCGROUP"),
+    (0x400_0000, "CLONE_NEWUTS"),
+    (0x800_0000, "CLONE_NEWIPC"),
+    (0x1000_0000, "CLONE_NEWUSER"),
+    (0x2000_0000, "CLONE_NEWPID"),
+    (0x4000_0000, "CLONE_NEWNET"),
+    (0x8000_0000, "CLONE_IO"),
+];
+
+const PRCTL_OPTIONS: &[(u64, &str)] = &[
+    (1, "PR_SET_PDEATHSIG"),
+    (3, "PR_GET_DUMPABLE"),
+    (4, "PR_SET_DUMPABLE"),
+    (8, "PR_SET_KEEPCAPS"),
+    (15, "PR_SET_NAME"),
+    (16, "PR_GET_NAME"),
+    (21, "PR_GET_SECCOMP"),
+    (22, "PR_SET_SECCOMP"),
+    (23, "PR_CAPBSET_READ"),
+    (24, "PR_CAPBSET_DROP"),
+    (28, "PR_SET_SECUREBITS"),
+    (35, "PR_SET_MM"),
+    (36, "PR_SET_CHILD_SUBREAPER"),
+    (38, "PR_SET_NO_NEW_PRIVS"),
+    (39, "PR_GET_NO_NEW_PRIVS"),
+    (47, "PR_CAP_AMBIENT"),
+    (0x5961_6d61, "PR_SET_PTRACER"),
+];
+
+const PTRACE_REQUESTS: &[(u64, &str)] = &[
+    (0, "PTRACE_TRACEME"),
+    (1, "PTRACE_PEEKTEXT"),
+    (2, "PTRACE_PEEKDATA"),
+    (3, "PTRACE_PEEKUSER"),
+    (4, "PTRACE_POKETEXT"),
+    (5, "PTRACE_POKEDATA"),
+    (6, "PTRACE_POKEUSER"),
+    (7, "PTRACE_CONT"),
+    (8, "PTRACE_KILL"),
+    (9, "PTRACE_SINGLESTEP"),
+    (16, "PTRACE_ATTACH"),
+    (17, "PTRACE_DETACH"),
+    (24, "PTRACE_SYSCALL"),
+    (0x4200, "PTRACE_SETOPTIONS"),
+    (0x4201, "PTRACE_GETEVENTMSG"),
+    (0x4206, "PTRACE_SEIZE"),
+    (0x4207, "PTRACE_INTERRUPT"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoded(
+        arch: Arch,
+        function: &str,
+        values: &[Option<u64>],
+    ) -> Vec<(String, Option<String>)> {
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = Elf::lazy_parse(header).unwrap();
+        decode_arguments(&elf, &[], arch, function, values)
+            .into_iter()
+            .map(|argument| (argument.name, argument.decoded))
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_wrapper_arguments() {
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = Elf::lazy_parse(header).unwrap();
+        let values = [Some(0x42_1230), Some(0x7000), Some(0x300)];
+
+        // The C library `clone` takes the function run by the child first.
+        let arguments: Vec<_> = decode_wrapper_arguments(&elf, &[], Arch::X86_64, "clone", &values)
+            .into_iter()
+            .map(|argument| (argument.name, argument.value, argument.decoded))
+            .collect();
+        assert_eq!(
+            arguments,
+            vec![
+                ("fn".to_string(), 0x42_1230, None),
+                ("stack".to_string(), 0x7000, None),
+                (
+                    "flags".to_string(),
+                    0x300,
+                    Some("CLONE_VM|CLONE_FS".to_string())
+                ),
+            ]
+        );
+        // The system call takes its flags first.
+        assert_eq!(
+            decoded(Arch::X86_64, "clone", &values),
+            vec![some(
+                "flags",
+                "CLONE_FS|CLONE_PIDFD|CLONE_NEWNS|CLONE_DETACHED|0x30"
+            )]
+        );
+        // The other wrappers share the parameters of the system calls.
+        assert_eq!(
+            decode_wrapper_arguments(&elf, &[], Arch::X86_64, "unshare", &[Some(0x2_0000)])[0]
+                .decoded
+                .as_deref(),
+            Some("CLONE_NEWNS")
+        );
+    }
+
+    fn some(name: &str, decoded: &str) -> (String, Option<String>) {
+        (name.to_string(), Some(decoded.to_string()))
+    }
+
+    #[test]
+    fn test_decode_arguments() {
+        assert_eq!(
+            decoded(Arch::X86_64, "socket", &[Some(2), Some(0x80003), Some(255)]),
+            vec![
+                some("domain", "AF_INET"),
+                some("type", "SOCK_RAW|SOCK_CLOEXEC"),
+                some("protocol", "IPPROTO_RAW"),
+            ]
+        );
+        assert_eq!(
+            decoded(Arch::X86_64, "socket", &[Some(16), Some(3), Some(0)]),
+            vec![
+                some("domain", "AF_NETLINK"),
+                some("type", "SOCK_RAW"),
+                ("protocol".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            decoded(
+                Arch::X86,
+                "openat",
+                &[Some(0xffff_ff9c), None, Some(0o1_101), Some(0o644)]
+            ),
+            vec![
+                some("dirfd", "AT_FDCWD"),
+                some("flags", "O_WRONLY|O_CREAT|O_TRUNC"),
+                some("mode", "0o644"),
+            ]
+        );
+        assert_eq!(
+            decoded(Arch::X86_64, "open", &[Some(0), Some(0o200_000)]),
+            vec![
+                ("pathname".to_string(), None),
+                some("flags", "O_RDONLY|O_DIRECTORY"),
+            ]
+        );
+        assert_eq!(
+            decoded(Arch::AArch64, "open", &[None, Some(0o200_000)]),
+            vec![some("flags", "O_RDONLY|O_DIRECT")]
+        );
+        assert_eq!(
+            decoded(Arch::X86_64, "mprotect", &[None, None, Some(5)]),
+            vec![some("prot", "PROT_READ|PROT_EXEC")]
+        );
+        assert_eq!(
+            decoded(
+                Arch::X86_64,
+                "mmap",
+                &[Some(0), Some(0x1000), Some(0), Some(0x40_0022)]
+            )[2..],
+            [
+                some("prot", "PROT_NONE"),
+                some("flags", "MAP_PRIVATE|MAP_ANONYMOUS|0x400000"),
+            ]
+        );
+        assert_eq!(
+            decoded(Arch::Arm, "ioctl", &[Some(0), Some(0x5412)])[1],
+            some("request", "TIOCSTI")
+        );
+        assert_eq!(
+            decoded(Arch::RiscV64, "prctl", &[Some(38), Some(1)]),
+            vec![
+                some("option", "PR_SET_NO_NEW_PRIVS"),
+                ("arg2".to_string(), None),
+            ]
+        );
+        assert!(decoded(Arch::X86_64, "write", &[Some(1), Some(0x2000), Some(5)]).is_empty());
+    }
+}
@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+
+use crate::{
+    reachability::SyscallReachability, FunctionId, FunctionNode, SyscallArgument, SyscallSite,
+};
+
+/// Linux capabilities an ELF binary may need, as listed in `capabilities(7)`.
+///
+/// Only the capabilities whose need can be told from the system calls of a binary and their
+/// arguments are inferred; e.g. `CAP_DAC_OVERRIDE` depends on the permissions of the files
+/// accessed at run time and is never reported.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum Capability {
+    #[serde(rename = "CAP_CHOWN")]
+    Chown,
+    #[serde(rename = "CAP_MKNOD")]
+    Mknod,
+    #[serde(rename = "CAP_NET_BIND_SERVICE")]
+    NetBindService,
+    #[serde(rename = "CAP_NET_RAW")]
+    NetRaw,
+    #[serde(rename = "CAP_SETGID")]
+    Setgid,
+    #[serde(rename = "CAP_SETUID")]
+    Setuid,
+    #[serde(rename = "CAP_SYS_ADMIN")]
+    SysAdmin,
+    #[serde(rename = "CAP_SYS_BOOT")]
+    SysBoot,
+    #[serde(rename = "CAP_SYS_CHROOT")]
+    SysChroot,
+    #[serde(rename = "CAP_SYS_MODULE")]
+    SysModule,
+    #[serde(rename = "CAP_SYS_PACCT")]
+    SysPacct,
+    #[serde(rename = "CAP_SYS_PTRACE")]
+    SysPtrace,
+    #[serde(rename = "CAP_SYS_RAWIO")]
+    SysRawio,
+    #[serde(rename = "CAP_SYS_TIME")]
+    SysTime,
+}
+
+impl Capability {
+    /// Returns the name of the capability, as in `capabilities(7)`.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Chown => "CAP_CHOWN",
+            Self::Mknod => "CAP_MKNOD",
+            Self::NetBindService => "CAP_NET_BIND_SERVICE",
+            Self::NetRaw => "CAP_NET_RAW",
+            Self::Setgid => "CAP_SETGID",
+            Self::Setuid => "CAP_SETUID",
+            Self::SysAdmin => "CAP_SYS_ADMIN",
+            Self::SysBoot => "CAP_SYS_BOOT",
+            Self::SysChroot => "CAP_SYS_CHROOT",
+            Self::SysModule => "CAP_SYS_MODULE",
+            Self::SysPacct => "CAP_SYS_PACCT",
+            Self::SysPtrace => "CAP_SYS_PTRACE",
+            Self::SysRawio => "CAP_SYS_RAWIO",
+            Self::SysTime => "CAP_SYS_TIME",
+        }
+    }
+
+    /// Returns what the capability allows the binary to do, shown in the reports.
+    #[must_use]
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Chown => "Change the owner of files",
+            Self::Mknod => "Create device special files",
+            Self::NetBindService => "Bind sockets to privileged ports (below 1024)",
+            Self::NetRaw => "Use raw and packet sockets",
+            Self::Setgid => "Change the group identities of the process",
+            Self::Setuid => "Change the user identities of the process",
+            Self::SysAdmin => {
+                "Mount filesystems, enter or create namespaces and administer the system"
+            }
+            Self::SysBoot => "Reboot the system or load a new kernel",
+            Self::SysChroot => "Change the root directory",
+            Self::SysModule => "Load and unload kernel modules",
+            Self::SysPacct => "Enable or disable process accounting",
+            Self::SysPtrace => "Trace or access the memory of other processes",
+            Self::SysRawio => "Access I/O ports",
+            Self::SysTime => "Set the system clock",
+        }
+    }
+}
+
+/// A capability inferred from a system call reachable from a root function.
+///
+/// # Fields
+///
+/// - `capability`: The capability needed.
+/// - `syscall`: The name of the system call needing it.
+/// - `call`: The system call with its recovered arguments, e.g.
+///   `socket(domain=AF_INET, type=SOCK_RAW)`.
+/// - `address`: The address of the system call instruction, or of the call to the wrapper
+///   performing it.
+/// - `implied_by`: The wrapper performing the system call, if any (see
+///   [`SyscallSite::implied_by`]).
+/// - `path`: The identifiers of the functions of a shortest call path from the root function
+///   to the one issuing the system call (see [`SyscallReachability::witness`]).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CapabilityEvidence {
+    pub capability: Capability,
+    pub syscall: String,
+    pub call: String,
+    pub address: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub implied_by: Option<String>,
+    pub path: Vec<FunctionId>,
+}
+
+/// Linux capabilities needed by the code reachable from every function of the call graph.
+///
+/// # Overview
+///
+/// Every system call site is checked against the operations that `capabilities(7)` reserves
+/// to privileged processes, using the arguments recovered during the detection (see
+/// [`SyscallInfo::arguments`](crate::SyscallInfo::arguments)) when the need depends on them:
+///
+/// - `CAP_NET_RAW` for `socket` with the `SOCK_RAW` type or the `AF_PACKET` family;
+/// - `CAP_NET_BIND_SERVICE` for `bind` to a port below 1024;
+/// - `CAP_SYS_ADMIN` for `mount`, `umount2`, `pivot_root`, `swapon`, `sethostname`, `setns`,
+///   and `unshare` or `clone` creating namespaces other than user namespaces;
+/// - `CAP_SETUID` and `CAP_SETGID` for the system calls changing the identities of the process;
+/// - `CAP_SYS_PTRACE` for `ptrace` attaching to a process and `process_vm_readv`;
+/// - `CAP_MKNOD` for `mknod` creating a character or block device;
+/// - `CAP_CHOWN`, `CAP_SYS_CHROOT`, `CAP_SYS_MODULE`, `CAP_SYS_BOOT`, `CAP_SYS_TIME`,
+///   `CAP_SYS_RAWIO` and `CAP_SYS_PACCT` for the system calls reserved to them.
+///
+/// The system calls whose need depends on an argument that could not be recovered (e.g.,
+/// a port computed at run time) are not reported, so that every capability comes with
+/// evidence. The capabilities are then followed through the call graph like the system
+/// calls are (see [`SyscallReachability::with_keys`]), so that the evidence of a capability
+/// lies at the end of a shortest call path from the root function.
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityReachability {
+    reachability: SyscallReachability,
+}
+
+impl CapabilityReachability {
+    /// Computes the capabilities needed by the code reachable from every function.
+    ///
+    /// # Arguments
+    ///
+    /// - `functions`: The analysed functions, keyed by [`FunctionId`].
+    #[must_use]
+    pub fn new<S: ::std::hash::BuildHasher>(
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+    ) -> Self {
+        let reachability = SyscallReachability::with_keys(functions, |node| {
+            let mut capabilities: Vec<Capability> = node
+                .syscalls
+                .iter()
+                .filter_map(required_capability)
+                .collect();
+            capabilities.sort_unstable();
+            capabilities.dedup();
+            capabilities
+                .into_iter()
+                .map(|capability| capability.name().to_string())
+                .collect()
+        });
+        Self { reachability }
+    }
+
+    /// Returns the evidence of every capability needed by the code reachable from `root`:
+    /// the system call sites needing it in the closest function, with the call path leading
+    /// to that function (see [`SyscallReachability::witness`]).
+    ///
+    /// # Arguments
+    ///
+    /// - `functions`: The analysed functions, keyed by [`FunctionId`].
+    /// - `root`: The identifier of the root function.
+    ///
+    /// # Returns
+    ///
+    /// - The evidence of every capability needed, ordered by capability and then by address.
+    #[must_use]
+    pub fn evidence<S: ::std::hash::BuildHasher>(
+        &self,
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+        root: FunctionId,
+    ) -> Vec<CapabilityEvidence> {
+        let mut evidence = Vec::new();
+        for name in self.reachability.syscalls(root) {
+            let Some(path) = self.reachability.witness(root, name) else {
+                continue;
+            };
+            let Some(node) = path.last().and_then(|id| functions.get(id)) else {
+                continue;
+            };
+            for site in &node.syscalls {
+                if let Some(capability) =
+                    required_capability(site).filter(|capability| capability.name() == name)
+                {
+                    evidence.push(evidence_of(capability, site, path.clone()));
+                }
+            }
+        }
+        evidence.sort_by_key(|evidence| (evidence.capability, evidence.address));
+        evidence
+    }
+}
+
+/// Infers the Linux capabilities needed by the code reachable from a root function.
+///
+/// A shorthand for [`CapabilityReachability::evidence`], which should be preferred when
+/// several root functions are examined.
+///
+/// # Arguments
+///
+/// - `functions`: The analysed functions, keyed by [`FunctionId`].
+/// - `root`: The identifier of the root function.
+///
+/// # Returns
+///
+/// - The evidence of every capability needed, ordered by capability and then by address.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+/// use manifest_producer_backend::{capabilities::infer_capabilities, FunctionNode};
+///
+/// let main = FunctionNode::new("main".to_string(), 0x1000, 0x1100);
+/// let functions = HashMap::from([(0x1000, main)]);
+///
+/// assert!(infer_capabilities(&functions, 0x1000).is_empty());
+/// ```
+#[must_use]
+pub fn infer_capabilities<S: ::std::hash::BuildHasher>(
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    root: FunctionId,
+) -> Vec<CapabilityEvidence> {
+    CapabilityReachability::new(functions).evidence(functions, root)
+}
+
+fn evidence_of(
+    capability: Capability,
+    site: &SyscallSite,
+    path: Vec<FunctionId>,
+) -> CapabilityEvidence {
+    let info = site.info.as_ref();
+    let syscall = info.map(|info| info.name.clone()).unwrap_or_default();
+    let arguments: Vec<String> = info
+        .map(|info| info.arguments.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|argument| match &argument.decoded {
+            Some(decoded) => format!("{}={decoded}", argument.name),
+            None => format!("{}={:#x}", argument.name, argument.value),
+        })
+        .collect();
+    CapabilityEvidence {
+        capability,
+        call: format!("{syscall}({})", arguments.join(", ")),
+        syscall,
+        address: site.address,
+        implied_by: site.implied_by.clone(),
+        path,
+    }
+}
+
+/// Returns the capability a system call site needs, if any.
+fn required_capability(site: &SyscallSite) -> Option<Capability> {
+    let info = site.info.as_ref()?;
+    let argument = |name: &str| argument(&info.arguments, name);
+    let capability = match info.name.as_str() {
+        "socket" => {
+            let raw = argument("type").is_some_and(|kind| kind & SOCK_TYPE_MASK == SOCK_RAW);
+            let packet = argument("domain").is_some_and(|domain| domain & 0xffff_ffff == AF_PACKET);
+            (raw || packet).then_some(Capability::NetRaw)?
+        }
+        "bind" => {
+            let port = argument("addr")?;
+            (1..PRIVILEGED_PORTS)
+                .contains(&port)
+                .then_some(Capability::NetBindService)?
+        }
+        "unshare" | "clone" => {
+            // The arguments of the C library `clone` are named after its own parameters, so
+            // that the function run by the child, passed first, is never taken for the flags.
+            let flags = argument("flags")?;
+            (flags & NAMESPACE_FLAGS != 0).then_some(Capability::SysAdmin)?
+        }
+        "ptrace" => {
+            let request = argument("request")? & 0xffff_ffff;
+            PTRACE_ATTACH_REQUESTS
+                .contains(&request)
+                .then_some(Capability::SysPtrace)?
+        }
+        "mknod" | "mknodat" => {
+            let kind = argument("mode")? & S_IFMT;
+            (kind == S_IFCHR || kind == S_IFBLK).then_some(Capability::Mknod)?
+        }
+        name => CAPABILITY_SYSCALLS
+            .iter()
+            .find(|(_, syscalls)| syscalls.contains(&name))
+            .map(|&(capability, _)| capability)?,
+    };
+    Some(capability)
+}
+
+/// Returns the value of the argument named `name`, if it was recovered.
+fn argument(arguments: &[SyscallArgument], name: &str) -> Option<u64> {
+    arguments
+        .iter()
+        .find(|argument| argument.name == name)
+        .map(|argument| argument.value)
+}
+
+/// Ports below this one can only be bound with `CAP_NET_BIND_SERVICE`.
+const PRIVILEGED_PORTS: u64 = 1024;
+
+const SOCK_RAW: u64 = 3;
+/// Mask of the type of a socket, without the `SOCK_NONBLOCK` and `SOCK_CLOEXEC` flags.
+const SOCK_TYPE_MASK: u64 = 0xf;
+const AF_PACKET: u64 = 17;
+
+/// `CLONE_NEWNS`, `CLONE_NEWCGROUP`, `CLONE_NEWUTS`, `CLONE_NEWIPC`, `CLONE_NEWPID` and
+/// `CLONE_NEWNET`: creating a user namespace (`CLONE_NEWUSER`) is not privileged.
+const NAMESPACE_FLAGS: u64 =
+    0x0002_0000 | 0x0200_0000 | 0x0400_0000 | 0x0800_0000 | 0x2000_0000 | 0x4000_0000;
+
+/// `PTRACE_ATTACH` and `PTRACE_SEIZE`: tracing a child with `PTRACE_TRACEME` is not
+/// privileged.
+const PTRACE_ATTACH_REQUESTS: [u64; 2] = [16, 0x4206];
+
+const S_IFMT: u64 = 0o170_000;
+const S_IFCHR: u64 = 0o020_000;
+const S_IFBLK: u64 = 0o060_000;
+
+/// System calls needing a capability whatever their arguments.
+const CAPABILITY_SYSCALLS: &[(Capability, &[&str])] = &[
+    (
+        Capability::Chown,
+        &[
+            "chown", "chown32", "fchown", "fchown32", "lchown", "lchown32", "fchownat",
+        ],
+    ),
+    (
+        Capability::Setgid,
+        &[
+            "setgid",
+            "setgid32",
+            "setregid",
+            "setregid32",
+            "setresgid",
+            "setresgid32",
+            "setfsgid",
+            "setfsgid32",
+            "setgroups",
+            "setgroups32",
+        ],
+    ),
+    (
+        Capability::Setuid,
+        &[
+            "setuid",
+            "setuid32",
+            "setreuid",
+            "setreuid32",
+            "setresuid",
+            "setresuid32",
+            "setfsuid",
+            "setfsuid32",
+        ],
+    ),
+    (
+        Capability::SysAdmin,
+        &[
+            "mount",
+            "umount",
+            "umount2",
+            "pivot_root",
+            "swapon",
+            "swapoff",
+            "sethostname",
+            "setdomainname",
+            "setns",
+            "quotactl",
+            "fsopen",
+            "fsmount",
+            "move_mount",
+        ],
+    ),
+    (
+        Capability::SysBoot,
+        &["reboot", "kexec_load", "kexec_file_load"],
+    ),
+    (Capability::SysChroot, &["chroot"]),
+    (
+        Capability::SysModule,
+        &["init_module", "finit_module", "delete_module"],
+    ),
+    (Capability::SysPacct, &["acct"]),
+    (
+        Capability::SysPtrace,
+        &["process_vm_readv", "process_vm_writev"],
+    ),
+    (Capability::SysRawio, &["iopl", "ioperm"]),
+    (
+        Capability::SysTime,
+        &[
+            "settimeofday",
+            "clock_settime",
+            "clock_settime64",
+            "adjtimex",
+            "clock_adjtime",
+            "stime",
+        ],
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arch::Arch,
+        arguments::decode_wrapper_arguments,
+        fixtures::{function, syscall_site},
+    };
+
+    fn site(address: u64, name: &str, arguments: &[(&str, u64)]) -> SyscallSite {
+        let mut site = syscall_site(address, name);
+        site.info.as_mut().unwrap().arguments = arguments
+            .iter()
+            .map(|&(name, value)| SyscallArgument {
+                name: name.to_string(),
+                value,
+                decoded: None,
+            })
+            .collect();
+        site
+    }
+
+    #[test]
+    fn test_infer_capabilities() {
+        let mut main = function("main", 0x1000, &[0x1100, 0x1200]);
+        main.add_syscall(site(0x1010, "setuid", &[("uid", 0)]));
+        main.add_syscall(site(0x1020, "socket", &[("domain", 2), ("type", 1)]));
+        let mut server = function("server", 0x1100, &[0x1200]);
+        server.add_syscall(site(0x1110, "bind", &[("addr", 80)]));
+        server.add_syscall(site(0x1120, "bind", &[("addr", 8080)]));
+        server.add_syscall(site(0x1130, "bind", &[]));
+        let mut sandbox = function("sandbox", 0x1200, &[]);
+        sandbox.add_syscall(site(0x1210, "unshare", &[("flags", 0x1000_0000)]));
+        sandbox.add_syscall(site(0x1220, "unshare", &[("flags", 0x1002_0000)]));
+        sandbox.add_syscall(site(0x1230, "socket", &[("domain", 2), ("type", 0x80003)]));
+        let mut unused = function("unused", 0x1300, &[]);
+        unused.add_syscall(site(0x1310, "mount", &[]));
+        let functions: HashMap<FunctionId, FunctionNode> = [main, server, sandbox, unused]
+            .into_iter()
+            .map(|node| (node.start_addr, node))
+            .collect();
+
+        let reachability = CapabilityReachability::new(&functions);
+        let evidence = reachability.evidence(&functions, 0x1000);
+        let found: Vec<(Capability, u64, Vec<FunctionId>)> = evidence
+            .iter()
+            .map(|evidence| (evidence.capability, evidence.address, evidence.path.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Capability::NetBindService, 0x1110, vec![0x1000, 0x1100]),
+                (Capability::NetRaw, 0x1230, vec![0x1000, 0x1200]),
+                (Capability::Setuid, 0x1010, vec![0x1000]),
+                (Capability::SysAdmin, 0x1220, vec![0x1000, 0x1200]),
+            ]
+        );
+        assert_eq!(evidence[0].call, "bind(addr=0x50)");
+        assert_eq!(evidence, infer_capabilities(&functions, 0x1000));
+        assert_eq!(
+            reachability.evidence(&functions, 0x1300)[0].path,
+            vec![0x1300]
+        );
+        assert_eq!(
+            serde_json::to_value(Capability::NetBindService).unwrap(),
+            Capability::NetBindService.name()
+        );
+    }
+
+    #[test]
+    fn test_infer_capabilities_from_arguments() {
+        let mut main = function("main", 0x1000, &[0x1100]);
+        // Tracing itself, creating a FIFO and a user namespace need no capability.
+        main.add_syscall(site(0x1010, "ptrace", &[("request", 0)]));
+        main.add_syscall(site(0x1020, "mknod", &[("mode", 0o010_644)]));
+        main.add_syscall(site(0x1030, "clone", &[("flags", 0x1000_0000)]));
+        main.add_syscall(site(0x1040, "clone", &[]));
+        let mut debugger = function("debugger", 0x1100, &[]);
+        debugger.add_syscall(site(0x1110, "ptrace", &[("request", 16)]));
+        debugger.add_syscall(site(0x1120, "ptrace", &[("request", 0x4206)]));
+        debugger.add_syscall(site(0x1130, "mknod", &[("mode", 0o020_644)]));
+        debugger.add_syscall(site(0x1140, "mknodat", &[("mode", 0o060_600)]));
+        debugger.add_syscall(site(0x1150, "clone", &[("flags", 0x1002_0000)]));
+        let functions: HashMap<FunctionId, FunctionNode> = [main, debugger]
+            .into_iter()
+            .map(|node| (node.start_addr, node))
+            .collect();
+
+        let found: Vec<(Capability, u64)> = infer_capabilities(&functions, 0x1000)
+            .iter()
+            .map(|evidence| (evidence.capability, evidence.address))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Capability::Mknod, 0x1130),
+                (Capability::Mknod, 0x1140),
+                (Capability::SysAdmin, 0x1150),
+                (Capability::SysPtrace, 0x1110),
+                (Capability::SysPtrace, 0x1120),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_infer_capabilities_from_clone_wrapper() {
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = goblin::elf::Elf::lazy_parse(header).unwrap();
+        // `clone(fn, stack, flags)` called with a function whose address has the bit of
+        // `CLONE_NEWNS` set.
+        let wrapper_site = |address: u64, flags: u64| {
+            let arguments = decode_wrapper_arguments(
+                &elf,
+                &[],
+                Arch::X86_64,
+                "clone",
+                &[Some(0x42_1230), Some(0x7000), Some(flags)],
+            );
+            let mut site = site(address, "clone", &[]);
+            site.info.as_mut().unwrap().arguments = arguments;
+            site.implied_by = Some("clone@GLIBC_2.2.5".to_string());
+            site
+        };
+        let mut main = function("main", 0x1000, &[]);
+        main.add_syscall(wrapper_site(0x1010, 0x300));
+        let functions = HashMap::from([(0x1000, main)]);
+        assert!(infer_capabilities(&functions, 0x1000).is_empty());
+
+        let mut main = function("main", 0x1000, &[]);
+        main.add_syscall(wrapper_site(0x1020, 0x2_0100));
+        let functions = HashMap::from([(0x1000, main)]);
+        let evidence = infer_capabilities(&functions, 0x1000);
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].capability, Capability::SysAdmin);
+        assert_eq!(
+            evidence[0].call,
+            "clone(fn=0x421230, stack=0x7000, flags=CLONE_VM|CLONE_NEWNS)"
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    error::Result, reachability::SyscallReachability, FunctionId, FunctionNode, SyscallInfo,
+};
+
+/// Behaviour categories of the system calls.
+///
+/// A system call may fall in several categories (e.g., `mount` is both a filesystem and a
+/// privileged operation), and a few ones in none (e.g., `uname` or `getrandom`). The reads
+/// and writes on file descriptors are classified as filesystem operations, as they cannot be
+/// told apart from socket or pipe operations.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyscallCategory {
+    Network,
+    Filesystem,
+    Process,
+    Ipc,
+    Memory,
+    Time,
+    Privileges,
+    Device,
+}
+
+impl SyscallCategory {
+    /// Every category, in display order.
+    pub const ALL: [Self; 8] = [
+        Self::Network,
+        Self::Filesystem,
+        Self::Process,
+        Self::Ipc,
+        Self::Memory,
+        Self::Time,
+        Self::Privileges,
+        Self::Device,
+    ];
+
+    /// Returns the name of the category shown in the reports.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Network => "Network",
+            Self::Filesystem => "Filesystem",
+            Self::Process => "Process/exec",
+            Self::Ipc => "IPC",
+            Self::Memory => "Memory/protection",
+            Self::Time => "Time",
+            Self::Privileges => "Privileges/credentials",
+            Self::Device => "Device/ioctl",
+        }
+    }
+}
+
+/// Knowledge base mapping the names of the system calls to their [`SyscallCategory`].
+///
+/// # Overview
+///
+/// The bundled knowledge base (see [`SyscallCategories::bundled`]) is a JSON object listing
+/// the names of the system calls of each category, shared by every architecture. It is used
+/// to annotate the syscall tables (see [`SyscallTable`](crate::syscall::SyscallTable)), unless
+/// their entries declare their own categories.
+#[derive(Debug, Clone, Default)]
+pub struct SyscallCategories {
+    categories: HashMap<String, Vec<SyscallCategory>>,
+}
+
+impl SyscallCategories {
+    /// Returns the knowledge base embedded in the crate.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::Json`](crate::error::Error::Json) if the embedded knowledge base is
+    ///   malformed.
+    pub fn bundled() -> Result<Self> {
+        Self::from_json(include_str!("../data/categories.json"))
+    }
+
+    /// Parses a knowledge base from a JSON object mapping categories to system call names.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::Json`](crate::error::Error::Json) if `data` is malformed.
+    pub fn from_json(data: &str) -> Result<Self> {
+        let syscalls: BTreeMap<SyscallCategory, Vec<String>> = serde_json::from_str(data)?;
+        let mut categories: HashMap<String, Vec<SyscallCategory>> = HashMap::new();
+        for (category, names) in syscalls {
+            for name in names {
+                categories.entry(name).or_default().push(category);
+            }
+        }
+        Ok(Self { categories })
+    }
+
+    /// Returns the categories of the system call named `name`, in display order.
+    #[must_use]
+    pub fn get(&self, name: &str) -> &[SyscallCategory] {
+        self.categories.get(name).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// System calls of a function, or reachable from it, grouped by [`SyscallCategory`].
+///
+/// # Overview
+///
+/// A profile aggregates the categories of the system calls of a function, either the ones it
+/// issues itself (see [`CategoryProfile::of_function`]) or the ones reachable through the
+/// functions it calls (see [`CategoryProfile::reachable`]), the latter describing what a root
+/// function such as `main` can ultimately do. The profile is then summarised in a sentence
+/// (see [`CategoryProfile::summary`]), e.g. "performs network server operations and spawns
+/// processes or threads".
+///
+/// # Fields
+///
+/// - `syscalls`: The names of the system calls of every category found, in display order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CategoryProfile {
+    pub syscalls: BTreeMap<SyscallCategory, BTreeSet<String>>,
+}
+
+impl CategoryProfile {
+    /// Returns the profile of the system calls issued by a function, including the ones
+    /// implied by the wrappers it calls.
+    #[must_use]
+    pub fn of_function(func: &FunctionNode) -> Self {
+        let mut profile = Self::default();
+        for info in func.syscalls.iter().filter_map(|site| site.info.as_ref()) {
+            profile.add(info);
+        }
+        profile
+    }
+
+    /// Returns the profile of the system calls reachable from a function.
+    ///
+    /// # Arguments
+    ///
+    /// - `reachability`: The system calls reachable from every function (see
+    ///   [`SyscallReachability::new`]).
+    /// - `functions`: The analysed functions, keyed by [`FunctionId`].
+    /// - `id`: The function whose profile is returned.
+    #[must_use]
+    pub fn reachable<S: ::std::hash::BuildHasher>(
+        reachability: &SyscallReachability,
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+        id: FunctionId,
+    ) -> Self {
+        let mut profile = Self::default();
+        for witness in reachability.witnesses(id) {
+            let Some(issuer) = witness.path.last().and_then(|id| functions.get(id)) else {
+                continue;
+            };
+            for info in issuer.syscalls.iter().filter_map(|site| site.info.as_ref()) {
+                if info.name == witness.syscall {
+                    profile.add(info);
+                }
+            }
+        }
+        profile
+    }
+
+    fn add(&mut self, info: &SyscallInfo) {
+        for &category in &info.categories {
+            self.syscalls
+                .entry(category)
+                .or_default()
+                .insert(info.name.clone());
+        }
+    }
+
+    /// Returns the categories found, in display order.
+    pub fn categories(&self) -> impl Iterator<Item = SyscallCategory> + '_ {
+        self.syscalls.keys().copied()
+    }
+
+    /// Whether no categorised system call was found.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.syscalls.is_empty()
+    }
+
+    /// Returns the notable behaviours of the profile, in display order.
+    ///
+    /// Only the system calls telling something about the purpose of a program are
+    /// considered: the ones every program performs (e.g., `mmap`, `futex` or `exit_group`)
+    /// add no behaviour.
+    #[must_use]
+    pub fn behaviours(&self) -> Vec<&'static str> {
+        let any = |category: SyscallCategory, names: &[&str]| {
+            self.syscalls
+                .get(&category)
+                .is_some_and(|syscalls| names.iter().any(|name| syscalls.contains(*name)))
+        };
+        let mut behaviours = Vec::new();
+
+        let server = any(
+            SyscallCategory::Network,
+            &["bind", "listen", "accept", "accept4"],
+        );
+        let client = any(SyscallCategory::Network, &["connect"]);
+        match (server, client) {
+            (true, true) => behaviours.push("performs network server and client operations"),
+            (true, false) => behaviours.push("performs network server operations"),
+            (false, true) => behaviours.push("performs network client operations"),
+            (false, false) if self.syscalls.contains_key(&SyscallCategory::Network) => {
+                behaviours.push("uses network sockets");
+            }
+            (false, false) => {}
+        }
+
+        let filesystem = SyscallCategory::Filesystem;
+        if any(filesystem, MODIFYING_SYSCALLS) {
+            behaviours.push("modifies the filesystem");
+        } else if any(filesystem, &["open", "openat", "openat2", "creat"]) {
+            behaviours.push("opens files");
+        }
+        if any(filesystem, MOUNTING_SYSCALLS) {
+            behaviours.push("changes mounts or its root directory");
+        }
+
+        let process = SyscallCategory::Process;
+        if any(process, &["fork", "vfork", "clone", "clone3"]) {
+            behaviours.push("spawns processes or threads");
+        }
+        if any(process, &["execve", "execveat"]) {
+            behaviours.push("executes programs");
+        }
+        if any(
+            process,
+            &[
+                "ptrace",
+                "process_vm_readv",
+                "process_vm_writev",
+                "pidfd_getfd",
+            ],
+        ) {
+            behaviours.push("inspects other processes");
+        }
+
+        if any(SyscallCategory::Ipc, &["kill", "pidfd_send_signal"]) {
+            behaviours.push("signals other processes");
+        }
+        if any(SyscallCategory::Ipc, COMMUNICATING_SYSCALLS) {
+            behaviours.push("communicates with other processes");
+        }
+        if any(SyscallCategory::Memory, &["mprotect", "pkey_mprotect"]) {
+            behaviours.push("changes memory protections");
+        }
+        if any(
+            SyscallCategory::Time,
+            &[
+                "settimeofday",
+                "clock_settime",
+                "clock_adjtime",
+                "adjtimex",
+                "stime",
+            ],
+        ) {
+            behaviours.push("changes the system clock");
+        }
+
+        let privileges = SyscallCategory::Privileges;
+        if any(privileges, CREDENTIAL_SYSCALLS) {
+            behaviours.push("changes its credentials");
+        }
+        if any(privileges, &["unshare", "setns"]) {
+            behaviours.push("enters or creates namespaces");
+        }
+        if any(
+            privileges,
+            &[
+                "seccomp",
+                "landlock_create_ruleset",
+                "landlock_restrict_self",
+            ],
+        ) {
+            behaviours.push("restricts its own system calls");
+        }
+        if any(privileges, ADMINISTRATION_SYSCALLS) {
+            behaviours.push("performs system administration operations");
+        }
+        if self.syscalls.contains_key(&SyscallCategory::Device) {
+            behaviours.push("controls devices");
+        }
+
+        behaviours
+    }
+
+    /// Returns the notable behaviours of the profile joined in a sentence fragment, e.g.
+    /// "performs network server operations and spawns processes or threads", or `None` if
+    /// there is none.
+    #[must_use]
+    pub fn summary(&self) -> Option<String> {
+        let behaviours = self.behaviours();
+        match behaviours.as_slice() {
+            [] => None,
+            [behaviour] => Some((*behaviour).to_string()),
+            [first @ .., last] => Some(format!("{} and {last}", first.join(", "))),
+        }
+    }
+}
+
+/// System calls creating, removing or changing files and directories.
+const MODIFYING_SYSCALLS: &[&str] = &[
+    "mkdir",
+    "mkdirat",
+    "rmdir",
+    "unlink",
+    "unlinkat",
+    "rename",
+    "renameat",
+    "renameat2",
+    "link",
+    "linkat",
+    "symlink",
+    "symlinkat",
+    "mknod",
+    "mknodat",
+    "chmod",
+    "fchmod",
+    "fchmodat",
+    "fchmodat2",
+    "chown",
+    "chown32",
+    "lchown",
+    "lchown32",
+    "fchown",
+    "fchown32",
+    "fchownat",
+    "truncate",
+    "truncate64",
+    "ftruncate",
+    "ftruncate64",
+];
+
+const MOUNTING_SYSCALLS: &[&str] = &[
+    "mount",
+    "umount",
+    "umount2",
+    "move_mount",
+    "fsmount",
+    "pivot_root",
+    "chroot",
+];
+
+/// Inter-process communication channels, as opposed to the synchronisation and polling
+/// primitives every threaded program uses.
+const COMMUNICATING_SYSCALLS: &[&str] = &[
+    "pipe",
+    "pipe2",
+    "ipc",
+    "msgget",
+    "msgsnd",
+    "msgrcv",
+    "semget",
+    "semop",
+    "semtimedop",
+    "shmget",
+    "shmat",
+    "mq_open",
+    "mq_timedsend",
+    "mq_timedreceive",
+    "memfd_create",
+];
+
+const CREDENTIAL_SYSCALLS: &[&str] = &[
+    "setuid",
+    "setuid32",
+    "setgid",
+    "setgid32",
+    "setreuid",
+    "setreuid32",
+    "setregid",
+    "setregid32",
+    "setresuid",
+    "setresuid32",
+    "setresgid",
+    "setresgid32",
+    "setfsuid",
+    "setfsuid32",
+    "setfsgid",
+    "setfsgid32",
+    "setgroups",
+    "setgroups32",
+    "capset",
+];
+
+const ADMINISTRATION_SYSCALLS: &[&str] = &[
+    "reboot",
+    "kexec_load",
+    "kexec_file_load",
+    "init_module",
+    "finit_module",
+    "delete_module",
+    "create_module",
+    "sethostname",
+    "setdomainname",
+    "swapon",
+    "swapoff",
+    "acct",
+    "quotactl",
+    "quotactl_fd",
+    "iopl",
+    "ioperm",
+    "bpf",
+    "syslog",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{function, with_syscalls};
+
+    #[test]
+    fn test_syscall_categories() {
+        let categories = SyscallCategories::bundled().unwrap();
+        assert_eq!(categories.get("accept4"), [SyscallCategory::Network]);
+        assert_eq!(
+            categories.get("mount"),
+            [SyscallCategory::Filesystem, SyscallCategory::Privileges]
+        );
+        assert!(categories.get("uname").is_empty());
+
+        // Every category is listed in the bundled knowledge base.
+        let data: BTreeMap<SyscallCategory, Vec<String>> =
+            serde_json::from_str(include_str!("../data/categories.json")).unwrap();
+        assert!(SyscallCategory::ALL
+            .iter()
+            .all(|category| data.contains_key(category)));
+    }
+
+    #[test]
+    fn test_category_profiles() {
+        let functions: HashMap<FunctionId, FunctionNode> = [
+            with_syscalls(function("main", 0x1000, &[0x1100, 0x1200]), &["exit_group"]),
+            with_syscalls(
+                function("serve", 0x1100, &[]),
+                &["socket", "bind", "listen", "accept4"],
+            ),
+            with_syscalls(function("spawn", 0x1200, &[]), &["clone", "execve", "mmap"]),
+        ]
+        .into_iter()
+        .map(|node| (node.start_addr, node))
+        .collect();
+        let reachability = SyscallReachability::new(&functions);
+
+        let main = CategoryProfile::of_function(&functions[&0x1000]);
+        assert_eq!(
+            main.categories().collect::<Vec<_>>(),
+            vec![SyscallCategory::Process]
+        );
+        assert_eq!(main.summary(), None);
+
+        let root = CategoryProfile::reachable(&reachability, &functions, 0x1000);
+        assert_eq!(
+            root.categories().collect::<Vec<_>>(),
+            vec![
+                SyscallCategory::Network,
+                SyscallCategory::Process,
+                SyscallCategory::Memory
+            ]
+        );
+        assert_eq!(
+            root.syscalls[&SyscallCategory::Process],
+            BTreeSet::from([
+                "clone".to_string(),
+                "execve".to_string(),
+                "exit_group".to_string()
+            ])
+        );
+        assert_eq!(
+            root.summary().as_deref(),
+            Some(
+                "performs network server operations, spawns processes or threads and executes programs"
+            )
+        );
+
+        // Signalling through a pidfd is both process management and IPC.
+        let notify = CategoryProfile::of_function(&with_syscalls(
+            function("notify", 0x1300, &[]),
+            &["pidfd_open", "pidfd_send_signal"],
+        ));
+        assert_eq!(
+            notify.syscalls[&SyscallCategory::Ipc],
+            BTreeSet::from(["pidfd_send_signal".to_string()])
+        );
+        assert_eq!(notify.summary().as_deref(), Some("signals other processes"));
+    }
+}
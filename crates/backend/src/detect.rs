@@ -1,6 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap, HashSet};
 
-use crate::{error::Result, FunctionNode};
+use crate::{
+    arch::Arch, discover::discover_functions, error::Result, inspect::is_legacy_rust_symbol,
+    FunctionId, FunctionNode,
+};
 use cpp_demangle::{DemangleOptions, Symbol};
 use goblin::{self, elf::Elf};
 use rustc_demangle::demangle;
@@ -11,28 +14,43 @@ use rustc_demangle::demangle;
 ///
 /// This function scans the ELF symbol table, identifies functions, and processes them into
 /// [`FunctionNode`] structures with details like start and end addresses. Function names are demangled if necessary.
+/// When the symbol table contains no function (stripped binaries), the functions are recovered
+/// with [`discover_functions`](crate::discover::discover_functions) instead. On ARM, the Thumb
+/// bit of the symbol addresses is cleared and recorded in [`FunctionNode::thumb`].
+///
+/// Functions are keyed by their start address (see [`FunctionId`]): functions sharing a name
+/// are kept apart, while aliases of the same function (e.g., `write` and `__write`) are merged
+/// into a single node named after the first global or weak symbol of `.symtab`.
 ///
 /// # Arguments
 ///
 /// - `elf`: A reference to an [`Elf`] structure containing the binary's symbol table.
+/// - `buffer`: Byte buffer of the ELF binary, disassembled when the symbol table is missing.
 /// - `language`: The programming language for function name demangling.
 ///
 /// # Returns
 ///
-/// - A `Result` containing a `HashMap<String, FunctionNode>` with function names as keys.
+/// - A `Result` containing a `HashMap<FunctionId, FunctionNode>` with start addresses as keys.
 ///
 /// # Errors
 ///
-/// - Returns errors if symbol name demangling fails.
+/// - Returns [`Error::InvalidFormat`](crate::error::Error::InvalidFormat) if the architecture
+///   of the binary is not supported.
+/// - Returns errors if symbol name demangling fails, or if the disassembler used to discover
+///   the functions of a stripped binary cannot be initialised.
 ///
 /// # Feature Flags
 ///
 /// - `progress_bar`: If enabled, displays a spinner indicating the function detection.
 pub fn function_detection<'a>(
     elf: &'a Elf<'a>,
+    buffer: &[u8],
     language: &str,
-) -> Result<HashMap<String, FunctionNode>> {
-    let mut func_found = HashMap::new();
+) -> Result<HashMap<FunctionId, FunctionNode>> {
+    let arch = Arch::from_elf(elf)?;
+    let mut func_found: HashMap<FunctionId, FunctionNode> = HashMap::new();
+    // Start addresses of the functions already named after a global or weak symbol.
+    let mut globals = HashSet::new();
 
     #[cfg(feature = "progress_bar")]
     let pb = {
@@ -53,18 +71,35 @@ pub fn function_detection<'a>(
         if symbol.st_type() == goblin::elf::sym::STT_FUNC && symbol.st_shndx != 0 {
             if let Some(func_name) = get_name_symbol(elf, &symbol) {
                 let demangled_name = demangle_function_name(func_name, language)?;
-                func_found.insert(
-                    demangled_name.clone(),
-                    FunctionNode::new(
-                        demangled_name,
-                        symbol.st_value,
-                        symbol.st_value + symbol.st_size,
-                    ),
-                );
+                let start_addr = arch.code_address(symbol.st_value);
+                let end_addr = start_addr + symbol.st_size;
+                let global = symbol.st_bind() != goblin::elf::sym::STB_LOCAL;
+                match func_found.entry(start_addr) {
+                    Entry::Occupied(mut entry) => {
+                        // Aliases: a global name is preferred to a local one.
+                        let node = entry.get_mut();
+                        node.end_addr = node.end_addr.max(end_addr);
+                        if global && globals.insert(start_addr) {
+                            node.name = demangled_name;
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        let mut node = FunctionNode::new(demangled_name, start_addr, end_addr);
+                        node.thumb = arch.is_thumb(symbol.st_value);
+                        entry.insert(node);
+                        if global {
+                            globals.insert(start_addr);
+                        }
+                    }
+                }
             }
         }
     }
 
+    if func_found.is_empty() {
+        func_found = discover_functions(elf, buffer, language)?;
+    }
+
     #[cfg(feature = "progress_bar")]
     pb.finish_with_message(format!(
         "Detection completed! Found {} functions.",
@@ -77,19 +112,30 @@ pub fn function_detection<'a>(
 pub(crate) fn demangle_function_name(mangled_name: &str, language: &str) -> Result<String> {
     match language {
         "Rust" | "rust" => Ok(demangle(mangled_name).to_string()),
-        "C_plus_plus_14" | "C++" => {
-            if mangled_name.starts_with("_Z") {
-                let options = DemangleOptions::default();
-                let demangled_name = Symbol::new(mangled_name)?.demangle(&options)?;
-                Ok(demangled_name)
+        "C++" => demangle_cpp(mangled_name),
+        lang if lang.starts_with("C_plus_plus") => demangle_cpp(mangled_name),
+        // In mixed binaries the mangling scheme of each symbol tells which demangler applies.
+        lang if lang.starts_with("Mixed") => {
+            if mangled_name.starts_with("_R") || is_legacy_rust_symbol(mangled_name) {
+                Ok(demangle(mangled_name).to_string())
             } else {
-                Ok(mangled_name.to_string())
+                Ok(demangle_cpp(mangled_name).unwrap_or_else(|_| mangled_name.to_string()))
             }
         }
         _ => Ok(mangled_name.to_string()),
     }
 }
 
+fn demangle_cpp(mangled_name: &str) -> Result<String> {
+    if mangled_name.starts_with("_Z") {
+        let options = DemangleOptions::default();
+        let demangled_name = Symbol::new(mangled_name)?.demangle(&options)?;
+        Ok(demangled_name)
+    } else {
+        Ok(mangled_name.to_string())
+    }
+}
+
 fn get_name_symbol<'a>(elf: &'a Elf<'a>, symbol: &'a goblin::elf::Sym) -> Option<&'a str> {
     elf.strtab.get_at(symbol.st_name)
 }
@@ -0,0 +1,440 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use gimli::UnwindSection;
+use goblin::elf::Elf;
+
+use crate::{
+    arch::{Arch, RegisterValues},
+    detect::demangle_function_name,
+    error::Result,
+    imports::is_plt,
+    inspect::{code_slice, executable_sections, find_section, section_data, Pointers},
+    instruction::disassemble,
+    FunctionId, FunctionNode,
+};
+
+/// Byte patterns that typically open an x86-64 function: `endbr64` and `push %rbp; mov %rsp, %rbp`.
+const PROLOGUES: &[&[u8]] = &[&[0xf3, 0x0f, 0x1e, 0xfa], &[0x55, 0x48, 0x89, 0xe5]];
+
+/// Compilers align function entries to 16 bytes; prologue matches elsewhere are ignored.
+const FUNCTION_ALIGNMENT: u64 = 16;
+
+/// Discovers the functions of a stripped ELF binary, without relying on `.symtab`.
+///
+/// # Overview
+///
+/// Function starts are collected from several sources, from the most to the least reliable:
+///
+/// 1. The entry point (`e_entry`), named `_start`.
+/// 2. The defined function symbols of `.dynsym`.
+/// 3. The ranges described by the FDEs of `.eh_frame`.
+/// 4. On x86-64, prologue heuristics (`endbr64`, `push %rbp; mov %rsp, %rbp`) on aligned
+///    addresses not covered by any of the previous sources.
+///
+/// Starting from these seeds, every function is disassembled and the targets of its direct
+/// calls, as well as the code addresses it loads (e.g., the `main` pointer handed to
+/// `__libc_start_main`), become new functions until a fixpoint is reached. On x86 the
+/// addresses loaded as immediates are only followed when an FDE or a prologue starts there,
+/// so that data and jump table addresses do not split the functions they point into.
+/// Functions without a symbol get a synthetic name such as `sub_401000`. Functions without an
+/// FDE end where the next function starts, or at the end of their section. On ARM, the
+/// instruction set of every function (ARM or Thumb) follows the Thumb bit of the address it
+/// was found at, and switches across `blx` calls.
+///
+/// # Arguments
+///
+/// - `elf`: A reference to an [`Elf`] structure.
+/// - `buffer`: Byte buffer of the ELF binary.
+/// - `language`: The programming language for function name demangling.
+///
+/// # Returns
+///
+/// - A `Result` containing a `HashMap<FunctionId, FunctionNode>` with start addresses as keys.
+///
+/// # Errors
+///
+/// - Returns errors if the architecture is not supported, the disassembler cannot be
+///   initialised or symbol name demangling fails.
+pub fn discover_functions(
+    elf: &Elf,
+    buffer: &[u8],
+    language: &str,
+) -> Result<HashMap<FunctionId, FunctionNode>> {
+    let arch = Arch::from_elf(elf)?;
+    let mut names = BTreeMap::new();
+    let mut ends = BTreeMap::new();
+    let mut thumb = BTreeSet::new();
+
+    let entry = arch.code_address(elf.header.e_entry);
+    if is_code(elf, entry) {
+        names.insert(entry, "_start".to_string());
+        if arch.is_thumb(elf.header.e_entry) {
+            thumb.insert(entry);
+        }
+    }
+    for symbol in &elf.dynsyms {
+        let start = arch.code_address(symbol.st_value);
+        if symbol.st_type() == goblin::elf::sym::STT_FUNC
+            && symbol.st_shndx != 0
+            && is_code(elf, start)
+        {
+            if let Some(name) = elf.dynstrtab.get_at(symbol.st_name) {
+                names
+                    .entry(start)
+                    .or_insert(demangle_function_name(name, language)?);
+                if symbol.st_size > 0 {
+                    ends.insert(start, start + symbol.st_size);
+                }
+                if arch.is_thumb(symbol.st_value) {
+                    thumb.insert(start);
+                }
+            }
+        }
+    }
+    for (start, end) in eh_frame_ranges(elf, buffer) {
+        if is_code(elf, start) {
+            ends.entry(start).or_insert(end);
+        }
+    }
+
+    let mut starts: BTreeSet<u64> = names.keys().chain(ends.keys()).copied().collect();
+    if arch == Arch::X86_64 {
+        starts.extend(prologue_starts(elf, buffer, &ends));
+    }
+    traverse(elf, arch, buffer, &mut starts, &mut thumb, &ends)?;
+
+    let mut functions = HashMap::new();
+    let bounds: Vec<u64> = starts.iter().copied().collect();
+    for (i, &start) in bounds.iter().enumerate() {
+        let end = ends.get(&start).copied().unwrap_or_else(|| {
+            let section_end = section_end(elf, start);
+            bounds
+                .get(i + 1)
+                .map_or(section_end, |&next| next.min(section_end))
+        });
+        let name = names
+            .get(&start)
+            .cloned()
+            .unwrap_or_else(|| format!("sub_{start:x}"));
+        let mut node = FunctionNode::new(name, start, end);
+        node.thumb = thumb.contains(&start);
+        functions.insert(start, node);
+    }
+
+    Ok(functions)
+}
+
+fn traverse(
+    elf: &Elf,
+    arch: Arch,
+    buffer: &[u8],
+    starts: &mut BTreeSet<u64>,
+    thumb: &mut BTreeSet<u64>,
+    ends: &BTreeMap<u64, u64>,
+) -> Result<()> {
+    let arm_cs = arch.capstone(false)?;
+    let thumb_cs = arch.capstone(true)?;
+    let pointers = Pointers::new(elf, buffer);
+    let mut pending: Vec<u64> = starts.iter().copied().collect();
+
+    while let Some(start) = pending.pop() {
+        let end = ends.get(&start).copied().unwrap_or_else(|| {
+            let section_end = section_end(elf, start);
+            starts
+                .range(start + 1..)
+                .next()
+                .map_or(section_end, |&next| next.min(section_end))
+        });
+        let Some(code) = code_slice(elf, buffer, start, end) else {
+            continue;
+        };
+        let is_thumb = thumb.contains(&start);
+        let cs = if is_thumb { &thumb_cs } else { &arm_cs };
+        let Ok(instructions) = disassemble(cs, arch, code, start) else {
+            continue;
+        };
+
+        let mut values = RegisterValues::new(arch, is_thumb);
+        for insn in &instructions {
+            let call_target = values.call_target(insn);
+            let value = values.update(&pointers, insn);
+            // Target address and whether it holds Thumb code.
+            let target = match insn.mnemonic.as_str() {
+                // `blx` with an immediate switches between ARM and Thumb.
+                m if insn.is_call() => call_target.map(|target| (target, is_thumb != (m == "blx"))),
+                "lea" if arch == Arch::X86_64 => insn.rip_relative().map(|target| (target, false)),
+                // Immediates may as well be data or jump table addresses: they are only
+                // followed when they point to the start of a function.
+                m if matches!(arch, Arch::X86 | Arch::X86_64)
+                    && (m.starts_with("mov") || m == "push") =>
+                {
+                    insn.immediate()
+                        .and_then(|target| u64::try_from(target).ok())
+                        .filter(|&target| {
+                            ends.contains_key(&target) || has_prologue(elf, buffer, target)
+                        })
+                        .map(|target| (target, false))
+                }
+                // `auipc` only yields the upper part of a PC-relative address, which points
+                // into the current function.
+                "auipc" => None,
+                _ => value.map(|value| (arch.code_address(value), arch.is_thumb(value))),
+            };
+            if let Some((target, target_thumb)) = target {
+                if is_code(elf, target) && starts.insert(target) {
+                    if target_thumb {
+                        thumb.insert(target);
+                    }
+                    pending.push(target);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn eh_frame_ranges(elf: &Elf, buffer: &[u8]) -> Vec<(u64, u64)> {
+    let mut ranges = Vec::new();
+    let Some(section) = find_section(elf, ".eh_frame") else {
+        return ranges;
+    };
+    let Some(data) = section_data(section, buffer) else {
+        return ranges;
+    };
+    let endian = if elf.little_endian {
+        gimli::RunTimeEndian::Little
+    } else {
+        gimli::RunTimeEndian::Big
+    };
+
+    let eh_frame = gimli::EhFrame::new(data, endian);
+    let mut bases = gimli::BaseAddresses::default().set_eh_frame(section.sh_addr);
+    if let Some(text) = find_section(elf, ".text") {
+        bases = bases.set_text(text.sh_addr);
+    }
+    if let Some(got) = find_section(elf, ".got") {
+        bases = bases.set_got(got.sh_addr);
+    }
+
+    let mut entries = eh_frame.entries(&bases);
+    while let Ok(Some(entry)) = entries.next() {
+        if let gimli::CieOrFde::Fde(partial) = entry {
+            if let Ok(fde) =
+                partial.parse(|_, bases, offset| eh_frame.cie_from_offset(bases, offset))
+            {
+                if fde.len() > 0 {
+                    ranges.push((fde.initial_address(), fde.initial_address() + fde.len()));
+                }
+            }
+        }
+    }
+
+    ranges
+}
+
+fn prologue_starts(elf: &Elf, buffer: &[u8], known: &BTreeMap<u64, u64>) -> Vec<u64> {
+    let covered = |addr: u64| {
+        known
+            .range(..=addr)
+            .next_back()
+            .is_some_and(|(_, &end)| addr < end)
+    };
+
+    let mut starts = Vec::new();
+    for section in executable_sections(elf) {
+        let Some(data) = section_data(section, buffer) else {
+            continue;
+        };
+        let first = section.sh_addr.next_multiple_of(FUNCTION_ALIGNMENT);
+        let end = section.sh_addr + section.sh_size;
+        let aligned =
+            std::iter::successors(Some(first), |addr| addr.checked_add(FUNCTION_ALIGNMENT));
+        for addr in aligned.take_while(|&addr| addr < end) {
+            let Ok(offset) = usize::try_from(addr - section.sh_addr) else {
+                break;
+            };
+            if !covered(addr)
+                && !is_plt(elf, addr)
+                && starts_with_prologue(&data[offset.min(data.len())..])
+            {
+                starts.push(addr);
+            }
+        }
+    }
+
+    starts
+}
+
+fn starts_with_prologue(code: &[u8]) -> bool {
+    PROLOGUES.iter().any(|prologue| code.starts_with(prologue))
+}
+
+/// Whether the code at `addr` starts with one of the [`PROLOGUES`].
+fn has_prologue(elf: &Elf, buffer: &[u8], addr: u64) -> bool {
+    code_slice(elf, buffer, addr, addr.saturating_add(8)).is_some_and(starts_with_prologue)
+}
+
+fn is_code(elf: &Elf, addr: u64) -> bool {
+    executable_sections(elf).any(|sec| addr >= sec.sh_addr && addr < sec.sh_addr + sec.sh_size)
+        && !is_plt(elf, addr)
+}
+
+fn section_end(elf: &Elf, addr: u64) -> u64 {
+    executable_sections(elf)
+        .find(|sec| addr >= sec.sh_addr && addr < sec.sh_addr + sec.sh_size)
+        .map_or(addr, |sec| sec.sh_addr + sec.sh_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goblin::elf::section_header::{SHF_ALLOC, SHF_EXECINSTR, SHT_PROGBITS};
+
+    /// Names of the sections of [`stripped_elf`].
+    const SECTION_NAMES: &[u8] = b"\0.text\0.eh_frame\0";
+
+    /// Code of a stripped x86-64 binary, loaded at `0x1000`.
+    fn text() -> Vec<u8> {
+        let mut text = vec![0xcc; 0x70];
+        let mut place = |addr: usize, code: &[u8]| {
+            text[addr - 0x1000..addr - 0x1000 + code.len()].copy_from_slice(code);
+        };
+        place(
+            0x1000,
+            &[
+                0xf3, 0x0f, 0x1e, 0xfa, // endbr64
+                0xbf, 0x44, 0x10, 0x00, 0x00, // mov $0x1044, %edi
+                0xbe, 0x28, 0x10, 0x00, 0x00, // mov $0x1028, %esi
+                0xe8, 0x0d, 0x00, 0x00, 0x00, // call 0x1020
+                0xf4, // hlt
+            ],
+        );
+        place(0x1020, &[0x31, 0xc0, 0xc3]); // xor %eax, %eax; ret
+        place(0x1044, &[0x55, 0x48, 0x89, 0xe5, 0x5d, 0xc3]); // push %rbp; mov %rsp, %rbp; ...
+        place(0x1050, &[0xf3, 0x0f, 0x1e, 0xfa, 0xc3]); // endbr64; ret
+        place(0x1060, &[0xb8, 0x00, 0x00, 0x00, 0x00, 0xc3]); // mov $0, %eax; ret
+        text
+    }
+
+    /// `.eh_frame` section, loaded at `0x2000`, with a single FDE covering `0x1060..0x1066`.
+    fn eh_frame() -> Vec<u8> {
+        let mut eh_frame = vec![
+            0x14, 0x00, 0x00, 0x00, // CIE length
+            0x00, 0x00, 0x00, 0x00, // CIE id
+            0x01, b'z', b'R', 0x00, // version, augmentation
+            0x01, 0x78, 0x10, // code and data alignment, return address register
+            0x01, 0x1b, // augmentation data: pc-relative sdata4 pointers
+            0x0c, 0x07, 0x08, 0x90, 0x01, 0x00, 0x00, // CFA instructions
+            0x10, 0x00, 0x00, 0x00, // FDE length
+            0x1c, 0x00, 0x00, 0x00, // CIE pointer
+        ];
+        let pc_begin = 0x1060_i32 - 0x2020;
+        eh_frame.extend(pc_begin.to_le_bytes());
+        eh_frame.extend([0x06, 0x00, 0x00, 0x00]); // pc range
+        eh_frame.extend([0x00, 0x00, 0x00, 0x00]); // augmentation data, padding
+        eh_frame.extend([0x00, 0x00, 0x00, 0x00]); // terminator
+        eh_frame
+    }
+
+    /// Builds a stripped x86-64 ELF made of [`text`] and, optionally, [`eh_frame`], and
+    /// returns it with the bytes of the file.
+    fn stripped_elf(with_eh_frame: bool) -> (Elf<'static>, Vec<u8>) {
+        let mut buffer = text();
+        let mut header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        header.e_machine = goblin::elf::header::EM_X86_64;
+        header.e_entry = 0x1000;
+        let mut elf = Elf::lazy_parse(header).unwrap();
+        elf.shdr_strtab = goblin::strtab::Strtab::new_preparsed(SECTION_NAMES, 0).unwrap();
+        elf.section_headers.push(goblin::elf::SectionHeader {
+            sh_name: 1,
+            sh_type: SHT_PROGBITS,
+            sh_flags: u64::from(SHF_ALLOC | SHF_EXECINSTR),
+            sh_addr: 0x1000,
+            sh_offset: 0,
+            sh_size: 0x70,
+            ..Default::default()
+        });
+        if with_eh_frame {
+            let eh_frame = eh_frame();
+            elf.section_headers.push(goblin::elf::SectionHeader {
+                sh_name: 7,
+                sh_type: SHT_PROGBITS,
+                sh_flags: u64::from(SHF_ALLOC),
+                sh_addr: 0x2000,
+                sh_offset: 0x70,
+                sh_size: eh_frame.len() as u64,
+                ..Default::default()
+            });
+            buffer.extend(eh_frame);
+        }
+        (elf, buffer)
+    }
+
+    #[test]
+    fn test_eh_frame_ranges() {
+        let (elf, buffer) = stripped_elf(true);
+        assert_eq!(eh_frame_ranges(&elf, &buffer), vec![(0x1060, 0x1066)]);
+
+        let (elf, buffer) = stripped_elf(false);
+        assert!(eh_frame_ranges(&elf, &buffer).is_empty());
+    }
+
+    #[test]
+    fn test_prologue_starts() {
+        let (elf, buffer) = stripped_elf(false);
+        assert_eq!(
+            prologue_starts(&elf, &buffer, &BTreeMap::new()),
+            vec![0x1000, 0x1050]
+        );
+
+        // Prologues covered by a known function are not function starts.
+        let known = BTreeMap::from([(0x1000, 0x1060)]);
+        assert!(prologue_starts(&elf, &buffer, &known).is_empty());
+    }
+
+    #[test]
+    fn test_traverse() {
+        let (elf, buffer) = stripped_elf(false);
+        let mut starts = BTreeSet::from([0x1000]);
+        let mut thumb = BTreeSet::new();
+
+        traverse(
+            &elf,
+            Arch::X86_64,
+            &buffer,
+            &mut starts,
+            &mut thumb,
+            &BTreeMap::new(),
+        )
+        .unwrap();
+
+        // The call target and the `main`-like pointer opening with a prologue are followed,
+        // the immediate pointing into the padding is not.
+        assert_eq!(starts, BTreeSet::from([0x1000, 0x1020, 0x1044]));
+        assert!(thumb.is_empty());
+    }
+
+    #[test]
+    fn test_discover_functions() {
+        let (elf, buffer) = stripped_elf(true);
+
+        let functions = discover_functions(&elf, &buffer, "C").unwrap();
+
+        let mut found: Vec<(&str, u64, u64)> = functions
+            .values()
+            .map(|node| (node.name.as_str(), node.start_addr, node.end_addr))
+            .collect();
+        found.sort_by_key(|&(_, start, _)| start);
+        assert_eq!(
+            found,
+            vec![
+                ("_start", 0x1000, 0x1020),
+                ("sub_1020", 0x1020, 0x1044),
+                ("sub_1044", 0x1044, 0x1050),
+                ("sub_1050", 0x1050, 0x1060),
+                ("sub_1060", 0x1060, 0x1066),
+            ]
+        );
+    }
+}
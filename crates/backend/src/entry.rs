@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 
-use regex::Regex;
-
 use crate::{
+    arch::Arch,
     error::{Error, Result},
-    FunctionNode,
+    instruction::Instruction,
+    FunctionId, FunctionNode,
 };
 
 /// Identifies the main function starting from the _start function in the disassembly.
@@ -12,18 +12,30 @@ use crate::{
 /// # Overview
 ///
 /// The `find_main` function extracts the address of the `main` function by analyzing
-/// the disassembly of the `_start` function. In the x86-64 calling convention, the first argument
+/// the disassembly of the `_start` function. When a `main` symbol is present, it is returned
+/// directly unless it is the Rust wrapper calling `std::rt::lang_start`, in which case the
+/// user main is extracted from the wrapper disassembly instead. In the x86-64 calling convention, the first argument
 /// to a function is passed in the `%rdi` register. Before invoking `__libc_start_main`, the address
-/// of the `main` function is loaded into `%rdi`. The `find_main` function looks for a `mov` instruction
-/// that loads the address of `main` into `%rdi` in the disassembly of `_start`. This is the point at which
-/// the address of `main` is set up for `__libc_start_main`. Once the address is extracted, it searches
-/// through the functions to find the one corresponding to `main`. If the `main` function cannot be found,
+/// of the `main` function is loaded into `%rdi`. The `find_main` function looks for a `mov` or `lea`
+/// instruction that loads the address of `main` into `%rdi` among the instructions of `_start`
+/// (see [`Instruction`]). This is the point at which
+/// the address of `main` is set up for `__libc_start_main`. Once the address is extracted, the function
+/// starting there is looked up by its [`FunctionId`]. If the `main` function cannot be found,
 /// an error is returned.
 ///
+/// On 32-bit x86 the arguments of `__libc_start_main` are pushed on the stack in reverse
+/// order, so `main` is the last immediate pushed before the call.
+///
+/// On the other architectures the address of `main` is built in the first argument register
+/// (see [`Arch::argument_register`]) by one or more instructions, whose resulting value is
+/// resolved during the analysis (see [`Instruction::value`]). The last value written to that register
+/// before the first call is taken as the address of `main`.
+///
 /// # Arguments
 ///
-/// - `functions`: A mutable reference to a `HashMap` mapping function names to their corresponding
+/// - `functions`: A reference to a `HashMap` mapping function identifiers to their corresponding
 ///   [`FunctionNode`] structures. The function names should include `_start` and possibly `main`.
+/// - `arch`: The architecture of the binary (see [`Arch::from_elf`]).
 ///
 /// # Returns
 ///
@@ -36,15 +48,14 @@ use crate::{
 ///   or if no valid main function address is extracted from `_start`'s disassembly.
 /// - `Error::FunctionNotFound("_start")`: If the `_start` function is not present in the functions map.
 /// - `Error::FunctionNotFound("_start disassembly")`: If no disassembly is available for `_start`.
-/// - `Error::InvalidRegex`: If there is an issue while parsing the disassembly with the regex used
-///   to extract the main address.
 ///
 /// # Feature Flags
 ///
 /// - `progress_bar`: If enabled, displays a spinner indicating the extraction process of the main function.
 ///
 pub fn find_main<S: ::std::hash::BuildHasher>(
-    functions: &HashMap<String, FunctionNode, S>,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
 ) -> Result<FunctionNode> {
     #[cfg(feature = "progress_bar")]
     let pb = {
@@ -58,19 +69,29 @@ pub fn find_main<S: ::std::hash::BuildHasher>(
         pb
     };
 
-    if let Some(main_wrapper) = functions.get("main") {
-        if let Some(disassembly) = &main_wrapper.disassembly {
+    if let Some(main_wrapper) = find_by_name(functions, "main") {
+        // Only Rust binaries wrap the user main: their `main` hands it to `std::rt::lang_start`.
+        // In C and C++ binaries `main` is already the user main.
+        if !main_wrapper.children.iter().any(|child| {
+            functions
+                .get(child)
+                .is_some_and(|child| child.name.contains("lang_start"))
+        }) {
+            #[cfg(feature = "progress_bar")]
+            pb.finish_with_message("User main function found: main".to_string());
+            return Ok(main_wrapper.clone());
+        }
+
+        if main_wrapper.disassembly.is_some() {
             #[cfg(feature = "progress_bar")]
             pb.set_message("Found main wrapper, extracting user main address...".to_string());
 
-            let user_main_addr = extract_main(disassembly)?;
+            let user_main_addr = extract_main(&main_wrapper.instructions, arch);
             if user_main_addr != 0 {
-                for (func_name, func_node) in functions {
-                    if func_node.start_addr == user_main_addr {
-                        #[cfg(feature = "progress_bar")]
-                        pb.finish_with_message(format!("User main function found: {func_name}"));
-                        return Ok(func_node.clone());
-                    }
+                if let Some(func_node) = functions.get(&user_main_addr) {
+                    #[cfg(feature = "progress_bar")]
+                    pb.finish_with_message(format!("User main function found: {}", func_node.name));
+                    return Ok(func_node.clone());
                 }
 
                 #[cfg(feature = "progress_bar")]
@@ -90,22 +111,22 @@ pub fn find_main<S: ::std::hash::BuildHasher>(
     let entry_points = ["_start", "__start", "start", "__dls2"];
 
     for entry_name in &entry_points {
-        if let Some(start_func) = functions.get(*entry_name) {
-            if let Some(disassembly) = &start_func.disassembly {
+        if let Some(start_func) = find_by_name(functions, entry_name) {
+            if start_func.disassembly.is_some() {
                 #[cfg(feature = "progress_bar")]
                 pb.set_message(format!("Trying entry point: {entry_name}"));
 
-                let main_addr = extract_main(disassembly)?;
-                if main_addr != 0 {
-                    for (func_name, func_node) in functions {
-                        if func_node.start_addr == main_addr {
-                            #[cfg(feature = "progress_bar")]
-                            pb.finish_with_message(format!(
-                                "Main function found via {entry_name}: {func_name}"
-                            ));
-                            return Ok(func_node.clone());
-                        }
-                    }
+                let main_addr = extract_main(&start_func.instructions, arch);
+                if main_addr == 0 {
+                    continue;
+                }
+                if let Some(func_node) = functions.get(&main_addr) {
+                    #[cfg(feature = "progress_bar")]
+                    pb.finish_with_message(format!(
+                        "Main function found via {entry_name}: {}",
+                        func_node.name
+                    ));
+                    return Ok(func_node.clone());
                 }
             }
         }
@@ -116,66 +137,135 @@ pub fn find_main<S: ::std::hash::BuildHasher>(
     Err(Error::FunctionNotFound("main".to_string()))
 }
 
-fn extract_main(disassembly: &str) -> Result<u64> {
-    let re_direct_mov = Regex::new(r"mov[ablqw]*\s+\$0x([a-fA-F0-9]+),\s+%rdi")?;
-    let re_rust_lea_pos = Regex::new(r"([0-9a-fA-F]+):\s+lea\s+0x([0-9a-fA-F]+)\(%rip\),\s+%rdi")?;
-    let re_rust_lea_neg = Regex::new(r"([0-9a-fA-F]+):\s+lea\s+-0x([0-9a-fA-F]+)\(%rip\),\s+%rdi")?;
-    let re_flexible_mov = Regex::new(r"mov[a-z]*\s+\$([0-9a-fA-F]+|0x[0-9a-fA-F]+),\s+%rdi")?;
-
-    for line in disassembly.lines() {
-        if let Some(caps) = re_direct_mov.captures(line) {
-            let addr_str = caps.get(1).unwrap().as_str();
-            let addr = u64::from_str_radix(addr_str, 16).unwrap();
-            return Ok(addr);
-        }
-
-        if let Some(caps) = re_flexible_mov.captures(line) {
-            let addr_str = caps.get(1).unwrap().as_str();
-            let addr_str = addr_str.strip_prefix("0x").unwrap_or(addr_str);
-            let addr = u64::from_str_radix(addr_str, 16).unwrap();
-            return Ok(addr);
-        }
-
-        if let Some(caps) = re_rust_lea_pos.captures(line) {
-            let lea_instruction_address_str = caps.get(1).unwrap().as_str();
-            let lea_instruction_address =
-                u64::from_str_radix(lea_instruction_address_str, 16).unwrap();
+/// Returns the address of `main` handed to the first call of `instructions`, or 0 if none is
+/// found.
+fn extract_main(instructions: &[Instruction], arch: Arch) -> u64 {
+    match arch {
+        Arch::X86_64 => extract_loaded_argument(instructions),
+        Arch::X86 => extract_pushed_argument(instructions),
+        _ => extract_argument(instructions, arch),
+    }
+}
 
-            let offset_str = caps.get(2).unwrap().as_str();
-            let offset = u64::from_str_radix(offset_str, 16).unwrap();
-            let rip_value = lea_instruction_address + 7;
-            let main_address = rip_value + offset;
+/// Returns the first address loaded into `%rdi`, either as an immediate (`mov $0x401136, %rdi`)
+/// or relative to `%rip` (`lea 0x2f3b(%rip), %rdi`), or 0 if there is none.
+fn extract_loaded_argument(instructions: &[Instruction]) -> u64 {
+    instructions
+        .iter()
+        .filter(|insn| insn.destination.as_deref() == Some(Arch::X86_64.argument_register()))
+        .find_map(|insn| match insn.mnemonic.as_str() {
+            "lea" => insn.rip_relative(),
+            m if m.starts_with("mov") => {
+                insn.immediate().and_then(|value| u64::try_from(value).ok())
+            }
+            _ => None,
+        })
+        .unwrap_or(0)
+}
 
-            return Ok(main_address);
+/// Returns the last immediate pushed on the stack before the first call of the function, or
+/// 0 if there is none.
+fn extract_pushed_argument(instructions: &[Instruction]) -> u64 {
+    let mut argument = 0;
+    for insn in instructions {
+        if insn.is_call() {
+            return argument;
         }
+        if insn.mnemonic == "push" {
+            argument = insn
+                .immediate()
+                .and_then(|value| u64::try_from(value).ok())
+                .unwrap_or(0);
+        }
+    }
+    0
+}
 
-        if let Some(caps) = re_rust_lea_neg.captures(line) {
-            let lea_instruction_address_str = caps.get(1).unwrap().as_str();
-            let lea_instruction_address =
-                u64::from_str_radix(lea_instruction_address_str, 16).unwrap();
-
-            let offset_str = caps.get(2).unwrap().as_str();
-            let offset = u64::from_str_radix(offset_str, 16).unwrap();
-
-            let rip_value = lea_instruction_address + 7;
-            let main_address = rip_value - offset;
-
-            return Ok(main_address);
+/// Returns the last value resolved for an instruction writing the first argument register
+/// before the first call of the function, or 0 if there is none.
+fn extract_argument(instructions: &[Instruction], arch: Arch) -> u64 {
+    let mut argument = 0;
+    for insn in instructions {
+        if insn.is_call() {
+            return argument;
+        }
+        let Some(destination) = &insn.destination else {
+            continue;
+        };
+        if arch.canonical_register(destination) == arch.argument_register() {
+            argument = insn.value.map_or(0, |value| arch.code_address(value));
         }
     }
+    0
+}
 
-    Ok(0)
+/// Returns the function named `name`. When several functions share the name (e.g., static
+/// functions of different translation units), the one with the lowest address is returned.
+pub fn find_by_name<'a, S: ::std::hash::BuildHasher>(
+    functions: &'a HashMap<FunctionId, FunctionNode, S>,
+    name: &str,
+) -> Option<&'a FunctionNode> {
+    functions
+        .values()
+        .filter(|func| func.name == name)
+        .min_by_key(|func| func.start_addr)
 }
 
-pub(crate) fn calculate_invocation_count(functions: &mut HashMap<String, FunctionNode>) {
+pub(crate) fn calculate_invocation_count(functions: &mut HashMap<FunctionId, FunctionNode>) {
     let nodes_to_update: Vec<_> = functions
         .values()
         .flat_map(|node| node.children.clone())
         .collect();
 
-    for node_name in nodes_to_update {
-        if let Some(func) = functions.get_mut(&node_name) {
+    for id in nodes_to_update {
+        if let Some(func) = functions.get_mut(&id) {
             func.invocation_entry += 1;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::disassemble;
+
+    /// Builds a `main` at `0x1000` loading the address of `0x3000` into `%rdi` before calling
+    /// the function `callee`, at `0x2000`.
+    fn main_calling(callee: &str, loaded: &str) -> HashMap<FunctionId, FunctionNode> {
+        let arch = Arch::X86_64;
+        let code = [
+            0x48, 0x8d, 0x3d, 0xf9, 0x1f, 0x00, 0x00, // lea 0x1ff9(%rip), %rdi
+            0xe8, 0xf4, 0x0f, 0x00, 0x00, // call 0x2000
+        ];
+        let mut main = FunctionNode::new("main".to_string(), 0x1000, 0x100c);
+        main.instructions =
+            disassemble(&arch.capstone(false).unwrap(), arch, &code, 0x1000).unwrap();
+        main.disassembly = Some(String::new());
+        main.children = vec![0x2000];
+        [
+            main,
+            FunctionNode::new(callee.to_string(), 0x2000, 0x2100),
+            FunctionNode::new(loaded.to_string(), 0x3000, 0x3100),
+        ]
+        .into_iter()
+        .map(|node| (node.start_addr, node))
+        .collect()
+    }
+
+    #[test]
+    fn test_find_main() {
+        // In a C binary `main` is the user main, whatever address it passes to its callees.
+        let functions = main_calling("atexit@GLIBC_2.2.5", "cleanup");
+        assert_eq!(find_main(&functions, Arch::X86_64).unwrap().name, "main");
+
+        // In a Rust binary `main` hands the user main to `std::rt::lang_start`.
+        let functions = main_calling(
+            "std::rt::lang_start::h6a1b2c3d4e5f6a7b",
+            "app::main::h0f1e2d3c4b5a6978",
+        );
+        assert_eq!(
+            find_main(&functions, Arch::X86_64).unwrap().name,
+            "app::main::h0f1e2d3c4b5a6978"
+        );
+    }
+}
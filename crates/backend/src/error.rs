@@ -16,7 +16,7 @@ pub enum Error {
     GimliError(gimli::Error),
     ObjectError(object::Error),
     FunctionNotFound(String),
-    InvalidRegex(String),
+    ManifestVersion(String),
     #[cfg(feature = "progress_bar")]
     ProgressStyleError(indicatif::style::TemplateError),
 }
@@ -37,7 +37,9 @@ impl fmt::Display for Error {
             Error::GimliError(e) => write!(f, "Gimli error -> {e}"),
             Error::ObjectError(e) => write!(f, "Object error -> {e}"),
             Error::FunctionNotFound(func) => write!(f, "Function '{func}' not found"),
-            Error::InvalidRegex(regex) => write!(f, "Invalid regex '{regex}'"),
+            Error::ManifestVersion(version) => {
+                write!(f, "Unsupported manifest version '{version}'")
+            }
             #[cfg(feature = "progress_bar")]
             Error::ProgressStyleError(e) => write!(f, "Progress style error -> {e}"),
         }
@@ -110,12 +112,6 @@ impl From<object::Error> for Error {
     }
 }
 
-impl From<regex::Error> for Error {
-    fn from(err: regex::Error) -> Self {
-        Error::InvalidRegex(err.to_string())
-    }
-}
-
 #[cfg(feature = "progress_bar")]
 impl From<indicatif::style::TemplateError> for Error {
     fn from(err: indicatif::style::TemplateError) -> Self {
@@ -0,0 +1,483 @@
+use std::collections::{BTreeMap, HashMap};
+
+use goblin::elf::{sym, Elf};
+
+use crate::{
+    arch::Arch,
+    detect::demangle_function_name,
+    error::Result,
+    inspect::{find_section, section_data},
+    instruction::{disassemble, Group, Instruction, Operand},
+    FunctionId, FunctionNode,
+};
+
+/// Sections holding the stubs through which imported functions are called.
+const PLT_SECTIONS: [&str; 3] = [".plt", ".plt.sec", ".plt.got"];
+
+/// Functions imported from shared libraries, indexed by the addresses they are called through.
+///
+/// # Overview
+///
+/// A dynamically linked binary calls an imported function through a PLT stub (`.plt`,
+/// `.plt.sec`, `.plt.got`) jumping to the address stored in a GOT slot or, when compiled
+/// without PLT, by loading the GOT slot itself (`call *off(%rip)`). The dynamic loader fills
+/// the slots according to the relocations of `.rela.plt` and `.rela.dyn`, which name the
+/// `.dynsym` symbol to bind. `Imports` maps the stubs and the slots back to these symbols.
+///
+/// Imports are named after the symbol and, when the binary requires a specific version of it,
+/// the version: `connect@GLIBC_2.2.5`.
+///
+/// On x86 the stubs are decoded to find the slot they jump through. On the other
+/// architectures the stubs follow the PLT header in the order of the `.rela.plt` relocations
+/// (see [`Arch::plt_layouts`]).
+#[derive(Debug, Default)]
+pub struct Imports {
+    stubs: HashMap<u64, String>,
+    slots: HashMap<u64, String>,
+    /// Address range of every import: its PLT stub, or its GOT slot when it has no stub.
+    ranges: BTreeMap<String, (u64, u64)>,
+    /// Value of `%ebx` in position-independent i386 code: the address of the GOT.
+    got: Option<u64>,
+}
+
+impl Imports {
+    /// Collects the functions imported by an ELF binary.
+    ///
+    /// # Arguments
+    ///
+    /// - `elf`: A reference to an [`Elf`] structure.
+    /// - `buffer`: Byte buffer of the ELF binary.
+    /// - `language`: The programming language for function name demangling.
+    ///
+    /// # Returns
+    ///
+    /// - A `Result` containing the [`Imports`] of the binary, empty for static binaries.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::InvalidFormat`](crate::error::Error::InvalidFormat) if the
+    ///   architecture of the binary is not supported.
+    /// - Returns errors if symbol name demangling fails, or if the disassembler used to decode
+    ///   the PLT stubs cannot be initialised.
+    pub fn new(elf: &Elf, buffer: &[u8], language: &str) -> Result<Self> {
+        let arch = Arch::from_elf(elf)?;
+        let mut imports = Self {
+            got: find_section(elf, ".got.plt")
+                .or_else(|| find_section(elf, ".got"))
+                .map(|got| got.sh_addr),
+            ..Self::default()
+        };
+
+        let mut plt_slots = Vec::new();
+        for reloc in &elf.pltrelocs {
+            if let Some(name) = import_name(elf, reloc.r_sym, language)? {
+                imports.slots.insert(reloc.r_offset, name);
+            }
+            plt_slots.push(reloc.r_offset);
+        }
+        for reloc in elf.dynrelas.iter().chain(elf.dynrels.iter()) {
+            if let Some(name) = import_name(elf, reloc.r_sym, language)? {
+                imports.slots.insert(reloc.r_offset, name);
+            }
+        }
+        if imports.slots.is_empty() {
+            return Ok(imports);
+        }
+
+        match arch {
+            Arch::X86 | Arch::X86_64 => imports.decode_stubs(elf, buffer, arch)?,
+            _ => imports.layout_stubs(elf, arch, &plt_slots),
+        }
+        for (&slot, name) in &imports.slots {
+            imports.ranges.entry(name.clone()).or_insert((slot, slot));
+        }
+
+        Ok(imports)
+    }
+
+    /// Returns the import called through the PLT stub at `addr`.
+    #[must_use]
+    pub fn stub(&self, addr: u64) -> Option<&str> {
+        self.stubs.get(&addr).map(String::as_str)
+    }
+
+    /// Returns the import called by an x86 indirect call through its GOT slot (e.g.,
+    /// `call *0x2fe2(%rip)`).
+    #[must_use]
+    pub fn indirect_call(&self, insn: &Instruction) -> Option<&str> {
+        let slot = slot_operand(insn, self.got)?;
+        self.slots.get(&slot).map(String::as_str)
+    }
+
+    /// Returns the identifier of the import `name` in the call graph: the address of its PLT
+    /// stub, or of its GOT slot when it has no stub.
+    #[must_use]
+    pub fn id(&self, name: &str) -> Option<FunctionId> {
+        self.ranges.get(name).map(|&(start, _)| start)
+    }
+
+    /// Returns the node representing the import identified by `id` (see [`Imports::id`]) in
+    /// the call graph, with [`FunctionNode::import`] set.
+    #[must_use]
+    pub fn node(&self, id: FunctionId) -> Option<FunctionNode> {
+        let name = self.stubs.get(&id).or_else(|| self.slots.get(&id))?;
+        let &(start, end) = self.ranges.get(name).filter(|&&(start, _)| start == id)?;
+        let mut node = FunctionNode::new(name.clone(), start, end);
+        node.import = true;
+        Some(node)
+    }
+
+    fn decode_stubs(&mut self, elf: &Elf, buffer: &[u8], arch: Arch) -> Result<()> {
+        let cs = arch.capstone(false)?;
+        for name in PLT_SECTIONS {
+            let Some(section) = find_section(elf, name) else {
+                continue;
+            };
+            let Some(code) = section_data(section, buffer) else {
+                continue;
+            };
+            let Ok(instructions) = disassemble(&cs, arch, code, section.sh_addr) else {
+                continue;
+            };
+
+            let mut endbr = None;
+            for insn in &instructions {
+                if insn.mnemonic.starts_with("endbr") {
+                    endbr = Some(insn.address);
+                    continue;
+                }
+                // With IBT enabled, stubs open with `endbr64` before jumping through the slot.
+                let start = endbr.take().unwrap_or(insn.address);
+                if !insn.is(Group::Jump) {
+                    continue;
+                }
+                let Some(slot) = slot_operand(insn, self.got) else {
+                    continue;
+                };
+                if let Some(name) = self.slots.get(&slot).cloned() {
+                    self.add_stub(start, insn.next(), name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn layout_stubs(&mut self, elf: &Elf, arch: Arch, plt_slots: &[u64]) {
+        let Some(plt) = find_section(elf, ".plt") else {
+            return;
+        };
+        let count = plt_slots.len() as u64;
+        let Some(&(header, entry)) = arch
+            .plt_layouts()
+            .iter()
+            .find(|(header, entry)| header + count * entry == plt.sh_size)
+        else {
+            return;
+        };
+        let stubs = (0..).map(|i| plt.sh_addr + header + i * entry);
+        for (stub, slot) in stubs.zip(plt_slots) {
+            if let Some(name) = self.slots.get(slot).cloned() {
+                self.add_stub(stub, stub + entry, name);
+            }
+        }
+    }
+
+    fn add_stub(&mut self, start: u64, end: u64, name: String) {
+        self.ranges.entry(name.clone()).or_insert((start, end));
+        self.stubs.insert(start, name);
+    }
+}
+
+/// Whether `addr` lies in a PLT section. The stubs are not functions of the binary: calls to
+/// them are resolved to the imported functions with [`Imports::stub`].
+pub(crate) fn is_plt(elf: &Elf, addr: u64) -> bool {
+    PLT_SECTIONS.iter().any(|name| {
+        find_section(elf, name)
+            .is_some_and(|sec| addr >= sec.sh_addr && addr < sec.sh_addr + sec.sh_size)
+    })
+}
+
+/// Returns the name of the undefined function symbol `index` of `.dynsym`, followed by its
+/// required version, or `None` if the symbol is defined by the binary or is not a function
+/// (`STT_FUNC` or `STT_GNU_IFUNC`).
+fn import_name(elf: &Elf, index: usize, language: &str) -> Result<Option<String>> {
+    let Some(symbol) = elf.dynsyms.get(index).filter(|_| index != 0) else {
+        return Ok(None);
+    };
+    if symbol.st_shndx != 0 || !matches!(symbol.st_type(), sym::STT_FUNC | sym::STT_GNU_IFUNC) {
+        return Ok(None);
+    }
+    let Some(name) = elf
+        .dynstrtab
+        .get_at(symbol.st_name)
+        .filter(|name| !name.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    let name = demangle_function_name(name, language)?;
+    Ok(Some(match symbol_version(elf, index) {
+        Some(version) => format!("{name}@{version}"),
+        None => name,
+    }))
+}
+
+fn symbol_version<'a>(elf: &'a Elf, index: usize) -> Option<&'a str> {
+    let version = elf.versym.as_ref()?.get_at(index)?.version();
+    // Versions 0 and 1 stand for local and global symbols without a version.
+    if version < 2 {
+        return None;
+    }
+    for need in elf.verneed.as_ref()? {
+        for aux in &need {
+            if aux.vna_other == version {
+                return elf.dynstrtab.get_at(aux.vna_name);
+            }
+        }
+    }
+    None
+}
+
+/// Returns the address of the GOT slot read by an x86 indirect jump or call: `*disp(%rip)`,
+/// `*addr`, or `*disp(%ebx)` in position-independent i386 code.
+fn slot_operand(insn: &Instruction, got: Option<u64>) -> Option<u64> {
+    let [Operand::Memory(memory)] = insn.operands.as_slice() else {
+        return None;
+    };
+    if memory.index.is_some() || memory.segment.is_some() {
+        return None;
+    }
+    match memory.base.as_deref() {
+        Some("rip") => insn.rip_relative(),
+        Some("ebx") => got.map(|got| got.wrapping_add(memory.displacement.cast_unsigned())),
+        Some(_) => None,
+        None => u64::try_from(memory.displacement).ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goblin::{
+        container::Ctx,
+        elf::{
+            header::{EM_AARCH64, EM_X86_64},
+            reloc::{R_AARCH64_JUMP_SLOT, R_X86_64_JUMP_SLOT},
+            section_header::{SHT_GNU_VERNEED, SHT_GNU_VERSYM, SHT_PROGBITS},
+            sym::{Symtab, STB_GLOBAL, STB_WEAK, STT_FUNC, STT_GNU_IFUNC, STT_NOTYPE},
+            RelocSection, SectionHeader, VerneedSection, VersymSection,
+        },
+        strtab::Strtab,
+    };
+
+    /// Names of the sections of [`dynamic_elf`].
+    const SECTION_NAMES: &[u8] = b"\0.plt\0.gnu.version\0.gnu.version_r\0";
+    /// Names of the dynamic symbols of [`dynamic_elf`] and of the version they require.
+    const DYNAMIC_NAMES: &[u8] =
+        b"\0connect\0write\0__gmon_start__\0getrandom\0exported\0GLIBC_2.2.5\0libc.so.6\0";
+
+    /// Offsets in the binary of `.dynsym`, `.gnu.version`, `.gnu.version_r`, `.rela.plt`,
+    /// `.rela.dyn` and `.plt`.
+    const DYNSYM: usize = 0x000;
+    const VERSYM: usize = 0x090;
+    const VERNEED: usize = 0x0a0;
+    const RELA_PLT: usize = 0x0c0;
+    const RELA_DYN: usize = 0x108;
+    const PLT: usize = 0x140;
+
+    /// Builds the bytes of a dynamically linked binary, with `plt` as its `.plt` section.
+    ///
+    /// The binary imports `connect@GLIBC_2.2.5`, `write` and the IFUNC `getrandom@GLIBC_2.2.5`
+    /// through the GOT slots `0x3018`, `0x3020` and `0x3028` of `.rela.plt`, in this order.
+    /// `.rela.dyn` also binds the untyped `__gmon_start__` and the function `exported` defined
+    /// by the binary, which are not imported functions.
+    fn image(plt: &[u8], jump_slot: u32) -> Vec<u8> {
+        let mut buffer = vec![0; PLT];
+        let symbols = [
+            (0, 0, 0),
+            (1, (STB_GLOBAL << 4) | STT_FUNC, 0),
+            (9, (STB_GLOBAL << 4) | STT_FUNC, 0),
+            (15, (STB_WEAK << 4) | STT_NOTYPE, 0),
+            (30, (STB_GLOBAL << 4) | STT_GNU_IFUNC, 0),
+            (40, (STB_GLOBAL << 4) | STT_FUNC, 12),
+        ];
+        for (i, (name, info, shndx)) in symbols.into_iter().enumerate() {
+            let symbol = &mut buffer[DYNSYM + i * 24..];
+            symbol[..4].copy_from_slice(&u32::to_le_bytes(name));
+            symbol[4] = info;
+            symbol[6..8].copy_from_slice(&u16::to_le_bytes(shndx));
+        }
+        // Symbols 1 and 4 require the version 2, `GLIBC_2.2.5` of `libc.so.6`.
+        for (i, version) in [0_u16, 2, 1, 1, 2, 1].into_iter().enumerate() {
+            buffer[VERSYM + i * 2..VERSYM + i * 2 + 2].copy_from_slice(&version.to_le_bytes());
+        }
+        // The `Verneed` entry of `libc.so.6`, followed by its single `Vernaux` entry.
+        let verneed = [1_u32 | (1 << 16), 61, 16, 0, 0, 2 << 16, 49, 0];
+        for (i, word) in verneed.into_iter().enumerate() {
+            buffer[VERNEED + i * 4..VERNEED + i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        let relocs = [
+            (RELA_PLT, 0x3018, 1),
+            (RELA_PLT + 24, 0x3020, 2),
+            (RELA_PLT + 48, 0x3028, 4),
+            (RELA_DYN, 0x3030, 3),
+            (RELA_DYN + 24, 0x3038, 5),
+        ];
+        for (offset, slot, symbol) in relocs {
+            let info = (symbol << 32) | u64::from(jump_slot);
+            buffer[offset..offset + 8].copy_from_slice(&u64::to_le_bytes(slot));
+            buffer[offset + 8..offset + 16].copy_from_slice(&info.to_le_bytes());
+        }
+        buffer.extend(plt);
+        buffer
+    }
+
+    /// Parses the binary built by [`image`] for the machine `machine`, with its `.plt` loaded
+    /// at `0x1000`.
+    fn dynamic_elf(buffer: &[u8], machine: u16) -> Elf<'_> {
+        let ctx = Ctx::default();
+        let mut header = goblin::elf::Header::new(ctx);
+        header.e_machine = machine;
+        let mut elf = Elf::lazy_parse(header).unwrap();
+        elf.shdr_strtab = Strtab::new_preparsed(SECTION_NAMES, 0).unwrap();
+        elf.section_headers = vec![
+            SectionHeader {
+                sh_name: 1,
+                sh_type: SHT_PROGBITS,
+                sh_addr: 0x1000,
+                sh_offset: PLT as u64,
+                sh_size: (buffer.len() - PLT) as u64,
+                ..Default::default()
+            },
+            SectionHeader {
+                sh_name: 6,
+                sh_type: SHT_GNU_VERSYM,
+                sh_offset: VERSYM as u64,
+                sh_size: 12,
+                ..Default::default()
+            },
+            SectionHeader {
+                sh_name: 19,
+                sh_type: SHT_GNU_VERNEED,
+                sh_offset: VERNEED as u64,
+                sh_size: 32,
+                sh_info: 1,
+                ..Default::default()
+            },
+        ];
+        elf.dynsyms = Symtab::parse(buffer, DYNSYM, 6, ctx).unwrap();
+        elf.dynstrtab = Strtab::new_preparsed(DYNAMIC_NAMES, 0).unwrap();
+        elf.versym = VersymSection::parse(buffer, &elf.section_headers, ctx).unwrap();
+        elf.verneed = VerneedSection::parse(buffer, &elf.section_headers, ctx).unwrap();
+        elf.pltrelocs = RelocSection::parse(buffer, RELA_PLT, 72, true, ctx).unwrap();
+        elf.dynrelas = RelocSection::parse(buffer, RELA_DYN, 48, true, ctx).unwrap();
+        elf
+    }
+
+    #[test]
+    fn test_import_names() {
+        let buffer = image(&[], R_X86_64_JUMP_SLOT);
+        let elf = dynamic_elf(&buffer, EM_X86_64);
+        let names: Vec<Option<String>> = (0..7)
+            .map(|index| import_name(&elf, index, "C").unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                None,
+                Some("connect@GLIBC_2.2.5".to_string()),
+                Some("write".to_string()),
+                None,
+                Some("getrandom@GLIBC_2.2.5".to_string()),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_stubs() {
+        let mut plt = vec![0xcc; 0x10]; // PLT header
+        for (slot, next) in [(0x3018_u32, 0x1016_u32), (0x3020, 0x1026), (0x3028, 0x1036)] {
+            plt.extend([0xff, 0x25]); // jmp *disp(%rip)
+            plt.extend((slot - next).to_le_bytes());
+            plt.extend([0x68, 0x00, 0x00, 0x00, 0x00]); // push $index
+            plt.extend([0xe9]); // jmp 0x1000
+            plt.extend((0x1000_i32 - i32::try_from(next + 10).unwrap()).to_le_bytes());
+        }
+        let buffer = image(&plt, R_X86_64_JUMP_SLOT);
+        let elf = dynamic_elf(&buffer, EM_X86_64);
+        let imports = Imports::new(&elf, &buffer, "C").unwrap();
+
+        assert_eq!(imports.stub(0x1010), Some("connect@GLIBC_2.2.5"));
+        assert_eq!(imports.stub(0x1020), Some("write"));
+        assert_eq!(imports.stub(0x1030), Some("getrandom@GLIBC_2.2.5"));
+        assert_eq!(imports.stub(0x1000), None);
+        assert_eq!(imports.id("write"), Some(0x1020));
+        assert_eq!(imports.id("__gmon_start__"), None);
+        assert_eq!(imports.id("exported"), None);
+
+        let node = imports.node(0x1010).unwrap();
+        assert_eq!((node.start_addr, node.end_addr), (0x1010, 0x1016));
+        assert!(node.import);
+        assert!(imports.node(0x3018).is_none());
+
+        // call *0x101a(%rip), through the slot of `write`
+        let insn = instruction(Arch::X86_64, &[0xff, 0x15, 0x1a, 0x10, 0x00, 0x00], 0x2000);
+        assert_eq!(imports.indirect_call(&insn), Some("write"));
+    }
+
+    #[test]
+    fn test_layout_stubs() {
+        // The PLT header is followed by 16-byte stubs, or 24-byte stubs with BTI.
+        for entry in [16, 24] {
+            let buffer = image(&vec![0; 32 + 3 * entry], R_AARCH64_JUMP_SLOT);
+            let elf = dynamic_elf(&buffer, EM_AARCH64);
+            let imports = Imports::new(&elf, &buffer, "C").unwrap();
+
+            let entry = entry as u64;
+            assert_eq!(imports.stub(0x1020), Some("connect@GLIBC_2.2.5"));
+            assert_eq!(imports.stub(0x1020 + entry), Some("write"));
+            assert_eq!(
+                imports.stub(0x1020 + 2 * entry),
+                Some("getrandom@GLIBC_2.2.5")
+            );
+            let node = imports.node(0x1020 + entry).unwrap();
+            assert_eq!(node.end_addr, 0x1020 + 2 * entry);
+        }
+
+        // A `.plt` matching no known layout leaves the imports reachable by their GOT slot.
+        let buffer = image(&[0; 0x44], R_AARCH64_JUMP_SLOT);
+        let elf = dynamic_elf(&buffer, EM_AARCH64);
+        let imports = Imports::new(&elf, &buffer, "C").unwrap();
+        assert_eq!(imports.stub(0x1020), None);
+        assert_eq!(imports.id("write"), Some(0x3020));
+    }
+
+    fn instruction(arch: Arch, code: &[u8], address: u64) -> Instruction {
+        let cs = arch.capstone(false).unwrap();
+        disassemble(&cs, arch, code, address).unwrap().remove(0)
+    }
+
+    #[test]
+    fn test_slot_operand() {
+        // call *0x2fe2(%rip)
+        let insn = instruction(Arch::X86_64, &[0xff, 0x15, 0xe2, 0x2f, 0x00, 0x00], 0x1030);
+        assert_eq!(slot_operand(&insn, None), Some(0x4018));
+        // jmp *0x804c00c
+        let insn = instruction(Arch::X86, &[0xff, 0x25, 0x0c, 0xc0, 0x04, 0x08], 0x804_9030);
+        assert_eq!(slot_operand(&insn, None), Some(0x804_c00c));
+        // jmp *0xc(%ebx)
+        let insn = instruction(Arch::X86, &[0xff, 0xa3, 0x0c, 0x00, 0x00, 0x00], 0x1030);
+        assert_eq!(slot_operand(&insn, Some(0x4000)), Some(0x400c));
+        // call *-0x10(%ebx)
+        let insn = instruction(Arch::X86, &[0xff, 0x53, 0xf0], 0x1030);
+        assert_eq!(slot_operand(&insn, Some(0x4000)), Some(0x3ff0));
+        // call *%rax
+        let insn = instruction(Arch::X86_64, &[0xff, 0xd0], 0x1030);
+        assert_eq!(slot_operand(&insn, None), None);
+        // call 0x1030
+        let insn = instruction(Arch::X86_64, &[0xe8, 0xfb, 0xff, 0xff, 0xff], 0x1030);
+        assert_eq!(slot_operand(&insn, None), None);
+    }
+}
@@ -1,18 +1,25 @@
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Read,
+    path::Path,
+};
 
 use crate::{
+    arch::Arch,
     error::{Error, Result},
     BasicInfo,
 };
 
-// use gimli::{DwarfSections, EndianSlice, RunTimeEndian};
+use gimli::{DwarfSections, EndianSlice, RunTimeEndian};
 use goblin::{
     self,
     elf::{Elf, SectionHeader},
 };
 #[cfg(feature = "progress_bar")]
 use indicatif::{ProgressBar, ProgressStyle};
-use object::{self, elf::SHT_PROGBITS /*, Object, ObjectSection*/};
+use object::{self, elf::SHT_PROGBITS, Object, ObjectSection};
 #[cfg(feature = "progress_bar")]
 use std::time::Duration;
 
@@ -35,7 +42,6 @@ use std::time::Duration;
 ///
 /// # Errors
 ///
-/// - Returns [`Error::DebugInfo`] if the ELF is stripped of debug information.
 /// - Propagates errors related to file I/O or parsing.
 ///
 /// # Feature Flags
@@ -64,9 +70,7 @@ pub fn inspect_binary<'a>(
         pb
     };
 
-    if is_stripped(elf) {
-        return Err(Error::DebugInfo);
-    }
+    let stripped = is_stripped(elf);
     let file_name = get_name(elf_path)?;
     let file_type = get_file_type(elf)?;
     let link_type = if is_static(elf) {
@@ -76,8 +80,7 @@ pub fn inspect_binary<'a>(
     };
     let arch = get_architecture(elf)?;
     let pie = is_pie(elf);
-    // let language = get_language(elf_path)?;
-    let language = "Rust".to_string(); // Hardcoded to "Rust" cause ascot is written in Rust and we don't have to support other languages.
+    let (language, languages) = get_language(elf, elf_path)?;
     let file_size = get_file_size(elf_path)?;
     let entry_point = get_entry_point(elf);
 
@@ -85,8 +88,10 @@ pub fn inspect_binary<'a>(
         .file_size(file_size)
         .arch(arch)
         .pie(pie)
+        .stripped(stripped)
         .static_linking(link_type)
         .language(language)
+        .languages(languages)
         .entry_point(entry_point);
 
     let file = File::create(format!("{output_path}/json/basic_info.json"))?;
@@ -139,32 +144,132 @@ pub fn read_elf(file_path: &str) -> Result<Vec<u8>> {
 /// # See also
 ///
 /// - [`read_elf`]: Reads ELF file contents into memory.
-pub fn parse_elf(elf_data: &[u8]) -> Result<Elf> {
+pub fn parse_elf(elf_data: &[u8]) -> Result<Elf<'_>> {
     Ok(Elf::parse(elf_data)?)
 }
 
-pub(crate) fn find_text_section<'a>(elf: &'a Elf<'a>) -> Option<&'a SectionHeader> {
-    elf.section_headers.iter().find(|sec| {
-        sec.sh_type == SHT_PROGBITS && {
-            let name = elf.shdr_strtab.get_at(sec.sh_name);
-            name == Some(".text")
-        }
+pub(crate) fn find_section<'a>(elf: &'a Elf<'a>, name: &str) -> Option<&'a SectionHeader> {
+    elf.section_headers
+        .iter()
+        .find(|sec| elf.shdr_strtab.get_at(sec.sh_name) == Some(name))
+}
+
+pub(crate) fn section_data<'b>(section: &SectionHeader, buffer: &'b [u8]) -> Option<&'b [u8]> {
+    if section.sh_type == goblin::elf::section_header::SHT_NOBITS {
+        return None;
+    }
+    let start = usize::try_from(section.sh_offset).ok()?;
+    let size = usize::try_from(section.sh_size).ok()?;
+    buffer.get(start..start.checked_add(size)?)
+}
+
+pub(crate) fn executable_sections<'a>(elf: &'a Elf<'a>) -> impl Iterator<Item = &'a SectionHeader> {
+    elf.section_headers.iter().filter(|sec| {
+        sec.sh_flags & u64::from(goblin::elf::section_header::SHF_EXECINSTR) != 0
+            && sec.sh_type == SHT_PROGBITS
     })
 }
 
-pub(crate) fn get_name_addr<'a>(elf: &'a Elf<'a>, address: u64) -> Option<&'a str> {
-    let symtab = &elf.syms;
+/// Returns the bytes of the code between `start_addr` and `end_addr`, provided that the
+/// range lies within a single executable section.
+pub(crate) fn code_slice<'b>(
+    elf: &Elf,
+    buffer: &'b [u8],
+    start_addr: u64,
+    end_addr: u64,
+) -> Option<&'b [u8]> {
+    let section = executable_sections(elf)
+        .find(|sec| start_addr >= sec.sh_addr && start_addr < sec.sh_addr + sec.sh_size)?;
+    let end_addr = end_addr.min(section.sh_addr + section.sh_size);
+    let data = section_data(section, buffer)?;
+    let start = usize::try_from(start_addr - section.sh_addr).ok()?;
+    let end = usize::try_from(end_addr.checked_sub(section.sh_addr)?).ok()?;
+    data.get(start..end)
+}
 
-    if let Some(sym) = symtab.iter().find(|sym| sym.st_value == address) {
-        if let Some(name) = elf.strtab.get_at(sym.st_name) {
-            return Some(name);
+/// Reads the NUL-terminated string stored at `addr` in a read-only section of the binary
+/// (e.g., `.rodata`), provided that it is made of printable characters.
+pub(crate) fn read_string(elf: &Elf, buffer: &[u8], addr: u64) -> Option<String> {
+    use goblin::elf::section_header::{SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE};
+
+    /// Longest string read, so that a wrong address does not produce a huge string.
+    const MAX_LENGTH: usize = 4096;
+
+    let section = elf.section_headers.iter().find(|sec| {
+        sec.sh_flags & u64::from(SHF_ALLOC) != 0
+            && sec.sh_flags & u64::from(SHF_WRITE | SHF_EXECINSTR) == 0
+            && addr >= sec.sh_addr
+            && addr < sec.sh_addr + sec.sh_size
+    })?;
+    let start = usize::try_from(addr - section.sh_addr).ok()?;
+    let data = section_data(section, buffer)?.get(start..)?;
+    let length = data.iter().take(MAX_LENGTH).position(|&byte| byte == 0)?;
+    let string = std::str::from_utf8(&data[..length]).ok()?;
+    (!string.is_empty() && !string.chars().any(char::is_control)).then(|| string.to_string())
+}
+
+/// Reader of the pointers stored in an ELF binary (e.g., GOT slots or literal pool entries).
+///
+/// When a dynamic relocation targets a slot, its resolved value is returned instead of the
+/// bytes of the file, which are usually zero in position-independent binaries. The
+/// relocations are indexed by the slot they fill when the reader is built, so that a pass
+/// reading many pointers builds a single reader.
+pub(crate) struct Pointers<'a> {
+    elf: &'a Elf<'a>,
+    buffer: &'a [u8],
+    /// Values of the slots filled by a dynamic relocation, `None` for undefined symbols.
+    relocated: HashMap<u64, Option<u64>>,
+}
+
+impl<'a> Pointers<'a> {
+    /// Indexes the dynamic relocations of `elf`, whose bytes are `buffer`.
+    pub(crate) fn new(elf: &'a Elf<'a>, buffer: &'a [u8]) -> Self {
+        let mut relocated = HashMap::new();
+        for reloc in elf.dynrelas.iter().chain(elf.dynrels.iter()) {
+            let addend = reloc.r_addend.unwrap_or(0);
+            let value = if reloc.r_sym != 0 {
+                elf.dynsyms
+                    .get(reloc.r_sym)
+                    .filter(|symbol| symbol.st_value != 0)
+                    .map(|symbol| symbol.st_value.wrapping_add_signed(addend))
+            } else if reloc.r_addend.is_some() {
+                u64::try_from(addend).ok()
+            } else {
+                // REL relocations (32-bit ARM, i386) keep the addend in the slot itself.
+                continue;
+            };
+            relocated.entry(reloc.r_offset).or_insert(value);
         }
-    } else if let Some(txt_sec) = find_text_section(elf) {
-        if let Some(name) = elf.strtab.get_at(txt_sec.sh_name) {
-            return Some(name);
+        Self {
+            elf,
+            buffer,
+            relocated,
         }
     }
-    None
+
+    /// Reads the pointer stored at `addr`.
+    pub(crate) fn read(&self, addr: u64) -> Option<u64> {
+        if let Some(&value) = self.relocated.get(&addr) {
+            return value;
+        }
+
+        let size: u64 = if self.elf.is_64 { 8 } else { 4 };
+        let section = self.elf.section_headers.iter().find(|sec| {
+            sec.sh_type != goblin::elf::section_header::SHT_NOBITS
+                && sec.sh_addr != 0
+                && addr >= sec.sh_addr
+                && addr.saturating_add(size) <= sec.sh_addr + sec.sh_size
+        })?;
+        let start = usize::try_from(addr - section.sh_addr).ok()?;
+        let end = start + usize::try_from(size).ok()?;
+        let bytes = section_data(section, self.buffer)?.get(start..end)?;
+        let read = |acc: u64, byte: &u8| (acc << 8) | u64::from(*byte);
+        Some(if self.elf.little_endian {
+            bytes.iter().rev().fold(0, read)
+        } else {
+            bytes.iter().fold(0, read)
+        })
+    }
 }
 
 fn get_file_size(elf_path: &str) -> Result<u64> {
@@ -186,7 +291,7 @@ fn get_name(elf_path: &str) -> Result<&str> {
     }
 }
 
-fn is_stripped(elf: &Elf) -> bool {
+pub(crate) fn is_stripped(elf: &Elf) -> bool {
     match elf.header.e_ident[goblin::elf::header::EI_CLASS] {
         goblin::elf::header::ELFCLASS64 | goblin::elf::header::ELFCLASS32 => {
             !has_sections(elf, goblin::elf::section_header::SHT_SYMTAB)
@@ -196,16 +301,8 @@ fn is_stripped(elf: &Elf) -> bool {
     }
 }
 
-fn get_architecture<'a>(elf: &'a Elf<'a>) -> Result<&'a str> {
-    match elf.header.e_machine {
-        goblin::elf::header::EM_X86_64 => Ok("x86_64"),
-        goblin::elf::header::EM_ARM => Err(Error::InvalidFormat(goblin::error::Error::Malformed(
-            "ARM architecture currently not supported.".to_string(),
-        ))),
-        _ => Err(Error::InvalidFormat(goblin::error::Error::Malformed(
-            "Architecture currently not supported.".to_string(),
-        ))),
-    }
+fn get_architecture(elf: &Elf) -> Result<&'static str> {
+    Ok(Arch::from_elf(elf)?.name())
 }
 
 fn get_file_type<'a>(elf: &'a Elf<'a>) -> Result<&'a str> {
@@ -238,76 +335,402 @@ fn is_pie(elf: &Elf) -> bool {
     }
 }
 
-// fn get_language(elf_path: &str) -> Result<String> {
-//     let file = fs::File::open(elf_path)?;
-//     let mmap = unsafe { memmap2::Mmap::map(&file)? };
-//     let object = object::File::parse(&*mmap)?;
-//     let endian = if object.is_little_endian() {
-//         gimli::RunTimeEndian::Little
-//     } else {
-//         gimli::RunTimeEndian::Big
-//     };
-
-//     let language = code_language(&object, endian)?;
-//     Ok(language)
-// }
-
-// fn code_language<'b>(object: &'b object::File<'b>, endian: gimli::RunTimeEndian) -> Result<String> {
-//     let load_section = |id: gimli::SectionId| -> Result<borrow::Cow<[u8]>> {
-//         match object.section_by_name(id.name()) {
-//             Some(ref section) => Ok(section
-//                 .uncompressed_data()
-//                 .unwrap_or(borrow::Cow::Borrowed(&[][..]))),
-//             None => Ok(borrow::Cow::Borrowed(&[][..])),
-//         }
-//     };
-//     let dwarf_sections = DwarfSections::load(&load_section)?;
-//     let borrow_section: &dyn for<'a> Fn(&'a borrow::Cow<[u8]>) -> EndianSlice<'a, RunTimeEndian> =
-//         &|section| EndianSlice::new(section, endian);
-//     let dwarf = dwarf_sections.borrow(&borrow_section);
-//     let mut iter = dwarf.units();
-//     let mut language_counts = HashMap::new();
-
-//     while let Some(header) = iter.next()? {
-//         let unit = dwarf.unit(header)?;
-//         let mut entries = unit.entries();
-
-//         while let Some((_, entry)) = entries.next_dfs()? {
-//             if let Some(language_attr) = entry.attr_value(gimli::DW_AT_language)? {
-//                 let gimli::AttributeValue::Language(language) = language_attr else {
-//                     continue;
-//                 };
-//                 increment_language_count(&mut language_counts, &language.to_string());
-//             }
-//         }
-//     }
-//     let mut max_count = 0;
-//     let mut max_language = String::new();
-
-//     // The presence of C99 in the Rust program is due to the musl library, used to statically compile the binary
-//     if language_counts.contains_key("DW_LANG_C99") && language_counts.contains_key("DW_LANG_Rust") {
-//         language_counts.remove_entry("DW_LANG_C99");
-//     }
-//     for (language, count) in language_counts {
-//         if count > max_count {
-//             max_count = count;
-//             max_language.clone_from(&language);
-//         }
-//     }
-//     let lang = match max_language.strip_prefix("DW_LANG_") {
-//         Some(stripped_lang) => stripped_lang.to_owned(),
-//         None => return Err(Error::LangNotFound),
-//     };
-//     Ok(lang)
-// }
-
-// fn increment_language_count(map: &mut HashMap<String, u32>, language: &str) {
-//     let count = map.entry(language.to_string()).or_insert(0);
-//     *count += 1;
-// }
+fn get_language(elf: &Elf, elf_path: &str) -> Result<(String, BTreeMap<String, usize>)> {
+    let file = File::open(elf_path)?;
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    let object = object::File::parse(&*mmap)?;
+    let endian = if object.is_little_endian() {
+        gimli::RunTimeEndian::Little
+    } else {
+        gimli::RunTimeEndian::Big
+    };
+
+    let languages = code_language(&object, endian);
+    if languages.is_empty() {
+        return Ok((symbol_language(elf).to_string(), languages));
+    }
+    let language = primary_language(&languages).ok_or(Error::LangNotFound)?;
+    Ok((language, languages))
+}
+
+fn code_language(object: &object::File, endian: RunTimeEndian) -> BTreeMap<String, usize> {
+    // `uncompressed_data` transparently handles both `SHF_COMPRESSED` and `.zdebug_*` sections.
+    let load_section = |id: gimli::SectionId| -> Result<Cow<[u8]>> {
+        match object.section_by_name(id.name()) {
+            Some(ref section) => Ok(section
+                .uncompressed_data()
+                .unwrap_or(Cow::Borrowed(&[][..]))),
+            None => Ok(Cow::Borrowed(&[][..])),
+        }
+    };
+    let mut language_counts = BTreeMap::new();
+    let Ok(dwarf_sections) = DwarfSections::load(&load_section) else {
+        return language_counts;
+    };
+    let dwarf = dwarf_sections.borrow(|section| EndianSlice::new(section, endian));
+
+    // Only the root DIE of each compilation unit carries `DW_AT_language`. A malformed unit
+    // stops the walk but keeps the units collected so far.
+    let mut iter = dwarf.units();
+    while let Ok(Some(header)) = iter.next() {
+        let Ok(unit) = dwarf.unit(header) else {
+            break;
+        };
+        let mut entries = unit.entries();
+        let Ok(Some((_, entry))) = entries.next_dfs() else {
+            continue;
+        };
+        if let Ok(Some(gimli::AttributeValue::Language(language))) =
+            entry.attr_value(gimli::DW_AT_language)
+        {
+            let name = language.static_string().map_or_else(
+                || format!("0x{:x}", language.0),
+                |name| name.strip_prefix("DW_LANG_").unwrap_or(name).to_string(),
+            );
+            *language_counts.entry(name).or_insert(0) += 1;
+        }
+    }
+
+    language_counts
+}
+
+/// Picks the language reported in [`BasicInfo`] from the compilation units breakdown.
+///
+/// Dialects are grouped by family (e.g., `C99` and `C11` are both C) and assembly units are
+/// ignored unless nothing else is present. A single family is reported with its most common
+/// dialect, while several families are reported as mixed (e.g., `Mixed (Rust, C99)`).
+fn primary_language(languages: &BTreeMap<String, usize>) -> Option<String> {
+    let mut families: BTreeMap<&str, (&str, usize, usize)> = BTreeMap::new();
+    for (language, &count) in languages {
+        let family = language_family(language);
+        let entry = families.entry(family).or_insert((language, count, 0));
+        if count > entry.1 {
+            entry.0 = language;
+            entry.1 = count;
+        }
+        entry.2 += count;
+    }
+    if families.len() > 1 {
+        families.remove("Assembly");
+    }
+
+    let mut dominant: Vec<_> = families.into_values().collect();
+    dominant.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)));
+    match dominant.as_slice() {
+        [] => None,
+        [(language, _, _)] => Some((*language).to_string()),
+        _ => {
+            let names: Vec<&str> = dominant.iter().map(|(language, _, _)| *language).collect();
+            Some(format!("Mixed ({})", names.join(", ")))
+        }
+    }
+}
+
+fn language_family(language: &str) -> &str {
+    if language.starts_with("C_plus_plus") {
+        "C++"
+    } else if matches!(language, "C" | "C89" | "C99" | "C11" | "C17") {
+        "C"
+    } else if language.contains("Assembler") || language.contains("Assembly") {
+        "Assembly"
+    } else {
+        language
+    }
+}
+
+/// Guesses the language from the symbols when the binary has no usable DWARF information.
+fn symbol_language(elf: &Elf) -> &'static str {
+    let has_section = |name: &str| {
+        elf.section_headers
+            .iter()
+            .any(|sec| elf.shdr_strtab.get_at(sec.sh_name) == Some(name))
+    };
+    if has_section(".rustc") {
+        return "Rust";
+    }
+
+    let names = elf
+        .syms
+        .iter()
+        .filter_map(|sym| elf.strtab.get_at(sym.st_name))
+        .chain(
+            elf.dynsyms
+                .iter()
+                .filter_map(|sym| elf.dynstrtab.get_at(sym.st_name)),
+        );
+    let mut cpp = false;
+    for name in names {
+        if name.starts_with("_R") || name == "rust_begin_unwind" || is_legacy_rust_symbol(name) {
+            return "Rust";
+        }
+        cpp |= name.starts_with("_Z");
+    }
+    if cpp {
+        "C++"
+    } else {
+        "C"
+    }
+}
+
+pub(crate) fn is_legacy_rust_symbol(name: &str) -> bool {
+    // Legacy Rust symbols end with a `17h<16 hex digits>E` hash segment.
+    name.starts_with("_ZN")
+        && name
+            .strip_suffix('E')
+            .and_then(|name| name.get(name.len().saturating_sub(19)..))
+            .and_then(|hash| hash.strip_prefix("17h"))
+            .is_some_and(|hash| hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+}
 
 fn has_sections(elf: &Elf, section_type: u32) -> bool {
     elf.section_headers
         .iter()
         .any(|section| section.sh_type == section_type)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn languages(units: &[(&str, usize)]) -> BTreeMap<String, usize> {
+        units
+            .iter()
+            .map(|(language, count)| ((*language).to_string(), *count))
+            .collect()
+    }
+
+    /// Compresses `data` into a zlib stream made of a single stored block.
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let length = u16::try_from(data.len()).unwrap();
+        let mut stream = vec![0x78, 0x01, 0x01];
+        stream.extend(length.to_le_bytes());
+        stream.extend((!length).to_le_bytes());
+        stream.extend(data);
+        let (a, b) = data.iter().fold((1_u32, 0_u32), |(a, b), &byte| {
+            let a = (a + u32::from(byte)) % 65521;
+            (a, (b + a) % 65521)
+        });
+        stream.extend(((b << 16) | a).to_be_bytes());
+        stream
+    }
+
+    /// How the DWARF sections of [`elf_with_units`] are stored.
+    #[derive(Clone, Copy)]
+    enum Storage {
+        Plain,
+        /// `SHF_COMPRESSED` sections, starting with an `Elf64_Chdr`.
+        Compressed,
+        /// GNU `.zdebug_*` sections, starting with `ZLIB` and the uncompressed size.
+        Zdebug,
+    }
+
+    /// Builds a little-endian x86-64 ELF file holding one DWARF compilation unit for every
+    /// language of `languages`, and a section for every name of `extra_sections`.
+    fn elf_with_units(
+        languages: &[gimli::DwLang],
+        storage: Storage,
+        extra_sections: &[&str],
+    ) -> Vec<u8> {
+        use gimli::write::{AttributeValue, Dwarf, EndianVec, LineProgram, Sections, Unit};
+
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = Dwarf::new();
+        for &language in languages {
+            let id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+            let unit = dwarf.units.get_mut(id);
+            let root = unit.root();
+            unit.get_mut(root)
+                .set(gimli::DW_AT_language, AttributeValue::Language(language));
+        }
+        let mut sections = Sections::new(EndianVec::new(gimli::LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let mut contents: Vec<(String, u64, Vec<u8>)> = Vec::new();
+        sections
+            .for_each(|id, data| {
+                let data = data.slice();
+                if !data.is_empty() {
+                    contents.push(match storage {
+                        Storage::Plain => (id.name().to_string(), 0, data.to_vec()),
+                        Storage::Compressed => {
+                            let mut section = Vec::new();
+                            section.extend(1_u32.to_le_bytes()); // ELFCOMPRESS_ZLIB
+                            section.extend(0_u32.to_le_bytes());
+                            section.extend((data.len() as u64).to_le_bytes());
+                            section.extend(1_u64.to_le_bytes());
+                            section.extend(zlib(data));
+                            let flags = goblin::elf::section_header::SHF_COMPRESSED;
+                            (id.name().to_string(), u64::from(flags), section)
+                        }
+                        Storage::Zdebug => {
+                            let mut section = b"ZLIB".to_vec();
+                            section.extend((data.len() as u64).to_be_bytes());
+                            section.extend(zlib(data));
+                            (id.name().replacen(".debug", ".zdebug", 1), 0, section)
+                        }
+                    });
+                }
+                Ok::<(), gimli::write::Error>(())
+            })
+            .unwrap();
+        for name in extra_sections {
+            contents.push(((*name).to_string(), 0, vec![0; 8]));
+        }
+
+        // Every section is named in `.shstrtab`, the last one.
+        let mut shstrtab = vec![0];
+        let mut name_offsets = Vec::new();
+        for name in contents
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .chain([".shstrtab"])
+        {
+            name_offsets.push(u32::try_from(shstrtab.len()).unwrap());
+            shstrtab.extend(name.bytes().chain([0]));
+        }
+        let mut sections: Vec<(u32, u64, &[u8])> = contents
+            .iter()
+            .map(|(_, flags, data)| (SHT_PROGBITS, *flags, data.as_slice()))
+            .collect();
+        sections.push((goblin::elf::section_header::SHT_STRTAB, 0, &shstrtab));
+
+        let mut file = vec![0; 64];
+        let mut headers = vec![[0_u8; 64]];
+        for (&name, (sh_type, flags, data)) in name_offsets.iter().zip(sections) {
+            let mut header = [0_u8; 64];
+            header[..4].copy_from_slice(&name.to_le_bytes());
+            header[4..8].copy_from_slice(&sh_type.to_le_bytes());
+            header[8..16].copy_from_slice(&flags.to_le_bytes());
+            header[24..32].copy_from_slice(&(file.len() as u64).to_le_bytes());
+            header[32..40].copy_from_slice(&(data.len() as u64).to_le_bytes());
+            header[48..56].copy_from_slice(&1_u64.to_le_bytes());
+            headers.push(header);
+            file.extend(data);
+        }
+
+        let shoff = file.len() as u64;
+        let shnum = u16::try_from(headers.len()).unwrap();
+        file.extend(headers.concat());
+        let mut header = vec![0x7f, b'E', b'L', b'F', 2, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        header.extend(goblin::elf::header::ET_EXEC.to_le_bytes());
+        header.extend(goblin::elf::header::EM_X86_64.to_le_bytes());
+        header.extend(1_u32.to_le_bytes());
+        header.extend([0; 16]); // entry point, program headers
+        header.extend(shoff.to_le_bytes());
+        header.extend(0_u32.to_le_bytes());
+        header.extend([64, 0, 0, 0, 0, 0, 64, 0]); // header sizes, no program header
+        header.extend(shnum.to_le_bytes());
+        header.extend((shnum - 1).to_le_bytes());
+        file[..64].copy_from_slice(&header);
+        file
+    }
+
+    fn code_languages(file: &[u8]) -> BTreeMap<String, usize> {
+        code_language(&object::File::parse(file).unwrap(), RunTimeEndian::Little)
+    }
+
+    #[test]
+    fn test_code_language() {
+        let units = [gimli::DW_LANG_C99, gimli::DW_LANG_Rust, gimli::DW_LANG_C99];
+        let expected = BTreeMap::from([("C99".to_string(), 2), ("Rust".to_string(), 1)]);
+        for storage in [Storage::Plain, Storage::Compressed, Storage::Zdebug] {
+            assert_eq!(
+                code_languages(&elf_with_units(&units, storage, &[])),
+                expected
+            );
+        }
+
+        // Languages without a name are reported by their code.
+        let units = [gimli::DwLang(0x9000)];
+        assert_eq!(
+            code_languages(&elf_with_units(&units, Storage::Plain, &[])),
+            BTreeMap::from([("0x9000".to_string(), 1)])
+        );
+        assert!(code_languages(&elf_with_units(&[], Storage::Plain, &[])).is_empty());
+    }
+
+    #[test]
+    fn test_get_language() {
+        let path = std::env::temp_dir().join("inspect_get_language");
+        let language = |file: &[u8]| {
+            std::fs::write(&path, file).unwrap();
+            let elf = parse_elf(file).unwrap();
+            get_language(&elf, path.to_str().unwrap()).unwrap()
+        };
+
+        let units = [gimli::DW_LANG_C11, gimli::DW_LANG_C99, gimli::DW_LANG_C11];
+        let (primary, languages) = language(&elf_with_units(&units, Storage::Compressed, &[]));
+        assert_eq!(primary, "C11");
+        assert_eq!(languages.values().sum::<usize>(), 3);
+
+        // Without `.debug_info` the language is guessed from the symbols and sections.
+        let (primary, languages) = language(&elf_with_units(&[], Storage::Plain, &[".rustc"]));
+        assert_eq!(primary, "Rust");
+        assert!(languages.is_empty());
+        let (primary, _) = language(&elf_with_units(&[], Storage::Plain, &[]));
+        assert_eq!(primary, "C");
+    }
+
+    #[test]
+    fn test_primary_language() {
+        assert_eq!(
+            primary_language(&languages(&[
+                ("C11", 120),
+                ("C99", 3),
+                ("Mips_Assembler", 8)
+            ])),
+            Some("C11".to_string())
+        );
+        assert_eq!(
+            primary_language(&languages(&[("Rust", 14), ("C99", 90)])),
+            Some("Mixed (C99, Rust)".to_string())
+        );
+        assert_eq!(
+            primary_language(&languages(&[("Mips_Assembler", 2)])),
+            Some("Mips_Assembler".to_string())
+        );
+        assert_eq!(primary_language(&BTreeMap::new()), None);
+    }
+
+    #[test]
+    fn test_is_legacy_rust_symbol() {
+        assert!(is_legacy_rust_symbol(
+            "_ZN4core3fmt5write17h0123456789abcdefE"
+        ));
+        assert!(!is_legacy_rust_symbol("_ZN3foo3barEv"));
+        assert!(!is_legacy_rust_symbol("main"));
+    }
+
+    #[test]
+    fn test_pointers() {
+        // A relative relocation of the slot `0x2000`, followed by the `.data` section loaded at
+        // `0x2000`, whose second slot holds `0x5678`.
+        let mut buffer = Vec::new();
+        buffer.extend(0x2000_u64.to_le_bytes());
+        buffer.extend(u64::from(goblin::elf::reloc::R_X86_64_RELATIVE).to_le_bytes());
+        buffer.extend(0x1234_i64.to_le_bytes());
+        buffer.extend([0; 8]);
+        buffer.extend(0x5678_u64.to_le_bytes());
+
+        let ctx = goblin::container::Ctx::default();
+        let mut elf = Elf::lazy_parse(goblin::elf::Header::new(ctx)).unwrap();
+        elf.is_64 = true;
+        elf.little_endian = true;
+        elf.dynrelas = goblin::elf::RelocSection::parse(&buffer, 0, 24, true, ctx).unwrap();
+        elf.section_headers.push(SectionHeader {
+            sh_type: SHT_PROGBITS,
+            sh_addr: 0x2000,
+            sh_offset: 24,
+            sh_size: 16,
+            ..Default::default()
+        });
+
+        let pointers = Pointers::new(&elf, &buffer);
+        assert_eq!(pointers.read(0x2000), Some(0x1234));
+        assert_eq!(pointers.read(0x2008), Some(0x5678));
+        assert_eq!(pointers.read(0x2010), None);
+    }
+}
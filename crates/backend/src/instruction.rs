@@ -0,0 +1,536 @@
+use capstone::{
+    arch::{
+        arm::ArmOperandType,
+        arm64::{Arm64OperandType, Arm64Shift},
+        riscv::RiscVOperand,
+        x86::X86OperandType,
+        ArchOperand,
+    },
+    Capstone, Insn, InsnGroupType, RegAccessType, RegId,
+};
+
+use crate::{arch::Arch, error::Result, FunctionId};
+
+/// Classes of instructions transferring control, as reported by Capstone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    /// Calls a function and returns (`call`, `bl`, `blr`, `blx`, `jal`).
+    Call,
+    /// Jumps without returning, conditionally or not.
+    Jump,
+    /// Returns to the caller.
+    Return,
+    /// Raises a software interrupt or traps into the kernel (`int`, `syscall`, `svc`, `ecall`).
+    Interrupt,
+    /// Transfers control to an address relative to the instruction.
+    BranchRelative,
+}
+
+/// An operand of an [`Instruction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    /// A register, named as in the disassembly (e.g., `rdi`, `w8`, `a7`).
+    Register(String),
+    /// An immediate value, shifted by its `lsl` amount on `AArch64` (`#0x40, lsl #16`). The
+    /// immediates of relative branches are absolute targets.
+    Immediate(i64),
+    /// A memory operand.
+    Memory(Memory),
+}
+
+/// A memory operand: `segment:[base + index * scale + displacement]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Memory {
+    pub segment: Option<String>,
+    pub base: Option<String>,
+    pub index: Option<String>,
+    pub scale: i32,
+    pub displacement: i64,
+    /// The number of bytes accessed, on x86; 0 on the other architectures, where the size
+    /// of loads and stores is given by their mnemonic (`strh`, `sw`).
+    pub size: u8,
+}
+
+impl Memory {
+    /// Returns the address of the operand, given the values of its registers.
+    pub fn address(&self, register: impl Fn(&str) -> Option<u64>) -> Option<u64> {
+        let mut address = self.displacement.cast_unsigned();
+        if let Some(base) = &self.base {
+            address = address.wrapping_add(register(base)?);
+        }
+        if let Some(index) = &self.index {
+            address = address
+                .wrapping_add(register(index)?.wrapping_mul(i64::from(self.scale).cast_unsigned()));
+        }
+        Some(address)
+    }
+}
+
+/// A disassembled instruction, described by the operand details of Capstone.
+///
+/// # Overview
+///
+/// The analysis reads the registers, immediates and memory operands of the instructions from
+/// the details computed by Capstone rather than from their textual disassembly, so that it
+/// does not depend on the syntax of the disassembler nor on the way numbers are printed.
+///
+/// Operands are listed in Intel order, with the destination first, whatever the syntax of the
+/// disassembly: the operands of x86 instructions, disassembled in AT&T syntax, are reversed.
+/// RISC-V loads and stores, whose address Capstone splits into an immediate and a register
+/// (`8(sp)`), get a [`Memory`] operand like on the other architectures.
+///
+/// Capstone does not classify most RISC-V control transfers: `jal` and `jalr` are calls when
+/// they link a register other than `zero`, and `ecall` is an interrupt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: u64,
+    pub size: u64,
+    /// The mnemonic, as written in the disassembly (see [`Arch`]).
+    pub mnemonic: String,
+    /// The operands, as written in the disassembly.
+    pub op_str: String,
+    pub operands: Vec<Operand>,
+    pub groups: Vec<Group>,
+    /// The register written by the instruction, if any.
+    pub destination: Option<String>,
+    /// The value written to the destination, when known to the analysis (see
+    /// [`analyse_functions`](crate::analyse::analyse_functions)).
+    pub value: Option<u64>,
+    /// The function called by the instruction, when resolved by the analysis to a function
+    /// of the call graph.
+    pub callee: Option<FunctionId>,
+}
+
+impl Instruction {
+    /// Builds the model of an instruction disassembled by `cs`, which must have been built
+    /// with details enabled.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::Capstone`](crate::error::Error::Capstone) if the details of the
+    ///   instruction are not available.
+    pub fn new(cs: &Capstone, arch: Arch, insn: &Insn) -> Result<Self> {
+        let detail = cs.insn_detail(insn)?;
+        let mnemonic = arch.mnemonic(cs, insn);
+
+        let mut operands = Vec::new();
+        let mut destination = None;
+        for (operand, written) in detail
+            .arch_detail()
+            .operands()
+            .into_iter()
+            .filter_map(|op| operand(cs, op))
+        {
+            if let (Operand::Register(name), true) = (&operand, written) {
+                destination = Some(name.clone());
+            }
+            operands.push(operand);
+        }
+
+        let mut groups: Vec<Group> = detail
+            .groups()
+            .iter()
+            .filter_map(|group| match u32::from(group.0) {
+                InsnGroupType::CS_GRP_CALL => Some(Group::Call),
+                InsnGroupType::CS_GRP_JUMP => Some(Group::Jump),
+                InsnGroupType::CS_GRP_RET => Some(Group::Return),
+                InsnGroupType::CS_GRP_INT => Some(Group::Interrupt),
+                InsnGroupType::CS_GRP_BRANCH_RELATIVE => Some(Group::BranchRelative),
+                _ => None,
+            })
+            .collect();
+
+        match arch {
+            Arch::X86 | Arch::X86_64 => operands.reverse(),
+            Arch::AArch64 | Arch::Arm => {}
+            Arch::RiscV64 => riscv_details(insn.address(), &mnemonic, &mut operands, &mut groups),
+        }
+        if !matches!(arch, Arch::X86 | Arch::X86_64) && writes_first_operand(arch, &mnemonic) {
+            if let Some(Operand::Register(name)) = operands.first() {
+                destination = Some(name.clone());
+            }
+        }
+
+        Ok(Self {
+            address: insn.address(),
+            size: insn.len() as u64,
+            mnemonic,
+            op_str: insn.op_str().unwrap_or_default().to_string(),
+            operands,
+            groups,
+            destination,
+            value: None,
+            callee: None,
+        })
+    }
+
+    /// Returns the address of the instruction following this one.
+    #[must_use]
+    pub fn next(&self) -> u64 {
+        self.address + self.size
+    }
+
+    /// Whether the instruction belongs to `group`.
+    #[must_use]
+    pub fn is(&self, group: Group) -> bool {
+        self.groups.contains(&group)
+    }
+
+    /// Whether the instruction transfers control to a function and returns.
+    #[must_use]
+    pub fn is_call(&self) -> bool {
+        self.is(Group::Call)
+    }
+
+    /// Whether the instruction jumps without returning, conditionally or not (`jmp`, `jne`,
+    /// `b`, `b.eq`, `cbz`, `j`, `beq`).
+    #[must_use]
+    pub fn is_jump(&self) -> bool {
+        self.is(Group::Jump) && !self.is(Group::Call) && !self.is(Group::Return)
+    }
+
+    /// Whether the instruction traps into the kernel (`syscall`, `int $0x80`, `sysenter`,
+    /// `call *%gs:0x10`, `svc`, `ecall`).
+    #[must_use]
+    pub fn is_syscall(&self, arch: Arch) -> bool {
+        match arch {
+            Arch::X86 => {
+                (self.is(Group::Interrupt)
+                    && (self.mnemonic == "sysenter"
+                        || self.operands == [Operand::Immediate(0x80)]))
+                    // The vDSO entry point stored in the thread control block.
+                    || (self.is_call()
+                        && self.memory().is_some_and(|mem| {
+                            mem.segment.as_deref() == Some("gs")
+                                && mem.base.is_none()
+                                && mem.index.is_none()
+                                && mem.displacement == 0x10
+                        }))
+            }
+            Arch::X86_64 => self.is(Group::Interrupt) && self.mnemonic == "syscall",
+            Arch::AArch64 | Arch::Arm => self.is(Group::Interrupt) && self.mnemonic == "svc",
+            Arch::RiscV64 => self.is(Group::Interrupt) && self.mnemonic == "ecall",
+        }
+    }
+
+    /// Returns the target of a direct call or jump.
+    #[must_use]
+    pub fn target(&self) -> Option<u64> {
+        if !self.is(Group::BranchRelative) {
+            return None;
+        }
+        match self.operands.last()? {
+            Operand::Immediate(target) => Some(target.cast_unsigned()),
+            _ => None,
+        }
+    }
+
+    /// Returns the first immediate operand of the instruction.
+    #[must_use]
+    pub fn immediate(&self) -> Option<i64> {
+        self.operands.iter().find_map(|op| match op {
+            Operand::Immediate(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// Returns the first memory operand of the instruction.
+    #[must_use]
+    pub fn memory(&self) -> Option<&Memory> {
+        self.operands.iter().find_map(|op| match op {
+            Operand::Memory(mem) => Some(mem),
+            _ => None,
+        })
+    }
+
+    /// Returns the address referenced by an x86-64 RIP-relative memory operand
+    /// (e.g., `lea 0x2fe3(%rip), %rdi`), relative to the next instruction.
+    #[must_use]
+    pub fn rip_relative(&self) -> Option<u64> {
+        let mem = self
+            .memory()
+            .filter(|mem| mem.base.as_deref() == Some("rip") && mem.index.is_none())?;
+        Some(self.next().wrapping_add(mem.displacement.cast_unsigned()))
+    }
+}
+
+/// Disassembles `code`, loaded at `address`, into [`Instruction`]s.
+///
+/// # Errors
+///
+/// - Returns [`Error::Capstone`](crate::error::Error::Capstone) if the code cannot be
+///   disassembled.
+pub(crate) fn disassemble(
+    cs: &Capstone,
+    arch: Arch,
+    code: &[u8],
+    address: u64,
+) -> Result<Vec<Instruction>> {
+    cs.disasm_all(code, address)?
+        .iter()
+        .map(|insn| Instruction::new(cs, arch, insn))
+        .collect()
+}
+
+/// Converts an operand of Capstone, and tells whether the instruction writes it. Only x86
+/// operands carry their access.
+fn operand(cs: &Capstone, operand: ArchOperand) -> Option<(Operand, bool)> {
+    let register = |reg: RegId| (reg != RegId::INVALID_REG).then(|| register_name(cs, reg));
+    let operand = match operand {
+        ArchOperand::X86Operand(op) => {
+            let written = op.access.is_some_and(RegAccessType::is_writable);
+            let operand = match op.op_type {
+                X86OperandType::Reg(reg) => Operand::Register(register_name(cs, reg)),
+                X86OperandType::Imm(value) => Operand::Immediate(value),
+                X86OperandType::Mem(mem) => Operand::Memory(Memory {
+                    segment: register(mem.segment()),
+                    base: register(mem.base()),
+                    index: register(mem.index()),
+                    scale: mem.scale(),
+                    displacement: mem.disp(),
+                    size: op.size,
+                }),
+                X86OperandType::Invalid => return None,
+            };
+            return Some((operand, written));
+        }
+        ArchOperand::ArmOperand(op) => match op.op_type {
+            ArmOperandType::Reg(reg) => Operand::Register(register_name(cs, reg)),
+            // Immediates are 32-bit values, printed unsigned (`#0x80000000`).
+            ArmOperandType::Imm(value) => Operand::Immediate(i64::from(value) & 0xffff_ffff),
+            ArmOperandType::Mem(mem) => Operand::Memory(Memory {
+                base: register(mem.base()),
+                index: register(mem.index()),
+                // `[r2, -r3]` subtracts the index.
+                scale: if op.subtracted {
+                    -mem.scale()
+                } else {
+                    mem.scale()
+                },
+                displacement: i64::from(mem.disp()),
+                ..Memory::default()
+            }),
+            _ => return None,
+        },
+        ArchOperand::Arm64Operand(op) => match op.op_type {
+            Arm64OperandType::Reg(reg) => Operand::Register(register_name(cs, reg)),
+            Arm64OperandType::Imm(value) => Operand::Immediate(match op.shift {
+                Arm64Shift::Lsl(shift) => value.checked_shl(shift).unwrap_or(0),
+                _ => value,
+            }),
+            Arm64OperandType::Mem(mem) => Operand::Memory(Memory {
+                base: register(mem.base()),
+                index: register(mem.index()),
+                scale: match op.shift {
+                    Arm64Shift::Lsl(shift) => 1_i32.checked_shl(shift).unwrap_or(0),
+                    _ => 1,
+                },
+                displacement: i64::from(mem.disp()),
+                ..Memory::default()
+            }),
+            _ => return None,
+        },
+        ArchOperand::RiscVOperand(op) => match op {
+            RiscVOperand::Reg(reg) => Operand::Register(register_name(cs, reg)),
+            RiscVOperand::Imm(value) => Operand::Immediate(value),
+            RiscVOperand::Mem(mem) => Operand::Memory(Memory {
+                base: register(mem.base()),
+                scale: 1,
+                displacement: mem.disp(),
+                ..Memory::default()
+            }),
+            RiscVOperand::Invalid => return None,
+        },
+        _ => return None,
+    };
+    Some((operand, false))
+}
+
+fn register_name(cs: &Capstone, reg: RegId) -> String {
+    cs.reg_name(reg).unwrap_or_default()
+}
+
+/// Conditional branches of RISC-V, including their pseudo-instructions.
+const RISCV_BRANCHES: [&str; 16] = [
+    "beq", "bne", "blt", "bge", "bltu", "bgeu", "beqz", "bnez", "blez", "bgez", "bltz", "bgtz",
+    "bgt", "ble", "bgtu", "bleu",
+];
+
+/// Completes the details that Capstone leaves out for RISC-V instructions.
+fn riscv_details(
+    address: u64,
+    mnemonic: &str,
+    operands: &mut Vec<Operand>,
+    groups: &mut Vec<Group>,
+) {
+    let mnemonic = strip_compressed(mnemonic);
+    if is_riscv_memory_access(mnemonic) {
+        if let [.., Operand::Immediate(displacement), Operand::Register(base)] = operands.as_slice()
+        {
+            let memory = Operand::Memory(Memory {
+                base: Some(base.clone()),
+                scale: 1,
+                displacement: *displacement,
+                ..Memory::default()
+            });
+            operands.truncate(operands.len() - 2);
+            operands.push(memory);
+        }
+    }
+
+    // `jal offset` and `c.jalr rs` link `ra`; `jalr zero, ...` is an indirect jump (`jr`,
+    // `ret`, tail calls).
+    let links = match operands.as_slice() {
+        [Operand::Register(rd), _, ..] => rd != "zero",
+        _ => true,
+    };
+    let relative = matches!(mnemonic, "jal" | "j") || RISCV_BRANCHES.contains(&mnemonic);
+    let group = match mnemonic {
+        "jal" | "jalr" if links => Some(Group::Call),
+        "ret" => Some(Group::Return),
+        "jal" | "jalr" | "j" | "jr" => Some(Group::Jump),
+        m if RISCV_BRANCHES.contains(&m) => Some(Group::Jump),
+        "ecall" => Some(Group::Interrupt),
+        _ => None,
+    };
+    if let Some(group) = group {
+        if !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+    if relative {
+        if let Some(Operand::Immediate(offset)) = operands.last_mut() {
+            *offset = address.cast_signed().wrapping_add(*offset);
+            if !groups.contains(&Group::BranchRelative) {
+                groups.push(Group::BranchRelative);
+            }
+        }
+    }
+}
+
+/// Whether a RISC-V instruction, stripped of its `c.` prefix, loads or stores memory.
+fn is_riscv_memory_access(mnemonic: &str) -> bool {
+    let mnemonic = mnemonic.strip_suffix("sp").unwrap_or(mnemonic);
+    let mnemonic = mnemonic.strip_prefix('f').unwrap_or(mnemonic);
+    matches!(
+        mnemonic,
+        "lb" | "lh" | "lw" | "ld" | "lbu" | "lhu" | "lwu" | "sb" | "sh" | "sw" | "sd"
+    )
+}
+
+/// Strips the `c.` prefix of the RISC-V compressed instructions, which behave as their
+/// uncompressed counterparts.
+pub(crate) fn strip_compressed(mnemonic: &str) -> &str {
+    mnemonic.strip_prefix("c.").unwrap_or(mnemonic)
+}
+
+/// Whether an instruction writes its first operand. Stores, comparisons, stack operations and
+/// branches do not.
+fn writes_first_operand(arch: Arch, mnemonic: &str) -> bool {
+    let mnemonic = strip_compressed(mnemonic);
+    if arch == Arch::RiscV64 {
+        return !(mnemonic.starts_with('b')
+            || matches!(
+                mnemonic,
+                "sb" | "sh"
+                    | "sw"
+                    | "sd"
+                    | "swsp"
+                    | "sdsp"
+                    | "fsw"
+                    | "fsd"
+                    | "fswsp"
+                    | "fsdsp"
+                    | "j"
+                    | "jr"
+                    | "ret"
+                    | "ecall"
+                    | "ebreak"
+            ));
+    }
+    !(mnemonic.starts_with("st")
+        || mnemonic.starts_with("b.")
+        || matches!(
+            mnemonic,
+            "b" | "br"
+                | "bx"
+                | "cbz"
+                | "cbnz"
+                | "tbz"
+                | "tbnz"
+                | "cmp"
+                | "cmn"
+                | "tst"
+                | "teq"
+                | "ret"
+                | "push"
+                | "pop"
+                | "svc"
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instructions(arch: Arch, code: &[u8], address: u64) -> Vec<Instruction> {
+        disassemble(&arch.capstone(false).unwrap(), arch, code, address).unwrap()
+    }
+
+    #[test]
+    fn test_x86_64_instruction_details() {
+        let code = [
+            0x48, 0x8d, 0x3d, 0xe3, 0x2f, 0x00, 0x00, // lea 0x2fe3(%rip), %rdi
+            0xb8, 0x3c, 0x00, 0x00, 0x00, // mov $0x3c, %eax
+            0xe8, 0xef, 0xff, 0xff, 0xff, // call 0x1000
+            0x0f, 0x05, // syscall
+            0xc3, // ret
+        ];
+        let insns = instructions(Arch::X86_64, &code, 0x1000);
+
+        assert_eq!(insns[0].rip_relative(), Some(0x3fea));
+        assert_eq!(insns[0].destination.as_deref(), Some("rdi"));
+        assert_eq!(
+            insns[1].operands,
+            vec![
+                Operand::Register("eax".to_string()),
+                Operand::Immediate(0x3c)
+            ]
+        );
+        assert_eq!(insns[1].destination.as_deref(), Some("eax"));
+        assert!(insns[2].is_call());
+        assert_eq!(insns[2].target(), Some(0x1000));
+        assert!(insns[3].is_syscall(Arch::X86_64));
+        assert!(!insns[3].is_call());
+        assert!(insns[4].is(Group::Return));
+        assert!(!insns[4].is_jump());
+    }
+
+    #[test]
+    fn test_riscv_instruction_details() {
+        let code = [
+            0x0c, 0x65, // c.ld a1, 8(a0)
+            0xef, 0xf0, 0x9f, 0xfe, // jal -0x18
+            0x67, 0x00, 0xe3, 0xfe, // jalr zero, t1, -0x12
+            0x73, 0x00, 0x00, 0x00, // ecall
+        ];
+        let insns = instructions(Arch::RiscV64, &code, 0x1_1000);
+
+        assert_eq!(
+            insns[0].memory(),
+            Some(&Memory {
+                base: Some("a0".to_string()),
+                scale: 1,
+                displacement: 8,
+                ..Memory::default()
+            })
+        );
+        assert_eq!(insns[0].destination.as_deref(), Some("a1"));
+        assert!(insns[1].is_call());
+        assert_eq!(insns[1].target(), Some(0x1_0fea));
+        assert!(!insns[2].is_call());
+        assert!(insns[2].is(Group::Jump));
+        assert!(insns[2].is_jump());
+        assert!(insns[3].is_syscall(Arch::RiscV64));
+    }
+}
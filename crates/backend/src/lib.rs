@@ -1,10 +1,29 @@
 pub mod analyse;
+pub mod arch;
+pub mod arguments;
+pub mod capabilities;
+pub mod categories;
 pub mod detect;
 pub mod digest;
+pub mod discover;
 pub mod entry;
 pub mod error;
+pub mod imports;
 pub mod inspect;
+pub mod instruction;
+pub mod manifest;
+pub mod reachability;
+pub mod roots;
+pub mod seccomp;
+pub mod symbols;
 pub mod syscall;
+pub mod threads;
+pub mod verify;
+pub mod wrappers;
+
+use std::collections::BTreeMap;
+
+use categories::SyscallCategory;
 
 /// Represents the basic metadata extracted from an ELF binary.
 ///
@@ -23,7 +42,10 @@ pub mod syscall;
 /// - `pie`: Indicates whether the binary is position-independent (true for PIE binaries).
 /// - `stripped`: Indicates whether the binary lacks debug symbols.
 /// - `static_linking`: A string indicating if the binary is statically or dynamically linked.
-/// - `language`: The programming language used to write the binary (e.g., `C`, `C++`).
+/// - `language`: The programming language used to write the binary (e.g., `C11`, `Rust`), or
+///   `Mixed (...)` when compilation units of several languages are linked together.
+/// - `languages`: The number of compilation units for each language, read from `DW_AT_language`.
+///   Empty when the binary carries no DWARF information and the language was guessed from its symbols.
 /// - `entry_point`: The address of the entry point in the binary.
 ///
 /// # Usage
@@ -44,6 +66,7 @@ pub struct BasicInfo<'a> {
     pub stripped: bool,
     pub static_linking: &'a str,
     pub language: String,
+    pub languages: BTreeMap<String, usize>,
     pub entry_point: u64,
 }
 impl<'a> BasicInfo<'a> {
@@ -78,6 +101,7 @@ impl<'a> BasicInfo<'a> {
             stripped: false,
             static_linking: "",
             language: String::new(),
+            languages: BTreeMap::new(),
             entry_point: 0,
         }
     }
@@ -164,6 +188,20 @@ impl<'a> BasicInfo<'a> {
         Self { language, ..self }
     }
 
+    /// Sets the number of compilation units for each language of the binary.
+    ///
+    /// # Arguments
+    ///
+    /// - `languages`: A map from language names to compilation unit counts.
+    ///
+    /// # Returns
+    ///
+    /// A new `BasicInfo` instance with the updated languages breakdown.
+    #[must_use]
+    pub fn languages(self, languages: BTreeMap<String, usize>) -> Self {
+        Self { languages, ..self }
+    }
+
     /// Sets the entry point address of the binary.
     ///
     /// # Arguments
@@ -196,6 +234,15 @@ impl<'a> BasicInfo<'a> {
     }
 }
 
+/// Stable identifier of a function: its start address.
+///
+/// Function tables such as the one returned by
+/// [`function_detection`](crate::detect::function_detection) are keyed by `FunctionId`, and
+/// [`FunctionNode::children`] refers to the called functions by `FunctionId`, so that functions
+/// sharing a name (C++ overloads, Rust monomorphisations, static functions of different
+/// translation units) are kept apart.
+pub type FunctionId = u64;
+
 /// Represents a node in the call tree of a binary's functions.
 ///
 /// # Overview
@@ -212,10 +259,26 @@ impl<'a> BasicInfo<'a> {
 /// - `start_addr`: The start address of the function in the binary.
 /// - `end_addr`: The end address of the function in the binary.
 /// - `invocation_entry`: The number of times this function is invoked by another function in the binary.
-/// - `jmp`:  The number of times the function is identified for the construction of its subtree.
-/// - `children`: A list of function names that are called by this function.
+/// - `jmp`: The number of tail calls of the function: jumps (`jmp`, `b`, conditional or not) to
+///   the start of another function, which returns directly to the caller of this function.
+/// - `children`: The identifiers (start addresses) of the functions called by this function,
+///   including the ones it tail-calls.
+/// - `tail_calls`: The children reached only through tail calls. Omitted from the JSON output
+///   when empty.
+/// - `spawns`: The threads this function starts, with the functions they run (see
+///   [`detect_thread_spawns`](crate::threads::detect_thread_spawns)). Omitted from the JSON
+///   output when empty.
 /// - `disassembly`: An optional field containing the disassembled machine code for the function.
+/// - `instructions`: The disassembled instructions of the function, with their operands (see
+///   [`Instruction`](crate::instruction::Instruction)). Not part of the JSON output.
 /// - `syscall`: Field set to false by default and indicating functions with system call invocations.
+/// - `syscalls`: The system calls issued by the function, one [`SyscallSite`] for each system
+///   call instruction, in address order.
+/// - `thumb`: Whether the function is made of Thumb instructions (32-bit ARM only). Omitted
+///   from the JSON output when false.
+/// - `import`: Whether the function is imported from a shared library, in which case it is
+///   named `name@VERSION` and has no disassembly (see [`Imports`](crate::imports::Imports)).
+///   Omitted from the JSON output when false.
 ///
 /// # See also
 ///
@@ -228,10 +291,20 @@ pub struct FunctionNode {
     pub end_addr: u64,
     pub invocation_entry: usize,
     pub jmp: usize,
-    pub children: Vec<String>,
+    pub children: Vec<FunctionId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tail_calls: Vec<FunctionId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spawns: Vec<threads::ThreadSpawn>,
     pub disassembly: Option<String>,
+    #[serde(skip)]
+    pub instructions: Vec<instruction::Instruction>,
     pub syscall: bool,
-    pub syscall_info: Option<SyscallInfo>,
+    pub syscalls: Vec<SyscallSite>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub thumb: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub import: bool,
 }
 impl FunctionNode {
     /// Creates a new `FunctionNode`.
@@ -267,9 +340,14 @@ impl FunctionNode {
             invocation_entry: 0,
             jmp: 0,
             children: Vec::new(),
+            tail_calls: Vec::new(),
+            spawns: Vec::new(),
             disassembly: None,
+            instructions: Vec::new(),
             syscall: false,
-            syscall_info: None,
+            syscalls: Vec::new(),
+            thumb: false,
+            import: false,
         }
     }
     /// Sets the disassembly for the function.
@@ -294,33 +372,99 @@ impl FunctionNode {
     pub fn set_disassembly(&mut self, disassembly: String) {
         self.disassembly = Some(disassembly);
     }
-    /// Sets the system call information for the function.
+    /// Records a system call issued by the function.
     ///
     /// # Arguments
     ///
-    /// - `syscall_info`: A `SyscallInfo` object containing information about the system call.
+    /// - `site`: A [`SyscallSite`] describing the system call instruction.
     ///
     /// # Example
     ///
     /// ```
-    /// use manifest_producer_backend::{FunctionNode, SyscallInfo};
+    /// use manifest_producer_backend::{
+    ///     categories::SyscallCategory, FunctionNode, SyscallInfo, SyscallSite,
+    /// };
     ///
     /// let syscall_info = SyscallInfo {
-    ///    id: 0,
-    ///   name: "read".to_string(),
-    ///  manpage: "https://manpages.debian.org/unstable/manpages-dev/read.2.en.html".to_string(),
+    ///     id: 0,
+    ///     name: "read".to_string(),
+    ///     manpage: "https://manpages.debian.org/unstable/manpages-dev/read.2.en.html".to_string(),
+    ///     arguments: Vec::new(),
+    ///     categories: vec![SyscallCategory::Filesystem],
     /// };
     ///
     /// let mut func_node = FunctionNode::new(
-    ///    "example_function".to_string(),
-    ///   0x1000,
-    /// 0x2000,
+    ///     "example_function".to_string(),
+    ///     0x1000,
+    ///     0x2000,
     /// );
-    /// func_node.set_syscall_info(syscall_info);
-    /// assert!(func_node.syscall_info.is_some());
+    /// func_node.add_syscall(SyscallSite {
+    ///     address: 0x1010,
+    ///     info: Some(syscall_info),
+    ///     resolved: true,
+    ///     implied_by: None,
+    /// });
+    /// assert_eq!(func_node.syscalls.len(), 1);
     /// ```
-    pub fn set_syscall_info(&mut self, syscall_info: SyscallInfo) {
-        self.syscall_info = Some(syscall_info);
+    pub fn add_syscall(&mut self, site: SyscallSite) {
+        self.syscalls.push(site);
+    }
+    /// Returns the names of the system calls issued by the function. The sites whose number
+    /// cannot be resolved are named [`DYNAMIC_SYSCALL`](crate::syscall::DYNAMIC_SYSCALL), and
+    /// the ones whose number is missing from the syscall table are skipped.
+    pub fn syscall_names(&self) -> impl Iterator<Item = &str> {
+        self.syscalls.iter().filter_map(SyscallSite::name)
+    }
+    /// Returns the identifiers of the functions run by the threads the function starts, each
+    /// once, in the order of the first thread running them.
+    pub fn thread_bodies(&self) -> impl Iterator<Item = FunctionId> + '_ {
+        self.spawns
+            .iter()
+            .enumerate()
+            .filter(|&(index, spawn)| !self.spawns[..index].iter().any(|s| s.body == spawn.body))
+            .map(|(_, spawn)| spawn.body)
+    }
+}
+
+/// A system call issued by a function.
+///
+/// # Overview
+///
+/// A function may issue several system calls (e.g., `socket`, `bind` and `listen`), and the
+/// number of some of them may not be recoverable statically (e.g., when it is loaded from
+/// memory). `SyscallSite` records every system call instruction together with the outcome of
+/// the recovery of its number, as well as the system calls implied by the calls to well-known
+/// wrapper functions (e.g., `write@GLIBC_2.2.5` or `std::fs::File::open`).
+///
+/// # Fields
+///
+/// - `address`: The address of the system call instruction.
+/// - `info`: The [`SyscallInfo`] of the system call, if its number was resolved and found in the
+///   syscall table of the architecture.
+/// - `resolved`: Whether the number of the system call was resolved.
+/// - `implied_by`: For a system call implied by a call to a wrapper function rather than
+///   directly observed (see [`detect_implied_syscalls`](crate::wrappers::detect_implied_syscalls)),
+///   the name of the wrapper; `address` is then the one of the call instruction. Omitted from
+///   the JSON output for the system call instructions.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SyscallSite {
+    pub address: u64,
+    pub info: Option<SyscallInfo>,
+    pub resolved: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub implied_by: Option<String>,
+}
+
+impl SyscallSite {
+    /// Returns the name of the system call, or
+    /// [`DYNAMIC_SYSCALL`](crate::syscall::DYNAMIC_SYSCALL) if its number was not resolved.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        match &self.info {
+            Some(info) => Some(&info.name),
+            None if !self.resolved => Some(syscall::DYNAMIC_SYSCALL),
+            None => None,
+        }
     }
 }
 
@@ -340,16 +484,23 @@ impl FunctionNode {
 /// - `id`: The unique numeric identifier of the system call.
 /// - `name`: The human-readable name of the system call (e.g., `"read"`, `"write"`).
 /// - `manpage`: A URL to the official Linux man page for the system call.
+/// - `arguments`: The arguments of a system call site recovered by the analysis (see
+///   [`decode_arguments`](crate::arguments::decode_arguments)). Empty, and omitted from the
+///   JSON output, in the syscall tables and when no argument could be recovered.
+/// - `categories`: The behaviour categories of the system call (see [`SyscallCategory`]), set
+///   when the syscall table is loaded.
 ///
 /// # Example
 ///
 /// ```
-/// use manifest_producer_backend::SyscallInfo;
+/// use manifest_producer_backend::{categories::SyscallCategory, SyscallInfo};
 ///
 /// let syscall = SyscallInfo {
 ///     id: 0,
 ///     name: "read".to_string(),
 ///     manpage: "https://manpages.debian.org/unstable/manpages-dev/read.2.en.html".to_string(),
+///     arguments: Vec::new(),
+///     categories: vec![SyscallCategory::Filesystem],
 /// };
 /// println!("Syscall {} - {}: {}", syscall.id, syscall.name, syscall.manpage);
 /// ```
@@ -358,4 +509,84 @@ pub struct SyscallInfo {
     pub id: u64,
     pub name: String,
     pub manpage: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<SyscallArgument>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<SyscallCategory>,
+}
+
+/// An argument of a system call whose value was recovered statically.
+///
+/// # Fields
+///
+/// - `name`: The name of the parameter, as in the manual page (e.g., `"domain"` for `socket`).
+/// - `value`: The raw value held by the register carrying the argument.
+/// - `decoded`: A readable form of the value when one is known: the flag or constant names
+///   (e.g., `"AF_INET"`, `"PROT_READ|PROT_EXEC"`), or the quoted string the value points to
+///   (e.g., a path stored in `.rodata`).
+///
+/// # Example
+///
+/// ```
+/// use manifest_producer_backend::SyscallArgument;
+///
+/// let argument = SyscallArgument {
+///     name: "domain".to_string(),
+///     value: 2,
+///     decoded: Some("AF_INET".to_string()),
+/// };
+/// println!("{} = {}", argument.name, argument.decoded.unwrap_or_default());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SyscallArgument {
+    pub name: String,
+    pub value: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<String>,
+}
+
+/// Fixtures shared by the unit tests of the modules.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::{
+        arch::Arch,
+        syscall::{SyscallTable, DYNAMIC_SYSCALL},
+        FunctionId, FunctionNode, SyscallInfo, SyscallSite,
+    };
+
+    /// Builds a function of `0x100` bytes starting at `id` and calling `children`.
+    pub(crate) fn function(name: &str, id: FunctionId, children: &[FunctionId]) -> FunctionNode {
+        let mut node = FunctionNode::new(name.to_string(), id, id + 0x100);
+        node.children = children.to_vec();
+        node
+    }
+
+    /// Builds a site at `address` issuing the x86-64 system call `name`, with its number and
+    /// categories from the bundled tables. [`DYNAMIC_SYSCALL`] builds a site whose number was
+    /// not resolved.
+    pub(crate) fn syscall_site(address: u64, name: &str) -> SyscallSite {
+        let info = (name != DYNAMIC_SYSCALL).then(|| SyscallInfo {
+            arguments: Vec::new(),
+            ..SyscallTable::bundled(Arch::X86_64)
+                .unwrap()
+                .find(name)
+                .unwrap()
+                .clone()
+        });
+        SyscallSite {
+            address,
+            resolved: info.is_some(),
+            info,
+            implied_by: None,
+        }
+    }
+
+    /// Adds to `node` a site for each of `syscalls`, at `0x10` bytes from each other after its
+    /// start address.
+    pub(crate) fn with_syscalls(mut node: FunctionNode, syscalls: &[&str]) -> FunctionNode {
+        for (address, name) in (node.start_addr + 0x10..).step_by(0x10).zip(syscalls) {
+            node.add_syscall(syscall_site(address, name));
+        }
+        node
+    }
 }
@@ -0,0 +1,245 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+
+use goblin::elf::Elf;
+
+use crate::{
+    categories::{CategoryProfile, SyscallCategory},
+    digest::calculate_digest,
+    error::Result,
+    reachability::SyscallReachability,
+    BasicInfo, FunctionId, FunctionNode,
+};
+
+/// Version of the manifest format produced by [`generate_manifest`].
+///
+/// The version is bumped whenever the layout of [`Manifest`] changes in a way that
+/// could break consumers comparing archived manifests.
+pub const MANIFEST_VERSION: &str = "1.0";
+
+/// Machine-readable summary of the behaviours detected in an ELF binary.
+///
+/// # Overview
+///
+/// The manifest is the stable artifact of the analysis: it is versioned, its lists are
+/// sorted, and it only contains data that does not depend on the order in which the
+/// functions were analysed. Two analyses of the same binary therefore produce byte-identical
+/// manifests, which makes them suitable to be archived and compared.
+///
+/// # Fields
+///
+/// - `manifest_version`: The version of the manifest format (see [`MANIFEST_VERSION`]).
+/// - `binary`: Identification data of the analysed binary.
+/// - `libraries`: The shared libraries the binary is linked against (`DT_NEEDED` entries).
+/// - `roots`: One [`RootManifest`] for each root function of the analysis.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Manifest {
+    pub manifest_version: String,
+    pub binary: BinaryDescriptor,
+    pub libraries: Vec<String>,
+    pub roots: Vec<RootManifest>,
+}
+
+/// Identification data of the binary a [`Manifest`] refers to.
+///
+/// # Fields
+///
+/// - `file_name`: The name of the ELF file.
+/// - `file_type`: The type of the ELF file (e.g., `Executable`).
+/// - `arch`: The architecture of the binary.
+/// - `static_linking`: Whether the binary is statically or dynamically linked.
+/// - `language`: The programming language of the binary.
+/// - `sha256`: The SHA-256 digest of the binary.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BinaryDescriptor {
+    pub file_name: String,
+    pub file_type: String,
+    pub arch: String,
+    pub static_linking: String,
+    pub language: String,
+    pub sha256: String,
+}
+
+/// Behaviours reachable from a single root function.
+///
+/// # Fields
+///
+/// - `id`: The identifier of the root function, which tells it apart from the functions sharing
+///   its name. Declared manifests may omit it.
+/// - `name`: The name of the root function.
+/// - `syscalls`: Every system call reachable from the root. System calls whose number cannot be
+///   resolved statically are listed as [`DYNAMIC_SYSCALL`](crate::syscall::DYNAMIC_SYSCALL).
+/// - `network`: The reachable system calls of the [`SyscallCategory::Network`] category.
+/// - `filesystem`: The reachable system calls of the [`SyscallCategory::Filesystem`] category.
+/// - `process`: The reachable system calls of the [`SyscallCategory::Process`] category.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RootManifest {
+    #[serde(default)]
+    pub id: FunctionId,
+    pub name: String,
+    pub syscalls: Vec<String>,
+    pub network: Vec<String>,
+    pub filesystem: Vec<String>,
+    pub process: Vec<String>,
+}
+
+/// Generates the behaviour manifest of an ELF binary and saves it in JSON format.
+///
+/// # Overview
+///
+/// Combines the metadata returned by [`inspect_binary`](crate::inspect::inspect_binary), the
+/// analysed functions and the root functions into a [`Manifest`]. For every root, the system
+/// calls reachable through the `children` edges (see [`SyscallReachability`]) are collected
+/// and grouped into sections after their categories (see [`CategoryProfile::reachable`]).
+///
+/// # Arguments
+///
+/// - `basic_info`: The metadata of the binary.
+/// - `elf`: A reference to an [`Elf`] structure, used to read the linked libraries.
+/// - `buffer`: Byte buffer of the ELF binary, used to compute its digest.
+/// - `functions`: The functions analysed by [`analyse_functions`](crate::analyse::analyse_functions).
+/// - `roots`: The identifiers of the root functions (e.g., the start address of the one returned
+///   by [`find_main`](crate::entry::find_main)).
+/// - `output_path`: Directory path where the `json/manifest.json` file is saved.
+///
+/// # Returns
+///
+/// - A `Result` containing the generated [`Manifest`].
+///
+/// # Errors
+///
+/// - Propagates errors related to file I/O or JSON serialization.
+pub fn generate_manifest<S: ::std::hash::BuildHasher>(
+    basic_info: &BasicInfo,
+    elf: &Elf,
+    buffer: &[u8],
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    roots: &[FunctionId],
+    output_path: &str,
+) -> Result<Manifest> {
+    let digest = calculate_digest(buffer);
+    let manifest = build_manifest(basic_info, &elf.libraries, &digest, functions, roots);
+
+    let file = File::create(format!("{output_path}/json/manifest.json"))?;
+    serde_json::to_writer_pretty(file, &manifest)?;
+
+    Ok(manifest)
+}
+
+/// Builds a [`Manifest`] without touching the file system.
+///
+/// # Arguments
+///
+/// - `basic_info`: The metadata of the binary.
+/// - `libraries`: The shared libraries the binary is linked against.
+/// - `digest`: The SHA-256 digest of the binary.
+/// - `functions`: The analysed functions.
+/// - `roots`: The identifiers of the root functions.
+///
+/// # Returns
+///
+/// - The generated [`Manifest`]. Roots not present in `functions` produce empty sections and
+///   are named after their address.
+#[must_use]
+pub fn build_manifest<S: ::std::hash::BuildHasher>(
+    basic_info: &BasicInfo,
+    libraries: &[&str],
+    digest: &str,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    roots: &[FunctionId],
+) -> Manifest {
+    let libraries: BTreeSet<String> = libraries.iter().map(ToString::to_string).collect();
+    let reachability = SyscallReachability::new(functions);
+
+    Manifest {
+        manifest_version: MANIFEST_VERSION.to_string(),
+        binary: BinaryDescriptor {
+            file_name: basic_info.file_name.to_string(),
+            file_type: basic_info.file_type.to_string(),
+            arch: basic_info.arch.to_string(),
+            static_linking: basic_info.static_linking.to_string(),
+            language: basic_info.language.clone(),
+            sha256: digest.to_string(),
+        },
+        libraries: libraries.into_iter().collect(),
+        roots: roots
+            .iter()
+            .map(|&root| root_manifest(root, functions, &reachability))
+            .collect(),
+    }
+}
+
+fn root_manifest<S: ::std::hash::BuildHasher>(
+    root: FunctionId,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    reachability: &SyscallReachability,
+) -> RootManifest {
+    let syscalls = reachability.syscalls(root);
+    let profile = CategoryProfile::reachable(reachability, functions, root);
+    let select = |category: SyscallCategory| -> Vec<String> {
+        profile
+            .syscalls
+            .get(&category)
+            .map(|names| names.iter().cloned().collect())
+            .unwrap_or_default()
+    };
+
+    RootManifest {
+        id: root,
+        name: functions
+            .get(&root)
+            .map_or_else(|| format!("0x{root:x}"), |node| node.name.clone()),
+        network: select(SyscallCategory::Network),
+        filesystem: select(SyscallCategory::Filesystem),
+        process: select(SyscallCategory::Process),
+        syscalls: syscalls.into_iter().map(ToString::to_string).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{function, with_syscalls},
+        syscall::DYNAMIC_SYSCALL,
+    };
+
+    #[test]
+    fn test_build_manifest() {
+        let mut functions = HashMap::new();
+        let main = with_syscalls(
+            function("main", 0x1000, &[0x1100, 0x1200]),
+            &["clone", "setuid"],
+        );
+        let serve = with_syscalls(function("serve", 0x1100, &[0x1000]), &["socket", "bind"]);
+        let log = with_syscalls(function("log", 0x1200, &[]), &["open", DYNAMIC_SYSCALL]);
+        // A static function of another translation unit, sharing the name of `log`.
+        let unused = with_syscalls(function("log", 0x1300, &[]), &["execve"]);
+        for node in [main, serve, log, unused] {
+            functions.insert(node.start_addr, node);
+        }
+
+        let info = BasicInfo::new("firmware", "Executable").arch("x86_64");
+        let manifest = build_manifest(
+            &info,
+            &["libc.so.6", "libssl.so.3"],
+            "digest",
+            &functions,
+            &[0x1000],
+        );
+
+        assert_eq!(manifest.manifest_version, MANIFEST_VERSION);
+        assert_eq!(manifest.libraries, vec!["libc.so.6", "libssl.so.3"]);
+        let root = &manifest.roots[0];
+        assert_eq!(root.id, 0x1000);
+        assert_eq!(root.name, "main");
+        assert_eq!(
+            root.syscalls,
+            vec!["bind", "clone", DYNAMIC_SYSCALL, "open", "setuid", "socket"]
+        );
+        assert_eq!(root.network, vec!["bind", "socket"]);
+        assert_eq!(root.filesystem, vec!["open"]);
+        // `setuid` changes credentials, a privileged operation rather than a process one.
+        assert_eq!(root.process, vec!["clone"]);
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque};
+
+use crate::{FunctionId, FunctionNode};
+
+/// System calls reachable from every function of the call graph.
+///
+/// # Overview
+///
+/// A system call is attached to the function issuing it (see
+/// [`FunctionNode::syscalls`]), while the question asked of an analysis is what a function
+/// can ultimately do through the functions it calls. `SyscallReachability` answers it for
+/// every function at once, following the `children` edges, and keeps for each reachable
+/// system call one shortest witness path leading to a function that issues it.
+///
+/// For each system call a breadth-first search walks the call graph backwards from the
+/// functions issuing it, so that every function is visited once per system call whatever
+/// the number of roots, and cycles of recursive functions are crossed only once. The functions
+/// are visited in address order, so that the witnesses chosen among paths of the same length
+/// do not depend on the order of the functions in the map.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+/// use manifest_producer_backend::{reachability::SyscallReachability, FunctionNode};
+///
+/// let mut main = FunctionNode::new("main".to_string(), 0x1000, 0x1100);
+/// main.children = vec![0x1100];
+/// let helper = FunctionNode::new("helper".to_string(), 0x1100, 0x1200);
+/// let functions = HashMap::from([(0x1000, main), (0x1100, helper)]);
+///
+/// let reachability = SyscallReachability::new(&functions);
+/// assert!(reachability.syscalls(0x1000).is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SyscallReachability {
+    /// For every system call, the functions reaching it and their next step towards it.
+    hops: BTreeMap<String, HashMap<FunctionId, Hop>>,
+}
+
+/// The next step of a function on its shortest path to a system call.
+#[derive(Debug, Clone, Copy)]
+struct Hop {
+    /// The child to call, or `None` if the function issues the system call itself.
+    next: Option<FunctionId>,
+}
+
+/// A system call reachable from a function, with the path leading to it.
+///
+/// # Fields
+///
+/// - `syscall`: The name of the system call.
+/// - `path`: The identifiers of the functions of a shortest call path, from the function the
+///   system call is reachable from to the one issuing it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SyscallWitness {
+    pub syscall: String,
+    pub path: Vec<FunctionId>,
+}
+
+impl SyscallReachability {
+    /// Computes the system calls reachable from every function.
+    ///
+    /// # Arguments
+    ///
+    /// - `functions`: The analysed functions, keyed by [`FunctionId`].
+    #[must_use]
+    pub fn new<S: ::std::hash::BuildHasher>(
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+    ) -> Self {
+        Self::with_keys(functions, |node| {
+            node.syscall_names().map(ToString::to_string).collect()
+        })
+    }
+
+    /// Computes the reachability of other behaviours of the functions than the names of
+    /// their system calls (e.g., the capabilities their system calls need). The keys of a
+    /// function then take the place of the system calls in [`SyscallReachability::syscalls`]
+    /// and [`SyscallReachability::witness`].
+    ///
+    /// # Arguments
+    ///
+    /// - `functions`: The analysed functions, keyed by [`FunctionId`].
+    /// - `keys`: Returns the behaviours of a function, named by their key.
+    #[must_use]
+    pub fn with_keys<S: ::std::hash::BuildHasher>(
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+        keys: impl Fn(&FunctionNode) -> Vec<String>,
+    ) -> Self {
+        let mut ids: Vec<FunctionId> = functions.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut callers: HashMap<FunctionId, Vec<FunctionId>> = HashMap::new();
+        let mut issuers: BTreeMap<String, Vec<FunctionId>> = BTreeMap::new();
+        for &id in &ids {
+            let node = &functions[&id];
+            for &child in &node.children {
+                if functions.contains_key(&child) {
+                    callers.entry(child).or_default().push(id);
+                }
+            }
+            for name in keys(node) {
+                let issuing = issuers.entry(name).or_default();
+                if issuing.last() != Some(&id) {
+                    issuing.push(id);
+                }
+            }
+        }
+        for list in callers.values_mut() {
+            list.dedup();
+        }
+
+        let hops = issuers
+            .into_iter()
+            .map(|(syscall, issuing)| (syscall, Self::search(&issuing, &callers)))
+            .collect();
+        Self { hops }
+    }
+
+    /// Walks the call graph backwards from the functions issuing a system call.
+    fn search(
+        issuing: &[FunctionId],
+        callers: &HashMap<FunctionId, Vec<FunctionId>>,
+    ) -> HashMap<FunctionId, Hop> {
+        let mut hops: HashMap<FunctionId, Hop> =
+            issuing.iter().map(|&id| (id, Hop { next: None })).collect();
+        let mut queue: VecDeque<FunctionId> = issuing.iter().copied().collect();
+
+        while let Some(id) = queue.pop_front() {
+            for &caller in callers.get(&id).into_iter().flatten() {
+                if let Entry::Vacant(entry) = hops.entry(caller) {
+                    entry.insert(Hop { next: Some(id) });
+                    queue.push_back(caller);
+                }
+            }
+        }
+
+        hops
+    }
+
+    /// Returns the names of the system calls reachable from a function, in alphabetical
+    /// order.
+    #[must_use]
+    pub fn syscalls(&self, id: FunctionId) -> Vec<&str> {
+        self.hops
+            .iter()
+            .filter(|(_, hops)| hops.contains_key(&id))
+            .map(|(syscall, _)| syscall.as_str())
+            .collect()
+    }
+
+    /// Returns a shortest call path from a function to one issuing `syscall`, or `None` if
+    /// the system call is not reachable from it.
+    #[must_use]
+    pub fn witness(&self, id: FunctionId, syscall: &str) -> Option<Vec<FunctionId>> {
+        let hops = self.hops.get(syscall)?;
+        let mut path = vec![id];
+        let mut current = hops.get(&id)?;
+        while let Some(next) = current.next {
+            path.push(next);
+            current = &hops[&next];
+        }
+        Some(path)
+    }
+
+    /// Returns every system call reachable from a function with its witness path, in
+    /// alphabetical order.
+    #[must_use]
+    pub fn witnesses(&self, id: FunctionId) -> Vec<SyscallWitness> {
+        self.syscalls(id)
+            .into_iter()
+            .filter_map(|syscall| {
+                Some(SyscallWitness {
+                    syscall: syscall.to_string(),
+                    path: self.witness(id, syscall)?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{function, with_syscalls};
+
+    #[test]
+    fn test_syscall_reachability() {
+        // main -> serve -> accept_loop -> serve (cycle), accept_loop -> do_accept;
+        // main -> log; serve -> do_bind.
+        let functions: HashMap<FunctionId, FunctionNode> = [
+            function("main", 0x1000, &[0x1100, 0x1400]),
+            function("serve", 0x1100, &[0x1200, 0x1500]),
+            function("accept_loop", 0x1200, &[0x1100, 0x1300]),
+            with_syscalls(function("do_accept", 0x1300, &[]), &["accept4"]),
+            with_syscalls(function("log", 0x1400, &[0x9999]), &["write", "openat"]),
+            with_syscalls(function("do_bind", 0x1500, &[]), &["socket", "bind"]),
+            with_syscalls(function("unused", 0x1600, &[0x1300]), &["execve"]),
+        ]
+        .into_iter()
+        .map(|node| (node.start_addr, node))
+        .collect();
+
+        let reachability = SyscallReachability::new(&functions);
+
+        assert_eq!(
+            reachability.syscalls(0x1000),
+            vec!["accept4", "bind", "openat", "socket", "write"]
+        );
+        assert_eq!(
+            reachability.witness(0x1000, "accept4"),
+            Some(vec![0x1000, 0x1100, 0x1200, 0x1300])
+        );
+        assert_eq!(
+            reachability.witness(0x1200, "bind"),
+            Some(vec![0x1200, 0x1100, 0x1500])
+        );
+        assert_eq!(reachability.witness(0x1400, "write"), Some(vec![0x1400]));
+        assert_eq!(reachability.witness(0x1000, "execve"), None);
+        assert_eq!(reachability.syscalls(0x1600), vec!["accept4", "execve"]);
+
+        let witnesses = reachability.witnesses(0x1100);
+        assert_eq!(witnesses.len(), 3);
+        assert_eq!(witnesses[0].syscall, "accept4");
+        assert_eq!(witnesses[0].path, vec![0x1100, 0x1200, 0x1300]);
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+
+use goblin::elf::{
+    header::ET_DYN,
+    sym::{STB_GLOBAL, STB_WEAK, STT_FUNC, STT_GNU_IFUNC, STV_DEFAULT},
+    Elf,
+};
+
+use crate::{
+    arch::Arch,
+    entry::find_main,
+    error::{Error, Result},
+    inspect::{find_section, Pointers},
+    syscall::RegisterState,
+    wrappers::wrapper_name,
+    FunctionId, FunctionNode,
+};
+
+/// How the execution of a root function starts.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum RootKind {
+    /// The `main` function of a program (see [`find_main`]).
+    Main,
+    /// The entry point of a program whose `main` function could not be found.
+    Entry,
+    /// A function of `.preinit_array`, run before the shared libraries are initialised.
+    Preinit,
+    /// A constructor of `.init_array`, run before `main`.
+    Init,
+    /// A destructor of `.fini_array`, run at exit.
+    Fini,
+    /// A signal handler registered with `signal` or `sigaction`.
+    SignalHandler,
+    /// The body of a thread started with `pthread_create` or `std::thread::spawn` (see
+    /// [`thread_spawns`]).
+    Thread,
+    /// A function exported by a shared library.
+    Export,
+}
+
+impl RootKind {
+    /// Returns the name of the kind shown in the reports.
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Main => "Main function",
+            Self::Entry => "Entry point",
+            Self::Preinit => "Pre-initialiser",
+            Self::Init => "Constructor",
+            Self::Fini => "Destructor",
+            Self::SignalHandler => "Signal handler",
+            Self::Thread => "Thread body",
+            Self::Export => "Exported function",
+        }
+    }
+}
+
+/// A function whose execution is started by the system rather than by a call of the binary.
+///
+/// # Fields
+///
+/// - `id`: The identifier of the function.
+/// - `name`: The name of the function.
+/// - `kind`: How its execution starts.
+/// - `evidence`: Where the analysis found it, e.g. `.init_array[1] at 0x3db8` or
+///   `registered for SIGTERM by main at 0x1189 through sigaction@GLIBC_2.2.5`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Root {
+    pub id: FunctionId,
+    pub name: String,
+    pub kind: RootKind,
+    pub evidence: String,
+}
+
+/// Discovers the root functions of an ELF binary.
+///
+/// # Overview
+///
+/// `main` is not the only function executed without being called by the binary: the
+/// dynamic loader and the C library run the constructors and destructors listed in the
+/// `.preinit_array`, `.init_array` and `.fini_array` sections, the kernel runs the signal
+/// handlers, the threads run concurrently with the functions starting them, and the programs
+/// linked to a shared library call its exported functions. Every
+/// such function is a root from which the call graph must be explored. The roots are
+/// returned in this order, each function once:
+///
+/// 1. The `main` function (see [`find_main`]) or, when it cannot be found, the entry point.
+/// 2. The functions of `.preinit_array`, `.init_array` and `.fini_array`, read through the
+///    dynamic relocations in position-independent binaries.
+/// 3. The signal handlers: the second argument of `signal`, `bsd_signal` or `sysv_signal`,
+///    and the first field (`sa_handler`) of the `struct sigaction` passed to `sigaction`,
+///    whether it is a local variable (see [`RegisterState`]) or stored in the binary.
+/// 4. The bodies of the threads started by the program, as found by the analysis (see
+///    [`FunctionNode::spawns`]).
+/// 5. In shared libraries (position-independent binaries without `main`), the functions
+///    exported by `.dynsym`, in address order.
+///
+/// # Arguments
+///
+/// - `elf`: A reference to the parsed ELF structure.
+/// - `buffer`: A byte slice containing the binary data.
+/// - `functions`: The analysed functions, keyed by [`FunctionId`].
+/// - `arch`: The architecture of the binary.
+///
+/// # Returns
+///
+/// - A `Result` containing the roots found.
+///
+/// # Errors
+///
+/// - Returns [`Error::FunctionNotFound`] if no root is found.
+/// - Propagates the other errors of [`find_main`].
+pub fn discover_roots<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    buffer: &[u8],
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+) -> Result<Vec<Root>> {
+    let root = |id: FunctionId, kind: RootKind, evidence: String| {
+        functions.get(&id).map(|node| Root {
+            id,
+            name: node.name.clone(),
+            kind,
+            evidence,
+        })
+    };
+    let pointers = Pointers::new(elf, buffer);
+    let mut roots = Vec::new();
+
+    let main = match find_main(functions, arch) {
+        Ok(main) => Some(main),
+        Err(Error::FunctionNotFound(_)) => None,
+        Err(error) => return Err(error),
+    };
+    match &main {
+        Some(main) => roots.extend(root(
+            main.start_addr,
+            RootKind::Main,
+            "main function of the program".to_string(),
+        )),
+        None => roots.extend(root(
+            arch.code_address(elf.entry),
+            RootKind::Entry,
+            format!("entry point of the binary ({:#x})", elf.entry),
+        )),
+    }
+
+    for (section_name, kind) in [
+        (".preinit_array", RootKind::Preinit),
+        (".init_array", RootKind::Init),
+        (".fini_array", RootKind::Fini),
+    ] {
+        let Some(section) = find_section(elf, section_name) else {
+            continue;
+        };
+        let size: u64 = if elf.is_64 { 8 } else { 4 };
+        for index in 0..section.sh_size / size {
+            let slot = section.sh_addr + index * size;
+            let Some(address) = pointers.read(slot) else {
+                continue;
+            };
+            roots.extend(root(
+                arch.code_address(address),
+                kind,
+                format!("{section_name}[{index}] at {slot:#x}"),
+            ));
+        }
+    }
+
+    let mut ids: Vec<FunctionId> = functions.keys().copied().collect();
+    ids.sort_unstable();
+    for &id in &ids {
+        for (handler, evidence) in signal_handlers(elf, &pointers, &functions[&id], functions, arch)
+        {
+            roots.extend(root(handler, RootKind::SignalHandler, evidence));
+        }
+    }
+
+    for id in &ids {
+        let func = &functions[id];
+        for spawn in &func.spawns {
+            let evidence = format!(
+                "started by {} at {:#x} through {}",
+                func.name, spawn.address, spawn.spawner
+            );
+            roots.extend(root(spawn.body, RootKind::Thread, evidence));
+        }
+    }
+
+    if main.is_none() && elf.header.e_type == ET_DYN {
+        let mut exports: Vec<(FunctionId, &str)> = elf
+            .dynsyms
+            .iter()
+            .filter(|sym| {
+                matches!(sym.st_type(), STT_FUNC | STT_GNU_IFUNC)
+                    && matches!(sym.st_bind(), STB_GLOBAL | STB_WEAK)
+                    && sym.st_visibility() == STV_DEFAULT
+                    && sym.st_shndx != 0
+                    && sym.st_value != 0
+            })
+            .filter_map(|sym| {
+                let name = elf.dynstrtab.get_at(sym.st_name)?;
+                Some((arch.code_address(sym.st_value), name))
+            })
+            .collect();
+        exports.sort_unstable();
+        for (id, name) in exports {
+            roots.extend(root(id, RootKind::Export, format!("exported as {name}")));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    roots.retain(|root| seen.insert(root.id));
+    if roots.is_empty() {
+        return Err(Error::FunctionNotFound("main".to_string()));
+    }
+    Ok(roots)
+}
+
+/// Returns the signal handlers registered by a function, with the evidence of their
+/// registration.
+fn signal_handlers<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    pointers: &Pointers,
+    func: &FunctionNode,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+) -> Vec<(FunctionId, String)> {
+    let mut handlers = Vec::new();
+    let mut registers = RegisterState::new(arch);
+
+    for insn in &func.instructions {
+        if let Some(callee) = insn.callee.and_then(|id| functions.get(&id)) {
+            let arguments = registers.call_arguments();
+            let action = arguments.get(1).copied().flatten();
+            let handler = match wrapper_name(&callee.name) {
+                "signal" | "ssignal" | "bsd_signal" | "__bsd_signal" | "sysv_signal"
+                | "__sysv_signal" | "sigset" => action,
+                "sigaction" | "__sigaction" | "__libc_sigaction" => {
+                    let size = if elf.is_64 { 8 } else { 4 };
+                    let local = arch
+                        .call_argument_registers()
+                        .get(1)
+                        .and_then(|register| registers.memory(register, size))
+                        .map(|bytes| {
+                            bytes
+                                .iter()
+                                .rev()
+                                .fold(0, |acc, &byte| (acc << 8) | u64::from(byte))
+                        });
+                    local.or_else(|| pointers.read(action?))
+                }
+                _ => None,
+            };
+            let handler = handler.map(|handler| arch.code_address(handler));
+            if let Some(handler) = handler.filter(|handler| functions.contains_key(handler)) {
+                let signal = arguments
+                    .first()
+                    .copied()
+                    .flatten()
+                    .map_or_else(|| "an unknown signal".to_string(), signal_name);
+                handlers.push((
+                    handler,
+                    format!(
+                        "registered for {signal} by {} at {:#x} through {}",
+                        func.name, insn.address, callee.name
+                    ),
+                ));
+            }
+        }
+        registers.update(insn);
+    }
+
+    handlers
+}
+
+/// Returns the name of a signal, or its number if it is not a standard signal.
+fn signal_name(number: u64) -> String {
+    let name = match number & 0xffff_ffff {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        10 => "SIGUSR1",
+        11 => "SIGSEGV",
+        12 => "SIGUSR2",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        17 => "SIGCHLD",
+        26 => "SIGVTALRM",
+        27 => "SIGPROF",
+        28 => "SIGWINCH",
+        _ => return format!("signal {number}"),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instruction::disassemble, threads::ThreadSpawn};
+
+    #[test]
+    fn test_discover_roots() {
+        let arch = Arch::X86_64;
+        let code = [
+            0xbf, 0x02, 0x00, 0x00, 0x00, // mov edi, 2
+            0xbe, 0x00, 0x40, 0x00, 0x00, // mov esi, 0x4000
+            0xe8, 0xf1, 0x0f, 0x00, 0x00, // call 0x2000 <signal@GLIBC_2.2.5>
+            0x48, 0x83, 0xec, 0x20, // sub rsp, 0x20
+            0x48, 0xc7, 0x04, 0x24, 0x00, 0x41, 0x00, 0x00, // mov qword ptr [rsp], 0x4100
+            0x48, 0x89, 0xe6, // mov rsi, rsp
+            0xbf, 0x0f, 0x00, 0x00, 0x00, // mov edi, 15
+            0xe8, 0xd8, 0x1f, 0x00, 0x00, // call 0x3000 <sigaction@GLIBC_2.2.5>
+        ];
+        let mut main = FunctionNode::new("main".to_string(), 0x1000, 0x1028);
+        main.instructions = disassemble(&arch.capstone(false).unwrap(), arch, &code, 0x1000)
+            .unwrap()
+            .into_iter()
+            .map(|mut insn| {
+                insn.callee = insn.target();
+                insn
+            })
+            .collect();
+        main.disassembly = Some(String::new());
+        main.spawns.push(ThreadSpawn {
+            caller: 0x1000,
+            address: 0x1024,
+            spawner: "pthread_create@GLIBC_2.34".to_string(),
+            body: 0x4200,
+        });
+
+        let functions: HashMap<FunctionId, FunctionNode> = [
+            main,
+            FunctionNode::new("signal@GLIBC_2.2.5".to_string(), 0x2000, 0x2000),
+            FunctionNode::new("sigaction@GLIBC_2.2.5".to_string(), 0x3000, 0x3000),
+            FunctionNode::new("on_interrupt".to_string(), 0x4000, 0x4100),
+            FunctionNode::new("on_terminate".to_string(), 0x4100, 0x4200),
+            FunctionNode::new("worker".to_string(), 0x4200, 0x4300),
+        ]
+        .into_iter()
+        .map(|node| (node.start_addr, node))
+        .collect();
+
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let mut elf = Elf::lazy_parse(header).unwrap();
+        elf.is_64 = true;
+
+        let roots = discover_roots(&elf, &[], &functions, arch).unwrap();
+        let found: Vec<(&str, RootKind)> = roots
+            .iter()
+            .map(|root| (root.name.as_str(), root.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("main", RootKind::Main),
+                ("on_interrupt", RootKind::SignalHandler),
+                ("on_terminate", RootKind::SignalHandler),
+                ("worker", RootKind::Thread),
+            ]
+        );
+        assert_eq!(
+            roots[2].evidence,
+            "registered for SIGTERM by main at 0x1023 through sigaction@GLIBC_2.2.5"
+        );
+        assert_eq!(
+            roots[3].evidence,
+            "started by main at 0x1024 through pthread_create@GLIBC_2.34"
+        );
+    }
+}
@@ -0,0 +1,593 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+
+use goblin::elf::Elf;
+
+use crate::{
+    arch::Arch,
+    error::Result,
+    reachability::SyscallReachability,
+    syscall::{SyscallTable, DYNAMIC_SYSCALL},
+    FunctionId, FunctionNode,
+};
+
+/// System calls allowed by every profile whatever the analysis found.
+///
+/// The filter is installed before the binary is executed (by the container runtime, systemd
+/// or the generated harness), so that the `execve` starting it must be allowed; `exit`,
+/// `exit_group` and `rt_sigreturn` are issued by the kernel interface itself (thread exit,
+/// return from signal handlers) rather than by code visible in the binary.
+const BASELINE_SYSCALLS: &[&str] = &["execve", "exit", "exit_group", "rt_sigreturn"];
+
+/// `SECCOMP_RET_ALLOW`.
+const RET_ALLOW: u32 = 0x7fff_0000;
+/// `SECCOMP_RET_ERRNO | EPERM`: the denied system calls fail with `EPERM`.
+const RET_ERRNO_EPERM: u32 = 0x0005_0001;
+/// `SECCOMP_RET_KILL_PROCESS`, for the system calls of another architecture.
+const RET_KILL_PROCESS: u32 = 0x8000_0000;
+
+/// `BPF_LD | BPF_W | BPF_ABS`.
+const BPF_LD_W_ABS: u16 = 0x20;
+/// `BPF_JMP | BPF_JEQ | BPF_K`.
+const BPF_JEQ_K: u16 = 0x15;
+/// `BPF_JMP | BPF_JGE | BPF_K`.
+const BPF_JGE_K: u16 = 0x35;
+/// `BPF_RET | BPF_K`.
+const BPF_RET_K: u16 = 0x06;
+
+/// Offset of `nr` in `struct seccomp_data`.
+const SECCOMP_DATA_NR: u32 = 0;
+/// Offset of `arch` in `struct seccomp_data`.
+const SECCOMP_DATA_ARCH: u32 = 4;
+/// Bit set in the numbers of the x32 system calls on x86-64.
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+/// An instruction of a classic BPF program (`struct sock_filter`).
+///
+/// # Fields
+///
+/// - `code`: The operation.
+/// - `jt`: The number of instructions skipped when a conditional jump is taken.
+/// - `jf`: The number of instructions skipped when it is not.
+/// - `k`: The operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SockFilter {
+    pub code: u16,
+    pub jt: u8,
+    pub jf: u8,
+    pub k: u32,
+}
+
+impl SockFilter {
+    fn statement(code: u16, k: u32) -> Self {
+        Self {
+            code,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    fn jump(code: u16, k: u32, jt: u8, jf: u8) -> Self {
+        Self { code, jt, jf, k }
+    }
+}
+
+/// Seccomp allowlist of the system calls an ELF binary can perform.
+///
+/// # Overview
+///
+/// The profile allows the system calls reachable from the root functions of the analysis
+/// (see [`SyscallReachability`]) together with a small baseline needed to start the binary
+/// and return from signal handlers, and denies every other one with `EPERM`. It is exported
+/// in three forms:
+///
+/// - an OCI seccomp profile, as used by Docker, Podman or Kubernetes (see
+///   [`SeccompProfile::oci_profile`]);
+/// - a systemd unit snippet setting `SystemCallFilter=` (see
+///   [`SeccompProfile::systemd_filter`]);
+/// - a compiled seccomp-bpf program (see [`SeccompProfile::bpf_program`]), with the source of
+///   a Rust harness running a program under it (see [`SeccompProfile::harness`]).
+///
+/// A profile is only as complete as the analysis: the system calls whose number could not
+/// be recovered (see [`DYNAMIC_SYSCALL`]) cannot be allowed, and the code executed before
+/// the roots (e.g., the dynamic loader) is not analysed. These cases are reported in
+/// `warnings` rather than silently producing a profile that would break the binary.
+///
+/// # Fields
+///
+/// - `arch`: The architecture of the binary, the only one the profile allows.
+/// - `syscalls`: The names of the allowed system calls, in alphabetical order.
+/// - `numbers`: The numbers of the allowed system calls, in increasing order.
+/// - `warnings`: The reasons why the profile may be incomplete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeccompProfile {
+    pub arch: Arch,
+    pub syscalls: BTreeSet<String>,
+    pub numbers: BTreeSet<u64>,
+    pub warnings: Vec<String>,
+}
+
+impl SeccompProfile {
+    /// Returns the profile allowing the system calls reachable from `roots`.
+    ///
+    /// # Arguments
+    ///
+    /// - `elf`: A reference to the parsed ELF structure, used to tell dynamically linked
+    ///   binaries apart.
+    /// - `functions`: The functions analysed by
+    ///   [`analyse_functions`](crate::analyse::analyse_functions).
+    /// - `roots`: The identifiers of the root functions. They should cover the code executed
+    ///   at start-up (e.g., the entry point) as well as `main`, since the filter is installed
+    ///   before the binary is executed.
+    /// - `syscall_table`: The table giving the numbers of the system calls.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::InvalidFormat`](crate::error::Error::InvalidFormat) if the
+    ///   architecture of the binary is not supported.
+    pub fn new<S: ::std::hash::BuildHasher>(
+        elf: &Elf,
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+        roots: &[FunctionId],
+        syscall_table: &SyscallTable,
+    ) -> Result<Self> {
+        let arch = Arch::from_elf(elf)?;
+        let reachability = SyscallReachability::new(functions);
+        let name = |id: FunctionId| {
+            functions
+                .get(&id)
+                .map_or_else(|| format!("0x{id:x}"), |node| node.name.clone())
+        };
+
+        let mut warnings = Vec::new();
+        if let Some(interpreter) = elf.interpreter {
+            warnings.push(format!(
+                "The binary is dynamically linked: the system calls of the dynamic loader \
+                 ({interpreter}) and of the shared libraries are only known through the calls \
+                 to well-known wrappers."
+            ));
+        }
+
+        let mut syscalls: BTreeSet<String> = BASELINE_SYSCALLS
+            .iter()
+            .filter(|name| syscall_table.find(name).is_some())
+            .map(ToString::to_string)
+            .collect();
+        for &root in roots {
+            for syscall in reachability.syscalls(root) {
+                if syscall != DYNAMIC_SYSCALL {
+                    syscalls.insert(syscall.to_string());
+                    continue;
+                }
+                let path: Vec<String> = reachability
+                    .witness(root, syscall)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(name)
+                    .collect();
+                warnings.push(format!(
+                    "A system call whose number cannot be resolved statically is reachable \
+                     from {} ({}): the profile may deny a system call the binary needs.",
+                    name(root),
+                    path.join(" -> ")
+                ));
+            }
+        }
+
+        let mut numbers = BTreeSet::new();
+        syscalls.retain(|syscall| {
+            if let Some(info) = syscall_table.find(syscall) {
+                numbers.insert(info.id);
+                true
+            } else {
+                warnings.push(format!(
+                    "The system call {syscall} is missing from the syscall table of {} and \
+                     cannot be allowed.",
+                    arch.name()
+                ));
+                false
+            }
+        });
+
+        Ok(Self {
+            arch,
+            syscalls,
+            numbers,
+            warnings,
+        })
+    }
+
+    /// Whether the analysis found every system call the binary may perform.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Returns the profile in the OCI seccomp format, as used by Docker, Podman or
+    /// Kubernetes. The warnings are listed in the `comment` field, which runtimes ignore.
+    #[must_use]
+    pub fn oci_profile(&self) -> serde_json::Value {
+        serde_json::json!({
+            "comment": self.warnings,
+            "defaultAction": "SCMP_ACT_ERRNO",
+            "defaultErrnoRet": 1,
+            "architectures": [oci_arch(self.arch)],
+            "syscalls": [
+                {
+                    "names": self.syscalls,
+                    "action": "SCMP_ACT_ALLOW",
+                }
+            ],
+        })
+    }
+
+    /// Returns a systemd unit snippet restricting a service to the profile, to be placed in
+    /// the `[Service]` section of the unit or in a drop-in file. The warnings are written as
+    /// comments.
+    #[must_use]
+    pub fn systemd_filter(&self) -> String {
+        let mut snippet = String::new();
+        for warning in &self.warnings {
+            let _ = writeln!(snippet, "# WARNING: {warning}");
+        }
+        snippet.push_str("[Service]\n");
+        snippet.push_str("SystemCallArchitectures=native\n");
+        snippet.push_str("SystemCallErrorNumber=EPERM\n");
+        let names: Vec<&str> = self.syscalls.iter().map(String::as_str).collect();
+        let _ = writeln!(snippet, "SystemCallFilter={}", names.join(" "));
+        snippet
+    }
+
+    /// Returns the profile compiled into a seccomp-bpf program.
+    ///
+    /// The program kills the process if the system call belongs to another architecture
+    /// (including the x32 ABI on x86-64), returns `SECCOMP_RET_ALLOW` for the allowed
+    /// numbers and makes every other system call fail with `EPERM`. It can be installed with
+    /// `prctl(PR_SET_SECCOMP, SECCOMP_MODE_FILTER, ...)` or `seccomp(2)`.
+    #[must_use]
+    pub fn bpf_program(&self) -> Vec<SockFilter> {
+        let mut program = vec![
+            SockFilter::statement(BPF_LD_W_ABS, SECCOMP_DATA_ARCH),
+            SockFilter::jump(BPF_JEQ_K, audit_arch(self.arch), 1, 0),
+            SockFilter::statement(BPF_RET_K, RET_KILL_PROCESS),
+            SockFilter::statement(BPF_LD_W_ABS, SECCOMP_DATA_NR),
+        ];
+        if self.arch == Arch::X86_64 {
+            program.push(SockFilter::jump(BPF_JGE_K, X32_SYSCALL_BIT, 0, 1));
+            program.push(SockFilter::statement(BPF_RET_K, RET_KILL_PROCESS));
+        }
+        for &number in &self.numbers {
+            // The numbers of the system calls fit in 32 bits on every architecture.
+            let number = u32::try_from(number).unwrap_or(u32::MAX);
+            program.push(SockFilter::jump(BPF_JEQ_K, number, 0, 1));
+            program.push(SockFilter::statement(BPF_RET_K, RET_ALLOW));
+        }
+        program.push(SockFilter::statement(BPF_RET_K, RET_ERRNO_EPERM));
+        program
+    }
+
+    /// Returns the BPF program as an array of `struct sock_filter`, in the byte order of the
+    /// architecture (little endian on every supported one).
+    #[must_use]
+    pub fn bpf_bytes(&self) -> Vec<u8> {
+        self.bpf_program()
+            .iter()
+            .flat_map(|insn| {
+                let mut bytes = Vec::with_capacity(8);
+                bytes.extend_from_slice(&insn.code.to_le_bytes());
+                bytes.push(insn.jt);
+                bytes.push(insn.jf);
+                bytes.extend_from_slice(&insn.k.to_le_bytes());
+                bytes
+            })
+            .collect()
+    }
+
+    /// Returns the source of a standalone Rust program running another program under the BPF
+    /// program of the profile, to test the profile before shipping it:
+    ///
+    /// ```text
+    /// rustc -O seccomp_harness.rs
+    /// ./seccomp_harness /path/to/binary [arguments...]
+    /// ```
+    ///
+    /// The harness sets `PR_SET_NO_NEW_PRIVS`, installs the filter and executes the program;
+    /// a denied system call then fails with `EPERM`.
+    #[must_use]
+    pub fn harness(&self) -> String {
+        let program = self.bpf_program();
+        let mut source = String::new();
+        source.push_str("//! Runs a program under the seccomp filter generated by the analysis.\n");
+        source.push_str("//!\n");
+        source.push_str("//! Build: rustc -O seccomp_harness.rs\n");
+        source.push_str("//! Usage: ./seccomp_harness <program> [arguments...]\n");
+        for warning in &self.warnings {
+            let _ = writeln!(source, "//!\n//! WARNING: {warning}");
+        }
+        let _ = write!(
+            source,
+            r#"
+use std::os::raw::{{c_int, c_ulong, c_ushort}};
+use std::os::unix::process::CommandExt;
+use std::process::{{exit, Command}};
+
+#[repr(C)]
+struct SockFilter {{
+    code: u16,
+    jt: u8,
+    jf: u8,
+    k: u32,
+}}
+
+#[repr(C)]
+struct SockFprog {{
+    len: c_ushort,
+    filter: *const SockFilter,
+}}
+
+extern "C" {{
+    fn prctl(option: c_int, arg2: c_ulong, arg3: c_ulong, arg4: c_ulong, arg5: c_ulong) -> c_int;
+}}
+
+const PR_SET_NO_NEW_PRIVS: c_int = 38;
+const PR_SET_SECCOMP: c_int = 22;
+const SECCOMP_MODE_FILTER: c_ulong = 2;
+
+/// Allowed system calls: {syscalls}.
+static FILTER: [SockFilter; {length}] = [
+"#,
+            syscalls = self
+                .syscalls
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+            length = program.len(),
+        );
+        for insn in &program {
+            let _ = writeln!(
+                source,
+                "    SockFilter {{ code: {:#06x}, jt: {}, jf: {}, k: {:#010x} }},",
+                insn.code, insn.jt, insn.jf, insn.k
+            );
+        }
+        source.push_str(
+            r#"];
+
+fn main() {
+    let mut args = std::env::args_os().skip(1);
+    let Some(program) = args.next() else {
+        eprintln!("usage: seccomp_harness <program> [arguments...]");
+        exit(2);
+    };
+    let mut command = Command::new(program);
+    command.args(args);
+    unsafe {
+        command.pre_exec(|| {
+            let fprog = SockFprog {
+                len: FILTER.len() as c_ushort,
+                filter: FILTER.as_ptr(),
+            };
+            if prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+                || prctl(
+                    PR_SET_SECCOMP,
+                    SECCOMP_MODE_FILTER,
+                    &fprog as *const SockFprog as c_ulong,
+                    0,
+                    0,
+                ) != 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let error = command.exec();
+    eprintln!("seccomp_harness: {error}");
+    exit(1);
+}
+"#,
+        );
+        source
+    }
+}
+
+/// Generates the seccomp profile of an ELF binary and saves it in every format.
+///
+/// # Overview
+///
+/// Builds a [`SeccompProfile`] and writes it in the `seccomp` directory of `output_path`:
+///
+/// - `profile.json`: the OCI seccomp profile;
+/// - `systemd.conf`: the systemd unit snippet;
+/// - `filter.bpf`: the compiled BPF program;
+/// - `seccomp_harness.rs`: the harness running a program under the BPF program.
+///
+/// # Arguments
+///
+/// - `elf`: A reference to the parsed ELF structure.
+/// - `functions`: The analysed functions.
+/// - `roots`: The identifiers of the root functions (see [`SeccompProfile::new`]).
+/// - `syscall_table`: The table giving the numbers of the system calls.
+/// - `output_path`: Directory path where the `seccomp` directory is created.
+///
+/// # Returns
+///
+/// - A `Result` containing the generated [`SeccompProfile`], whose warnings should be shown
+///   to the user.
+///
+/// # Errors
+///
+/// - Returns [`Error::InvalidFormat`](crate::error::Error::InvalidFormat) if the
+///   architecture of the binary is not supported.
+/// - Propagates errors related to file I/O or JSON serialization.
+pub fn generate_seccomp_profile<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    roots: &[FunctionId],
+    syscall_table: &SyscallTable,
+    output_path: &str,
+) -> Result<SeccompProfile> {
+    let profile = SeccompProfile::new(elf, functions, roots, syscall_table)?;
+
+    let directory = format!("{output_path}/seccomp");
+    fs::create_dir_all(&directory)?;
+    fs::write(
+        format!("{directory}/profile.json"),
+        serde_json::to_string_pretty(&profile.oci_profile())?,
+    )?;
+    fs::write(
+        format!("{directory}/systemd.conf"),
+        profile.systemd_filter(),
+    )?;
+    fs::write(format!("{directory}/filter.bpf"), profile.bpf_bytes())?;
+    fs::write(format!("{directory}/seccomp_harness.rs"), profile.harness())?;
+
+    Ok(profile)
+}
+
+/// Returns the `AUDIT_ARCH_*` value the kernel reports for the system calls of an
+/// architecture.
+fn audit_arch(arch: Arch) -> u32 {
+    match arch {
+        Arch::X86 => 0x4000_0003,
+        Arch::X86_64 => 0xc000_003e,
+        Arch::AArch64 => 0xc000_00b7,
+        Arch::Arm => 0x4000_0028,
+        Arch::RiscV64 => 0xc000_00f3,
+    }
+}
+
+/// Returns the name of an architecture in the OCI seccomp format.
+fn oci_arch(arch: Arch) -> &'static str {
+    match arch {
+        Arch::X86 => "SCMP_ARCH_X86",
+        Arch::X86_64 => "SCMP_ARCH_X86_64",
+        Arch::AArch64 => "SCMP_ARCH_AARCH64",
+        Arch::Arm => "SCMP_ARCH_ARM",
+        Arch::RiscV64 => "SCMP_ARCH_RISCV64",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{function, with_syscalls};
+
+    /// Runs a BPF program on a `struct seccomp_data`, as the kernel does.
+    fn run(program: &[SockFilter], arch: u32, nr: u32) -> u32 {
+        let mut pc = 0;
+        let mut accumulator = 0;
+        loop {
+            let insn = program[pc];
+            pc += 1;
+            match insn.code {
+                BPF_LD_W_ABS if insn.k == SECCOMP_DATA_NR => accumulator = nr,
+                BPF_LD_W_ABS if insn.k == SECCOMP_DATA_ARCH => accumulator = arch,
+                BPF_JEQ_K | BPF_JGE_K => {
+                    let taken = if insn.code == BPF_JEQ_K {
+                        accumulator == insn.k
+                    } else {
+                        accumulator >= insn.k
+                    };
+                    pc += usize::from(if taken { insn.jt } else { insn.jf });
+                }
+                BPF_RET_K => return insn.k,
+                code => panic!("unexpected instruction {code:#x}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_seccomp_profile() {
+        let arch = Arch::X86_64;
+        let table = SyscallTable::bundled(arch).unwrap();
+        let main = with_syscalls(function("main", 0x1000, &[0x1100]), &["socket"]);
+        let serve = with_syscalls(function("serve", 0x1100, &[]), &["accept4"]);
+        let unused = with_syscalls(
+            function("unused", 0x1200, &[]),
+            &["ptrace", DYNAMIC_SYSCALL],
+        );
+        let mut functions: HashMap<FunctionId, FunctionNode> = [main, serve, unused]
+            .into_iter()
+            .map(|node| (node.start_addr, node))
+            .collect();
+
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let mut elf = Elf::lazy_parse(header).unwrap();
+        elf.header.e_machine = goblin::elf::header::EM_X86_64;
+
+        let profile = SeccompProfile::new(&elf, &functions, &[0x1000], &table).unwrap();
+        assert!(profile.is_complete());
+        assert_eq!(
+            profile.syscalls.iter().collect::<Vec<_>>(),
+            vec![
+                "accept4",
+                "execve",
+                "exit",
+                "exit_group",
+                "rt_sigreturn",
+                "socket"
+            ]
+        );
+        assert_eq!(profile.oci_profile()["syscalls"][0]["names"][0], "accept4");
+        assert!(profile
+            .systemd_filter()
+            .ends_with("SystemCallFilter=accept4 execve exit exit_group rt_sigreturn socket\n"));
+
+        let program = profile.bpf_program();
+        let x86_64 = audit_arch(Arch::X86_64);
+        assert_eq!(run(&program, x86_64, 41), RET_ALLOW); // socket
+        assert_eq!(run(&program, x86_64, 288), RET_ALLOW); // accept4
+        assert_eq!(run(&program, x86_64, 101), RET_ERRNO_EPERM); // ptrace
+        assert_eq!(
+            run(&program, x86_64, 0x29 | X32_SYSCALL_BIT),
+            RET_KILL_PROCESS
+        );
+        assert_eq!(run(&program, audit_arch(Arch::X86), 41), RET_KILL_PROCESS);
+        assert_eq!(profile.bpf_bytes().len(), program.len() * 8);
+        assert!(profile
+            .harness()
+            .contains(&format!("static FILTER: [SockFilter; {}]", program.len())));
+
+        // A dynamic system call reachable from the root makes the profile incomplete.
+        functions.get_mut(&0x1100).unwrap().children = vec![0x1200];
+        let profile = SeccompProfile::new(&elf, &functions, &[0x1000], &table).unwrap();
+        assert!(profile.syscalls.contains("ptrace"));
+        assert_eq!(profile.warnings.len(), 1);
+        assert!(profile.warnings[0].contains("main -> serve -> unused"));
+        assert!(profile.systemd_filter().starts_with("# WARNING: "));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_seccomp_harness_compiles() {
+        let table = SyscallTable::bundled(Arch::X86_64).unwrap();
+        let main = with_syscalls(function("main", 0x1000, &[]), &["socket", DYNAMIC_SYSCALL]);
+        let functions = HashMap::from([(0x1000, main)]);
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let mut elf = Elf::lazy_parse(header).unwrap();
+        elf.header.e_machine = goblin::elf::header::EM_X86_64;
+        // The warnings are part of the source, as doc comments.
+        let profile = SeccompProfile::new(&elf, &functions, &[0x1000], &table).unwrap();
+        assert_eq!(profile.warnings.len(), 1);
+
+        let dir = std::env::temp_dir().join("seccomp_harness");
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("seccomp_harness.rs");
+        fs::write(&source, profile.harness()).unwrap();
+        let output = std::process::Command::new("rustc")
+            .args(["--edition", "2021", "--emit=metadata", "--out-dir"])
+            .arg(&dir)
+            .arg(&source)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use goblin::elf::{sym, Elf};
+
+use crate::{arch::Arch, detect::demangle_function_name, error::Result, FunctionId, FunctionNode};
+
+/// Index of the functions of a binary by address.
+///
+/// # Overview
+///
+/// Resolving the target of every call instruction by walking `.symtab` costs a linear scan
+/// per call, which dominates the analysis of large statically linked binaries. `SymbolIndex`
+/// sorts the function ranges once and answers lookups with a binary search, either for the
+/// function starting at an address ([`SymbolIndex::get`]) or for the function whose range
+/// contains it ([`SymbolIndex::containing`]).
+///
+/// When several symbols share an address (e.g., `write` and `__write`), the address is named
+/// after the function detected there, or else after the first symbol in `.symtab` order.
+/// Mapping symbols (`$a`, `$t`, `$d`, `$x`) are never indexed, and the Thumb bit of 32-bit ARM
+/// symbols is cleared (see [`Arch::code_address`]).
+#[derive(Debug, Default, Clone)]
+pub struct SymbolIndex {
+    /// Function ranges `(start, end, name)`, sorted by start address with unique starts.
+    entries: Vec<(u64, u64, String)>,
+}
+
+impl SymbolIndex {
+    /// Builds the index of the functions of an ELF binary.
+    ///
+    /// # Arguments
+    ///
+    /// - `elf`: A reference to an [`Elf`] structure.
+    /// - `functions`: The functions detected in the binary, which name the addresses lacking a
+    ///   symbol (e.g., the synthetic `sub_401000` functions of stripped binaries).
+    /// - `language`: The programming language for function name demangling.
+    ///
+    /// # Returns
+    ///
+    /// - A `Result` containing the [`SymbolIndex`] of the binary.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::InvalidFormat`](crate::error::Error::InvalidFormat) if the
+    ///   architecture of the binary is not supported.
+    /// - Returns errors if symbol name demangling fails.
+    pub fn new<S: ::std::hash::BuildHasher>(
+        elf: &Elf,
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+        language: &str,
+    ) -> Result<Self> {
+        let arch = Arch::from_elf(elf)?;
+        let mut entries: Vec<_> = Self::ranges(functions).collect();
+        for symbol in &elf.syms {
+            // ARM mapping symbols share the addresses of the functions, without a type.
+            let function = match symbol.st_type() {
+                sym::STT_FUNC | sym::STT_GNU_IFUNC => true,
+                sym::STT_NOTYPE => arch != Arch::Arm,
+                _ => false,
+            };
+            if !function || symbol.st_shndx == 0 || symbol.st_value == 0 {
+                continue;
+            }
+            let Some(name) = elf
+                .strtab
+                .get_at(symbol.st_name)
+                .filter(|name| !name.is_empty() && !name.starts_with('$'))
+            else {
+                continue;
+            };
+            let start = arch.code_address(symbol.st_value);
+            entries.push((
+                start,
+                start + symbol.st_size,
+                demangle_function_name(name, language)?,
+            ));
+        }
+        Ok(Self::sorted(entries))
+    }
+
+    /// Builds the index of the functions of a map returned by
+    /// [`function_detection`](crate::detect::function_detection), without reading the binary.
+    #[must_use]
+    pub fn from_functions<S: ::std::hash::BuildHasher>(
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+    ) -> Self {
+        Self::sorted(Self::ranges(functions).collect())
+    }
+
+    /// Returns the name of the function starting at `addr`.
+    #[must_use]
+    pub fn get(&self, addr: u64) -> Option<&str> {
+        self.entries
+            .binary_search_by_key(&addr, |&(start, _, _)| start)
+            .ok()
+            .map(|i| self.entries[i].2.as_str())
+    }
+
+    /// Returns the name and the start address of the function whose range contains `addr`.
+    /// Functions of unknown size only contain their start address.
+    #[must_use]
+    pub fn containing(&self, addr: u64) -> Option<(&str, u64)> {
+        let i = self.entries.partition_point(|&(start, _, _)| start <= addr);
+        let (start, end, name) = self.entries.get(i.checked_sub(1)?)?;
+        (addr == *start || addr < *end).then_some((name.as_str(), *start))
+    }
+
+    /// Number of functions in the index.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index holds no function.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn ranges<S: ::std::hash::BuildHasher>(
+        functions: &HashMap<FunctionId, FunctionNode, S>,
+    ) -> impl Iterator<Item = (u64, u64, String)> + '_ {
+        functions
+            .values()
+            .filter(|func| !func.import)
+            .map(|func| (func.start_addr, func.end_addr, func.name.clone()))
+    }
+
+    fn sorted(mut entries: Vec<(u64, u64, String)>) -> Self {
+        // The sort is stable: the first entry of every address is kept.
+        entries.sort_by_key(|&(start, _, _)| start);
+        entries.dedup_by_key(|&mut (start, _, _)| start);
+        Self { entries }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn functions(ranges: &[(&str, u64, u64)]) -> HashMap<FunctionId, FunctionNode> {
+        ranges
+            .iter()
+            .map(|&(name, start, end)| (start, FunctionNode::new(name.to_string(), start, end)))
+            .collect()
+    }
+
+    #[test]
+    fn test_symbol_index_lookup() {
+        let index = SymbolIndex::from_functions(&functions(&[
+            ("main", 0x1130, 0x1160),
+            ("helper", 0x1100, 0x1130),
+            ("_start", 0x1040, 0x1040),
+        ]));
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get(0x1100), Some("helper"));
+        assert_eq!(index.get(0x1130), Some("main"));
+        assert_eq!(index.get(0x1104), None);
+
+        assert_eq!(index.containing(0x1104), Some(("helper", 0x1100)));
+        assert_eq!(index.containing(0x115f), Some(("main", 0x1130)));
+        assert_eq!(index.containing(0x1160), None);
+        assert_eq!(index.containing(0x1040), Some(("_start", 0x1040)));
+        assert_eq!(index.containing(0x1044), None);
+        assert_eq!(index.containing(0x1000), None);
+    }
+}
@@ -1,19 +1,45 @@
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 
-use crate::{error::Result, FunctionNode, SyscallInfo};
+use goblin::elf::Elf;
 
-use regex::Regex;
+use crate::{
+    arch::Arch,
+    arguments::{decode_arguments, decode_socket_address, SOCKET_ADDRESS_PREFIX},
+    categories::SyscallCategories,
+    error::Result,
+    instruction::{strip_compressed, Instruction, Memory, Operand},
+    FunctionId, FunctionNode, SyscallInfo, SyscallSite,
+};
 
-type RegisterMap = HashMap<String, u64>;
-type SyscallSet = Vec<u64>;
-type HandlerFn = Box<dyn Fn(&str, &mut RegisterMap, &mut SyscallSet)>;
+/// Name under which the system calls whose number cannot be resolved statically are reported.
+pub const DYNAMIC_SYSCALL: &str = "dynamic syscall";
 
 /// Detects system calls within the provided functions and updates their information.
 ///
+/// # Overview
+///
+/// Every system call instruction of a function is recorded as a [`SyscallSite`] in
+/// [`FunctionNode::syscalls`], including the ones whose number cannot be resolved.
+///
+/// The arguments of the system calls are recovered from the registers carrying them (see
+/// [`Arch::syscall_argument_registers`]) when they hold constants or addresses of strings
+/// stored in the binary, and decoded for the security-relevant system calls (see
+/// [`decode_arguments`]).
+///
+/// The sites found by a previous detection are replaced, so that the system calls can be
+/// detected again with another [`SyscallTable`].
+///
 /// # Arguments
 ///
-/// - `functions`: A mutable reference to a `HashMap` containing function names as keys and `FunctionNode` as values.
+/// - `elf`: A reference to the parsed ELF structure, used to read the strings passed to the
+///   system calls.
+/// - `buffer`: A byte slice containing the binary data.
+/// - `functions`: A mutable reference to a `HashMap` containing function identifiers as keys and `FunctionNode` as values.
+/// - `arch`: The architecture of the binary, which selects the register holding the syscall
+///   number.
+/// - `syscall_table`: The table naming the system calls (see [`SyscallTable::bundled`]).
 ///
 /// # Returns
 ///
@@ -21,13 +47,17 @@ type HandlerFn = Box<dyn Fn(&str, &mut RegisterMap, &mut SyscallSet)>;
 ///
 /// # Errors
 ///
-/// - Returns errors if loading the syscall table or extracting syscall numbers fails.
+/// - Returns errors if the progress bar template is invalid.
 ///
 /// # Feature Flags
 ///
 /// - `progress_bar`: If enabled, displays a progress bar indicating the progress of syscall detection.
 pub fn detect_syscalls<S: ::std::hash::BuildHasher>(
-    functions: &mut HashMap<String, FunctionNode, S>,
+    elf: &Elf,
+    buffer: &[u8],
+    functions: &mut HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+    syscall_table: &SyscallTable,
 ) -> Result<()> {
     #[cfg(feature = "progress_bar")]
     let pb = {
@@ -40,16 +70,31 @@ pub fn detect_syscalls<S: ::std::hash::BuildHasher>(
         pb
     };
 
-    let syscall_table = load_syscall_table()?;
     for func_node in functions.values_mut() {
+        func_node.syscalls.clear();
         if func_node.syscall {
-            if let Some(ref disasm) = func_node.disassembly {
-                let syscall_numbers = extract_syscall_numbers(disasm)?;
-                for number in syscall_numbers {
-                    if let Some(info) = syscall_table.get(&number) {
-                        func_node.set_syscall_info(info.clone());
-                    }
-                }
+            for site in extract_syscall_sites(&func_node.instructions, arch) {
+                let info = site
+                    .number
+                    .and_then(|number| syscall_table.get(number))
+                    .map(|info| {
+                        let mut arguments =
+                            decode_arguments(elf, buffer, arch, &info.name, &site.arguments);
+                        arguments.extend(decode_socket_address(
+                            &info.name,
+                            site.socket_address.as_deref(),
+                        ));
+                        SyscallInfo {
+                            arguments,
+                            ..info.clone()
+                        }
+                    });
+                func_node.add_syscall(SyscallSite {
+                    address: site.address,
+                    info,
+                    resolved: site.number.is_some(),
+                    implied_by: None,
+                });
             }
         }
         #[cfg(feature = "progress_bar")]
@@ -61,111 +106,535 @@ pub fn detect_syscalls<S: ::std::hash::BuildHasher>(
     Ok(())
 }
 
-fn load_syscall_table() -> Result<HashMap<u64, SyscallInfo>> {
-    // let data = fs::read_to_string("./data/syscall_tab.json")?;
-    let data = fs::read_to_string("syscall_tab.json")?;
+/// Table mapping the numbers of the system calls of an architecture to their [`SyscallInfo`].
+///
+/// # Overview
+///
+/// The tables of the supported architectures are embedded in the crate at compile time (see
+/// [`SyscallTable::bundled`]), so that the analysis does not depend on the working directory.
+/// A table can also be loaded from a JSON file with the same layout, a list of [`SyscallInfo`]
+/// objects, e.g. for kernels with custom system calls (see [`SyscallTable::from_file`]).
+///
+/// # Example
+///
+/// ```
+/// use manifest_producer_backend::{arch::Arch, syscall::SyscallTable};
+///
+/// let table = SyscallTable::bundled(Arch::X86_64).unwrap();
+/// assert_eq!(table.get(1).map(|info| info.name.as_str()), Some("write"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SyscallTable {
+    syscalls: HashMap<u64, SyscallInfo>,
+}
+
+impl SyscallTable {
+    /// Returns the table embedded in the crate for an architecture.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::Json`](crate::error::Error::Json) if the embedded table is malformed.
+    pub fn bundled(arch: Arch) -> Result<Self> {
+        Self::from_json(arch.syscall_table())
+    }
+
+    /// Loads a table from a JSON file holding a list of [`SyscallInfo`] objects.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::Io`](crate::error::Error::Io) if the file cannot be read.
+    /// - Returns [`Error::Json`](crate::error::Error::Json) if the file is malformed.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
 
-    let syscalls: Vec<SyscallInfo> = serde_json::from_str(&data)?;
-    Ok(syscalls.into_iter().map(|info| (info.id, info)).collect())
+    /// Parses a table from a JSON list of [`SyscallInfo`] objects. The entries declaring no
+    /// category are annotated with the ones of the bundled knowledge base (see
+    /// [`SyscallCategories::bundled`]).
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::Json`](crate::error::Error::Json) if `data` is malformed.
+    pub fn from_json(data: &str) -> Result<Self> {
+        let syscalls: Vec<SyscallInfo> = serde_json::from_str(data)?;
+        let categories = SyscallCategories::bundled()?;
+        Ok(Self {
+            syscalls: syscalls
+                .into_iter()
+                .map(|mut info| {
+                    if info.categories.is_empty() {
+                        info.categories = categories.get(&info.name).to_vec();
+                    }
+                    (info.id, info)
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the system call with the given number.
+    #[must_use]
+    pub fn get(&self, number: u64) -> Option<&SyscallInfo> {
+        self.syscalls.get(&number)
+    }
+
+    /// Returns the system call with the given name.
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<&SyscallInfo> {
+        self.syscalls.values().find(|info| info.name == name)
+    }
+
+    /// Number of system calls in the table.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.syscalls.len()
+    }
+
+    /// Whether the table holds no system call.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.syscalls.is_empty()
+    }
+}
+
+/// A system call instruction, with the values of the registers it reads.
+#[derive(Debug)]
+struct SyscallInstruction {
+    address: u64,
+    /// The number of the system call, `None` when it is computed at run time.
+    number: Option<u64>,
+    /// The values of the registers carrying the arguments, `None` when unknown.
+    arguments: Vec<Option<u64>>,
+    /// The first bytes of the local variable the second argument points to, when known: the
+    /// family and port of the socket address of `bind` and `connect`.
+    socket_address: Option<Vec<u8>>,
+}
+
+/// Returns the system calls issued by `instructions`, in order.
+///
+/// The values of the registers are followed across the function by a [`RegisterState`]. At
+/// every system call the value of the register holding the number (see
+/// [`Arch::syscall_register`]) and of the ones carrying the arguments are collected; the
+/// number is `None` when it is computed at run time (e.g., loaded from memory or passed as an
+/// argument), in which case the system call is reported as a [`DYNAMIC_SYSCALL`]. The register
+/// receiving the result (see [`Arch::syscall_return_register`]) is unknown after each system
+/// call, so a later one that does not reload it is not attributed the previous number.
+fn extract_syscall_sites(instructions: &[Instruction], arch: Arch) -> Vec<SyscallInstruction> {
+    let mut syscall_sites = Vec::new();
+    let mut registers = RegisterState::new(arch);
+
+    for insn in instructions {
+        if insn.is_syscall(arch) {
+            syscall_sites.push(SyscallInstruction {
+                address: insn.address,
+                number: registers.get(arch.syscall_register()),
+                arguments: registers.arguments(arch.syscall_argument_registers()),
+                socket_address: registers
+                    .memory(arch.syscall_argument_registers()[1], SOCKET_ADDRESS_PREFIX),
+            });
+            registers.clobber(arch.syscall_return_register());
+            continue;
+        }
+        registers.update(insn);
+    }
+
+    syscall_sites
+}
+
+/// Values of the registers of a function, followed across its instructions to recover the
+/// numbers of its system calls.
+///
+/// The views of a register (`al`, `ax`, `eax` and `rax` on x86) share a single value (see
+/// [`Arch::register_view`]): writing a 32-bit view clears the upper half of the register, as
+/// the processor does, while writing an 8 or 16-bit view keeps the other bits. The idioms
+/// compilers and hand-written assembly use to load a constant are understood:
+///
+/// - `mov`/`movabs` of an immediate or of another register, and `movzx`;
+/// - `lea` of an absolute or register-relative address;
+/// - `push $imm; pop %reg`;
+/// - `xor %reg, %reg` and `sub %reg, %reg`, which clear the register;
+/// - `add` and `sub` of a constant to a known register;
+/// - on the other architectures, the values resolved during the analysis (see
+///   [`Instruction::value`]).
+///
+/// Any other write makes the value of the destination unknown, calls forget every value and
+/// system calls forget the register receiving their result.
+///
+/// The local variables initialised with constants are followed as well, so that the
+/// structures passed by address can be read at a call (see [`RegisterState::memory`]): the
+/// stores of known values relative to the stack or frame pointer (see
+/// [`Arch::frame_registers`]) are recorded byte by byte, and so are the registers pointing to
+/// them (`lea`, `mov` or `add` of the stack pointer). Writing the stack or frame pointer
+/// forgets the variables addressed through it.
+#[derive(Debug)]
+pub(crate) struct RegisterState {
+    arch: Arch,
+    values: HashMap<String, u64>,
+    /// Values pushed on the stack, `None` when unknown.
+    stack: Vec<Option<u64>>,
+    /// Bytes of the local variables, keyed by the frame register and offset they are
+    /// addressed with.
+    frame: HashMap<(String, i64), u8>,
+    /// Registers holding the address of a local variable, as a frame register and offset.
+    pointers: HashMap<String, (String, i64)>,
 }
 
-fn extract_syscall_numbers(disassembly: &str) -> Result<Vec<u64>> {
-    let mut syscall_numbers: Vec<u64> = Vec::new();
-    let mut registers: HashMap<String, u64> = HashMap::new();
+impl RegisterState {
+    pub(crate) fn new(arch: Arch) -> Self {
+        Self {
+            arch,
+            values: HashMap::new(),
+            stack: Vec::new(),
+            frame: HashMap::new(),
+            pointers: HashMap::new(),
+        }
+    }
+
+    /// Returns the first `length` bytes of the local variable `register` points to, or `None`
+    /// if the register does not point to a local variable or if one of the bytes is unknown.
+    pub(crate) fn memory(&self, register: &str, length: usize) -> Option<Vec<u8>> {
+        let (base, offset) = self.pointers.get(&self.arch.canonical_register(register))?;
+        (0..length)
+            .map(|index| {
+                let offset = offset.checked_add(i64::try_from(index).ok()?)?;
+                self.frame.get(&(base.clone(), offset)).copied()
+            })
+            .collect()
+    }
+
+    /// Returns the values of the arguments of a call: the values pushed on the stack, last
+    /// first, on 32-bit x86, the argument registers elsewhere (see
+    /// [`Arch::call_argument_registers`]).
+    pub(crate) fn call_arguments(&self) -> Vec<Option<u64>> {
+        match self.arch {
+            Arch::X86 => self.stack.iter().rev().copied().collect(),
+            arch => self.arguments(arch.call_argument_registers()),
+        }
+    }
+
+    /// Returns the values of `registers`.
+    fn arguments(&self, registers: &[&str]) -> Vec<Option<u64>> {
+        registers
+            .iter()
+            .map(|register| self.get(register))
+            .collect()
+    }
 
-    let patterns = get_patterns()?;
+    /// Returns the value of a register, read through the view named by `register`.
+    pub(crate) fn get(&self, register: &str) -> Option<u64> {
+        let view = self.arch.register_view(register);
+        let value = self.values.get(&view.register)?;
+        Some((value & view.mask()) >> view.shift)
+    }
 
-    for line in disassembly.lines() {
-        if let Some(handler) = patterns.iter().find_map(|(re, handler)| {
-            if re.is_match(line) {
-                Some(handler)
+    /// Writes the view named by `register`, or forgets the value of the register when
+    /// `value` is unknown.
+    fn set(&mut self, register: &str, value: Option<u64>) {
+        let view = self.arch.register_view(register);
+        let merged = value.and_then(|value| {
+            let value = (value << view.shift) & view.mask();
+            if view.bits >= 32 {
+                Some(value)
             } else {
-                None
+                let old = self.values.get(&view.register)?;
+                Some((old & !view.mask()) | value)
             }
-        }) {
-            handler(line, &mut registers, &mut syscall_numbers);
+        });
+        match merged {
+            Some(value) => self.values.insert(view.register, value),
+            None => self.values.remove(&view.register),
+        };
+    }
+
+    fn operand(&self, operand: &Operand) -> Option<u64> {
+        match operand {
+            Operand::Immediate(value) => Some(value.cast_unsigned()),
+            Operand::Register(register) => self.get(register),
+            Operand::Memory(_) => None,
         }
     }
 
-    Ok(syscall_numbers)
-}
+    /// Follows the effect of an instruction on the registers. Must be called after the
+    /// arguments of a call have been read, as the values are forgotten at the call.
+    pub(crate) fn update(&mut self, insn: &Instruction) {
+        self.update_frame(insn);
+        if insn.is_call() {
+            self.values.clear();
+            self.stack.clear();
+            return;
+        }
+        let x86 = matches!(self.arch, Arch::X86 | Arch::X86_64);
+        match (insn.mnemonic.as_str(), insn.operands.as_slice()) {
+            ("push", [source]) if x86 => {
+                let value = self.operand(source);
+                self.stack.push(value);
+                return;
+            }
+            ("pop", [Operand::Register(destination)]) if x86 => {
+                let value = self.stack.pop().flatten();
+                self.set(destination, value);
+                return;
+            }
+            _ => {}
+        }
 
-fn get_patterns() -> Result<Vec<(Regex, HandlerFn)>> {
-    let mov_imm_to_reg = Regex::new(r"mov\s+\$([a-fA-F0-9x]+),\s+%(\w+)")?;
-    let mov_reg_to_reg = Regex::new(r"mov\s+%(\w+),\s*%(\w+)")?;
-    let xor_reg_to_self = Regex::new(r"xor\s+%(\w+),\s*%(\w+)")?;
-    let syscall_re = Regex::new(r"syscall")?;
-
-    Ok(vec![
-        (
-            mov_imm_to_reg.clone(),
-            Box::new(move |line, registers, _| {
-                if let Some(caps) = mov_imm_to_reg.captures(line) {
-                    let value_str = caps.get(1).unwrap().as_str();
-                    let register = caps.get(2).unwrap().as_str().to_string();
-                    let value = if let Some(value_str) = value_str.strip_prefix("0x") {
-                        u64::from_str_radix(value_str, 16).unwrap()
-                    } else {
-                        value_str.parse::<u64>().unwrap()
-                    };
-                    registers.insert(register, value);
-                }
-            }),
-        ),
-        (
-            mov_reg_to_reg.clone(),
-            Box::new(move |line, registers, _| {
-                if let Some(caps) = mov_reg_to_reg.captures(line) {
-                    let src = caps.get(1).unwrap().as_str();
-                    let dest = caps.get(2).unwrap().as_str().to_string();
-                    if let Some(&value) = registers.get(src) {
-                        registers.insert(dest, value);
+        let Some(destination) = insn.destination.as_deref() else {
+            return;
+        };
+        let value =
+            insn.value.or_else(
+                || match (insn.mnemonic.as_str(), insn.operands.as_slice()) {
+                    ("mov" | "movabs" | "movzx", [_, source]) => self.operand(source),
+                    ("lea", [_, Operand::Memory(memory)]) => {
+                        memory.address(|register| match register {
+                            "rip" | "eip" => Some(insn.next()),
+                            _ => self.get(register),
+                        })
                     }
-                }
-            }),
-        ),
-        (
-            xor_reg_to_self.clone(),
-            Box::new(move |line, registers, _| {
-                if let Some(caps) = xor_reg_to_self.captures(line) {
-                    let reg1 = caps.get(1).unwrap().as_str();
-                    let reg2 = caps.get(2).unwrap().as_str();
-                    if reg1 == reg2 {
-                        registers.insert(reg1.to_string(), 0);
+                    ("xor" | "sub", [Operand::Register(first), Operand::Register(second)])
+                        if first == second =>
+                    {
+                        Some(0)
                     }
+                    ("add", [Operand::Register(register), source]) => self
+                        .get(register)
+                        .zip(self.operand(source))
+                        .map(|(value, addend)| value.wrapping_add(addend)),
+                    ("sub", [Operand::Register(register), source]) => self
+                        .get(register)
+                        .zip(self.operand(source))
+                        .map(|(value, subtrahend)| value.wrapping_sub(subtrahend)),
+                    _ => None,
+                },
+            );
+        self.set(destination, value);
+    }
+
+    /// Forgets the value of `register`, and of all its views, once the kernel has written it.
+    fn clobber(&mut self, register: &str) {
+        let register = self.arch.canonical_register(register);
+        self.values.remove(&register);
+        self.pointers.remove(&register);
+    }
+
+    /// Follows the effect of an instruction on the local variables and on the registers
+    /// pointing to them. Must be called before the values of the registers are updated.
+    fn update_frame(&mut self, insn: &Instruction) {
+        if insn.is_call() {
+            self.pointers.clear();
+            return;
+        }
+        let x86 = matches!(self.arch, Arch::X86 | Arch::X86_64);
+        if x86 && matches!(insn.mnemonic.as_str(), "push" | "pop") {
+            self.forget_frame("rsp");
+        }
+
+        if let Some((memory, size, value)) = self.store(insn) {
+            if let Some((base, offset)) = self.frame_address(memory) {
+                let bytes = value.map(u64::to_le_bytes);
+                for index in 0..size {
+                    let key = (base.clone(), offset + i64::from(index));
+                    match bytes {
+                        Some(bytes) => self.frame.insert(key, bytes[usize::from(index)]),
+                        None => self.frame.remove(&key),
+                    };
                 }
-            }),
-        ),
-        (
-            syscall_re.clone(),
-            Box::new(move |_, registers, syscall_numbers| {
-                if let Some(&value) = registers.get("eax") {
-                    syscall_numbers.push(value);
-                }
-            }),
-        ),
-    ])
+            }
+            return;
+        }
+
+        let Some(destination) = insn.destination.as_deref() else {
+            return;
+        };
+        let destination = self.arch.canonical_register(destination);
+        let pointer = match (strip_compressed(&insn.mnemonic), insn.operands.as_slice()) {
+            ("lea", [_, Operand::Memory(memory)]) => self.frame_address(memory),
+            ("mov" | "mv", [_, Operand::Register(source)]) => self.frame_pointer(source, 0),
+            (
+                "add" | "addi" | "addi4spn" | "addi16sp",
+                [_, Operand::Register(source), Operand::Immediate(addend)],
+            ) => self.frame_pointer(source, *addend),
+            ("add", [Operand::Register(register), Operand::Immediate(addend)]) if x86 => {
+                self.frame_pointer(register, *addend)
+            }
+            ("sub", [Operand::Register(register), Operand::Immediate(subtrahend)]) if x86 => {
+                self.frame_pointer(register, subtrahend.wrapping_neg())
+            }
+            _ => None,
+        };
+        if self.arch.frame_registers().contains(&destination.as_str()) {
+            self.forget_frame(&destination);
+        }
+        match pointer {
+            Some(pointer) => self.pointers.insert(destination, pointer),
+            None => self.pointers.remove(&destination),
+        };
+    }
+
+    /// Returns the memory operand written by a store, the number of bytes written and the
+    /// value stored, `None` when unknown.
+    fn store<'a>(&self, insn: &'a Instruction) -> Option<(&'a Memory, u8, Option<u64>)> {
+        let mnemonic = strip_compressed(&insn.mnemonic);
+        let (source, memory) = match (self.arch, insn.operands.as_slice()) {
+            (Arch::X86 | Arch::X86_64, [Operand::Memory(memory), source]) if mnemonic == "mov" => {
+                (source, memory)
+            }
+            (Arch::X86 | Arch::X86_64, _) => return None,
+            (_, [source @ Operand::Register(_), Operand::Memory(memory)]) => (source, memory),
+            _ => return None,
+        };
+        let size = match (self.arch, mnemonic, source) {
+            (Arch::X86 | Arch::X86_64, _, _) => memory.size,
+            (Arch::AArch64 | Arch::Arm, "strb" | "sturb", _) | (Arch::RiscV64, "sb", _) => 1,
+            (Arch::AArch64 | Arch::Arm, "strh" | "sturh", _) | (Arch::RiscV64, "sh", _) => 2,
+            (Arch::AArch64, "str" | "stur", Operand::Register(register))
+                if register.starts_with('x') =>
+            {
+                8
+            }
+            (Arch::AArch64 | Arch::Arm, "str" | "stur", _) | (Arch::RiscV64, "sw", _) => 4,
+            (Arch::RiscV64, "sd", _) => 8,
+            _ => return None,
+        };
+        let value = match source {
+            Operand::Register(register) if matches!(register.as_str(), "wzr" | "xzr" | "zero") => {
+                Some(0)
+            }
+            source => self.operand(source),
+        };
+        (1..=8).contains(&size).then_some((memory, size, value))
+    }
+
+    /// Returns the frame register and offset a memory operand addresses, if it addresses a
+    /// local variable.
+    fn frame_address(&self, memory: &Memory) -> Option<(String, i64)> {
+        if memory.index.is_some() {
+            return None;
+        }
+        self.frame_pointer(memory.base.as_deref()?, memory.displacement)
+    }
+
+    /// Returns the frame register and offset of `register` plus `addend`, if `register` is
+    /// a frame register or points to a local variable.
+    fn frame_pointer(&self, register: &str, addend: i64) -> Option<(String, i64)> {
+        let register = self.arch.canonical_register(register);
+        if self.arch.frame_registers().contains(&register.as_str()) {
+            return Some((register, addend));
+        }
+        let (base, offset) = self.pointers.get(&register)?;
+        Some((base.clone(), offset.checked_add(addend)?))
+    }
+
+    /// Forgets the local variables addressed through a frame register and the registers
+    /// pointing to them, once the frame register is written.
+    fn forget_frame(&mut self, register: &str) {
+        self.frame.retain(|(base, _), _| base != register);
+        self.pointers.retain(|_, (base, _)| base != register);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        arch::RegisterValues, categories::SyscallCategory, inspect::Pointers,
+        instruction::disassemble,
+    };
+    use goblin::elf::Elf;
     use std::collections::HashMap;
 
+    fn empty_elf() -> Elf<'static> {
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        Elf::lazy_parse(header).unwrap()
+    }
+
+    /// Disassembles `code` and resolves the values of its registers, as the analysis does.
+    fn instructions(arch: Arch, code: &[u8]) -> Vec<Instruction> {
+        let elf = empty_elf();
+        let pointers = Pointers::new(&elf, &[]);
+        let mut values = RegisterValues::new(arch, false);
+        let mut instructions =
+            disassemble(&arch.capstone(false).unwrap(), arch, code, 0x1000).unwrap();
+        for insn in &mut instructions {
+            insn.value = values.update(&pointers, insn);
+        }
+        instructions
+    }
+
+    /// Returns the address and number of the system calls issued by `code`.
+    fn sites(arch: Arch, code: &[u8]) -> Vec<(u64, Option<u64>)> {
+        extract_syscall_sites(&instructions(arch, code), arch)
+            .into_iter()
+            .map(|site| (site.address, site.number))
+            .collect()
+    }
+
+    /// `mov $0x1, %eax; syscall`
+    const X86_64_WRITE: [u8; 7] = [0xb8, 0x01, 0x00, 0x00, 0x00, 0x0f, 0x05];
+
     #[test]
     fn test_detect_syscalls() {
         let mut functions = HashMap::new();
         let mut func_node = FunctionNode::new("mock_function".to_string(), 0x1000, 0x2000);
         func_node.syscall = true;
-        func_node.disassembly = Some("mov $0x1, %eax\nsyscall".to_string());
-        functions.insert("mock_function".to_string(), func_node);
+        func_node.instructions = instructions(Arch::X86_64, &X86_64_WRITE);
+        functions.insert(0x1000, func_node);
 
-        let result = detect_syscalls(&mut functions);
+        let result = detect_syscalls(
+            &empty_elf(),
+            &[],
+            &mut functions,
+            Arch::X86_64,
+            &SyscallTable::bundled(Arch::X86_64).unwrap(),
+        );
 
         assert!(result.is_ok());
 
-        let func_node = functions.get("mock_function").unwrap();
-        assert!(func_node.syscall_info.is_some());
+        let func_node = functions.get(&0x1000).unwrap();
+        assert_eq!(func_node.syscalls.len(), 1);
+        assert_eq!(func_node.syscalls[0].address, 0x1005);
+        assert!(func_node.syscalls[0].resolved);
+        assert_eq!(func_node.syscall_names().collect::<Vec<_>>(), vec!["write"]);
+    }
+
+    #[test]
+    fn test_detect_syscall_arguments() {
+        let code = [
+            0xbf, 0x02, 0x00, 0x00, 0x00, // mov $2, %edi
+            0xbe, 0x03, 0x00, 0x00, 0x00, // mov $3, %esi
+            0xba, 0xff, 0x00, 0x00, 0x00, // mov $0xff, %edx
+            0xb8, 0x29, 0x00, 0x00, 0x00, // mov $0x29, %eax
+            0x0f, 0x05, // syscall
+        ];
+        let mut functions = HashMap::new();
+        let mut func_node = FunctionNode::new("raw_socket".to_string(), 0x1000, 0x2000);
+        func_node.syscall = true;
+        func_node.instructions = instructions(Arch::X86_64, &code);
+        functions.insert(0x1000, func_node);
+
+        detect_syscalls(
+            &empty_elf(),
+            &[],
+            &mut functions,
+            Arch::X86_64,
+            &SyscallTable::bundled(Arch::X86_64).unwrap(),
+        )
+        .unwrap();
+
+        let info = functions[&0x1000].syscalls[0].info.clone().unwrap();
+        assert_eq!(info.name, "socket");
+        let arguments: Vec<_> = info
+            .arguments
+            .iter()
+            .map(|argument| (argument.name.as_str(), argument.decoded.as_deref()))
+            .collect();
+        assert_eq!(
+            arguments,
+            vec![
+                ("domain", Some("AF_INET")),
+                ("type", Some("SOCK_RAW")),
+                ("protocol", Some("IPPROTO_RAW")),
+            ]
+        );
     }
 
     #[test]
@@ -174,14 +643,234 @@ mod tests {
         let mut functions = HashMap::new();
         let mut func_node = FunctionNode::new("mock_function".to_string(), 0x1000, 0x2000);
         func_node.syscall = true;
-        func_node.disassembly = Some("mov $0x1, %eax\nsyscall".to_string());
-        functions.insert("mock_function".to_string(), func_node);
+        func_node.instructions = instructions(Arch::X86_64, &X86_64_WRITE);
+        functions.insert(0x1000, func_node);
 
-        let result = detect_syscalls(&mut functions);
+        let result = detect_syscalls(
+            &empty_elf(),
+            &[],
+            &mut functions,
+            Arch::X86_64,
+            &SyscallTable::bundled(Arch::X86_64).unwrap(),
+        );
 
         assert!(result.is_ok());
 
-        let func_node = functions.get("mock_function").unwrap();
-        assert!(func_node.syscall_info.is_some());
+        let func_node = functions.get(&0x1000).unwrap();
+        assert_eq!(func_node.syscalls.len(), 1);
+        assert_eq!(func_node.syscalls[0].address, 0x1005);
+        assert!(func_node.syscalls[0].resolved);
+        assert_eq!(func_node.syscall_names().collect::<Vec<_>>(), vec!["write"]);
+    }
+
+    #[test]
+    fn test_syscall_tables() {
+        for arch in [
+            Arch::X86,
+            Arch::X86_64,
+            Arch::AArch64,
+            Arch::Arm,
+            Arch::RiscV64,
+        ] {
+            assert!(!SyscallTable::bundled(arch).unwrap().is_empty());
+        }
+        let write = |arch| SyscallTable::bundled(arch).unwrap().get(64).cloned();
+        assert_eq!(write(Arch::AArch64).unwrap().name, "write");
+        assert_eq!(write(Arch::X86_64).unwrap().name, "semget");
+
+        let x86_64 = SyscallTable::bundled(Arch::X86_64).unwrap();
+        for (number, name) in [
+            (46, "sendmsg"),
+            (47, "recvmsg"),
+            (48, "shutdown"),
+            (49, "bind"),
+            (50, "listen"),
+        ] {
+            assert_eq!(x86_64.get(number).unwrap().name, name);
+        }
+
+        let custom =
+            SyscallTable::from_json(r#"[{"id": 1000, "name": "vendor_call", "manpage": ""}]"#)
+                .unwrap();
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom.get(1000).unwrap().name, "vendor_call");
+        assert!(custom.get(1000).unwrap().categories.is_empty());
+        assert_eq!(
+            write(Arch::AArch64).unwrap().categories,
+            [SyscallCategory::Filesystem]
+        );
+        assert!(SyscallTable::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_extract_aarch64_syscall_sites() {
+        let code = [
+            0xc8, 0x0b, 0x80, 0x52, // mov w8, #0x5e
+            0x01, 0x00, 0x00, 0xd4, // svc #0
+            0xc9, 0x18, 0x80, 0x52, // mov w9, #198
+            0xe8, 0x03, 0x09, 0xaa, // mov x8, x9
+            0x01, 0x00, 0x00, 0xd4, // svc #0
+        ];
+
+        let sites = sites(Arch::AArch64, &code);
+
+        assert_eq!(sites, vec![(0x1004, Some(94)), (0x1010, Some(198))]);
+    }
+
+    #[test]
+    fn test_extract_socket_addresses() {
+        let socket_addresses = |arch, code: &[u8]| -> Vec<Option<Vec<u8>>> {
+            extract_syscall_sites(&instructions(arch, code), arch)
+                .into_iter()
+                .map(|site| site.socket_address)
+                .collect()
+        };
+        let code = [
+            0x48, 0x83, 0xec, 0x10, // sub rsp, 0x10
+            0x66, 0xc7, 0x04, 0x24, 0x02, 0x00, // mov word ptr [rsp], 2
+            0x66, 0xc7, 0x44, 0x24, 0x02, 0x00, 0x50, // mov word ptr [rsp + 2], 0x5000
+            0x48, 0x89, 0xe6, // mov rsi, rsp
+            0xb8, 0x31, 0x00, 0x00, 0x00, // mov eax, 0x31
+            0x0f, 0x05, // syscall
+            0x48, 0x83, 0xec, 0x08, // sub rsp, 8
+            0x0f, 0x05, // syscall
+        ];
+        assert_eq!(
+            socket_addresses(Arch::X86_64, &code),
+            vec![Some(vec![0x02, 0x00, 0x00, 0x50]), None]
+        );
+
+        let code = [
+            0x48, 0x00, 0x80, 0x52, // mov w8, #2
+            0xe8, 0x23, 0x00, 0x79, // strh w8, [sp, #0x10]
+            0x08, 0x00, 0x8a, 0x52, // mov w8, #0x5000
+            0xe8, 0x27, 0x00, 0x79, // strh w8, [sp, #0x12]
+            0xe1, 0x43, 0x00, 0x91, // add x1, sp, #0x10
+            0x08, 0x19, 0x80, 0xd2, // mov x8, #200
+            0x01, 0x00, 0x00, 0xd4, // svc #0
+        ];
+        let addresses = socket_addresses(Arch::AArch64, &code);
+        assert_eq!(addresses, vec![Some(vec![0x02, 0x00, 0x00, 0x50])]);
+
+        let argument = decode_socket_address("bind", addresses[0].as_deref()).unwrap();
+        assert_eq!(argument.value, 80);
+        assert_eq!(
+            argument.decoded.as_deref(),
+            Some("{sa_family=AF_INET, sin_port=htons(80)}")
+        );
+    }
+
+    #[test]
+    fn test_extract_i386_syscall_sites() {
+        let code = [
+            0xb8, 0x04, 0x00, 0x00, 0x00, // mov $4, %eax
+            0xcd, 0x80, // int $0x80
+            0xb8, 0x67, 0x01, 0x00, 0x00, // mov $0x167, %eax
+            0x0f, 0x34, // sysenter
+            0x31, 0xc0, // xor %eax, %eax
+            0x65, 0xff, 0x15, 0x10, 0x00, 0x00, 0x00, // call *%gs:0x10
+            0xcd, 0x03, // int $3
+        ];
+
+        let sites = sites(Arch::X86, &code);
+
+        assert_eq!(
+            sites,
+            vec![(0x1005, Some(4)), (0x100c, Some(359)), (0x1010, Some(0))]
+        );
+    }
+
+    #[test]
+    fn test_extract_riscv_syscall_sites() {
+        let code = [
+            0x93, 0x08, 0xe0, 0x05, // addi a7, zero, 0x5e
+            0x73, 0x00, 0x00, 0x00, // ecall
+            0x93, 0x02, 0x60, 0x0c, // addi t0, zero, 0xc6
+            0x96, 0x88, // c.mv a7, t0
+            0x73, 0x00, 0x00, 0x00, // ecall
+        ];
+
+        let sites = sites(Arch::RiscV64, &code);
+
+        assert_eq!(sites, vec![(0x1004, Some(94)), (0x100e, Some(198))]);
+    }
+
+    #[test]
+    fn test_extract_unresolved_syscall_sites() {
+        let code = [
+            0xb8, 0x29, 0x00, 0x00, 0x00, // mov $0x29, %eax
+            0x0f, 0x05, // syscall
+            0x8b, 0x07, // mov (%rdi), %eax
+            0x0f, 0x05, // syscall
+        ];
+
+        let sites = sites(Arch::X86_64, &code);
+
+        assert_eq!(sites, vec![(0x1005, Some(41)), (0x1009, None)]);
+    }
+
+    #[test]
+    fn test_extract_x86_64_syscall_idioms() {
+        let code = [
+            0x48, 0xc7, 0xc0, 0x29, 0x00, 0x00, 0x00, // mov $0x29, %rax
+            0x0f, 0x05, // syscall
+            0x31, 0xc0, // xor %eax, %eax
+            0xb0, 0x31, // mov $0x31, %al
+            0x0f, 0x05, // syscall
+            0x8d, 0x04, 0x25, 0x32, 0x00, 0x00, 0x00, // lea 0x32, %eax
+            0x0f, 0x05, // syscall
+            0x6a, 0x2a, // push $0x2a
+            0x58, // pop %rax
+            0x0f, 0x05, // syscall
+            0xb8, 0x2b, 0x00, 0x00, 0x00, // movl $0x2b, %eax
+            0x0f, 0x05, // syscall
+            0x48, 0x89, 0xf8, // mov %rdi, %rax
+            0x0f, 0x05, // syscall
+            0x41, 0xba, 0x3b, 0x00, 0x00, 0x00, // mov $0x3b, %r10d
+            0x4c, 0x89, 0xd0, // mov %r10, %rax
+            0x83, 0xc0, 0x01, // add $1, %eax
+            0x0f, 0x05, // syscall
+        ];
+
+        let sites = sites(Arch::X86_64, &code);
+
+        assert_eq!(
+            sites,
+            vec![
+                (0x1007, Some(0x29)),
+                (0x100d, Some(0x31)),
+                (0x1016, Some(0x32)),
+                (0x101b, Some(0x2a)),
+                (0x1022, Some(0x2b)),
+                (0x1027, None),
+                (0x1035, Some(0x3c)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_syscall_results() {
+        let code = [
+            0xb8, 0x27, 0x00, 0x00, 0x00, // mov $0x27, %eax
+            0x0f, 0x05, // syscall
+            0x0f, 0x05, // syscall
+        ];
+        assert_eq!(
+            sites(Arch::X86_64, &code),
+            vec![(0x1005, Some(0x27)), (0x1007, None)]
+        );
+
+        let code = [
+            0x20, 0x00, 0x80, 0xd2, // mov x0, #1
+            0x08, 0x08, 0x80, 0x52, // mov w8, #0x40
+            0x01, 0x00, 0x00, 0xd4, // svc #0
+            0x01, 0x00, 0x00, 0xd4, // svc #0
+        ];
+        let first_arguments: Vec<_> =
+            extract_syscall_sites(&instructions(Arch::AArch64, &code), Arch::AArch64)
+                .into_iter()
+                .map(|site| (site.number, site.arguments[0]))
+                .collect();
+        assert_eq!(first_arguments, vec![(Some(64), Some(1)), (Some(64), None)]);
     }
 }
@@ -0,0 +1,408 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use goblin::elf::Elf;
+
+use crate::{
+    arch::Arch, inspect::Pointers, syscall::RegisterState, wrappers::wrapper_name, FunctionId,
+    FunctionNode,
+};
+
+/// The C functions starting a thread, with the index of the argument holding its body.
+const C_SPAWN_FUNCTIONS: &[(&str, usize)] = &[
+    ("pthread_create", 2),
+    ("__pthread_create_2_1", 2),
+    ("thrd_create", 1),
+    ("clone", 0),
+    ("__clone", 0),
+];
+
+/// The last path segments of the Rust functions starting a thread (e.g.,
+/// `std::thread::spawn` or `std::thread::Builder::spawn`).
+const RUST_SPAWN_FUNCTIONS: &[&str] = &[
+    "spawn",
+    "spawn_scoped",
+    "spawn_unchecked",
+    "spawn_unchecked_",
+];
+
+/// The function of the Rust standard library calling the closure run by a thread.
+const RUST_THREAD_START: &str = "__rust_begin_short_backtrace";
+
+/// How deep the functions of the Rust standard library are explored from a spawn function to
+/// find the closure of the thread.
+const RUST_SPAWN_DEPTH: usize = 16;
+
+/// How many slots of a table referenced by an instruction (e.g., the vtable of a boxed
+/// closure) are read when looking for function pointers.
+const TABLE_SLOTS: u64 = 6;
+
+/// A thread started by a function.
+///
+/// # Fields
+///
+/// - `caller`: The function starting the thread.
+/// - `address`: The address of the call to the spawn function.
+/// - `spawner`: The name of the spawn function, e.g. `pthread_create@GLIBC_2.34` or
+///   `std::thread::functions::spawn`.
+/// - `body`: The function run by the thread.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ThreadSpawn {
+    pub caller: FunctionId,
+    pub address: u64,
+    pub spawner: String,
+    pub body: FunctionId,
+}
+
+/// Finds the threads started by the functions of a binary.
+///
+/// # Overview
+///
+/// A function passed to `pthread_create`, `thrd_create` or `clone` runs concurrently with
+/// its caller, which only calls the spawn function. The function pointer is recovered from
+/// the registers carrying the arguments of the call (see
+/// [`Arch::call_argument_registers`]), like the arguments of the wrapper functions.
+///
+/// A closure passed to `std::thread::spawn` or `std::thread::Builder::spawn` is not passed as
+/// a pointer: the spawn function is instantiated for the type of the closure, and boxes it
+/// with a vtable that the new thread calls through `__rust_begin_short_backtrace`. The
+/// functions of the standard library reached from the spawn function, through its calls and
+/// the function pointers of the tables it references, are explored until the one calling
+/// `__rust_begin_short_backtrace`; the last function it passes there is the closure of the
+/// thread, followed through the `FnOnce` shims to the closure of the program when it is not
+/// inlined.
+///
+/// The spawn sites in the standard library itself (e.g., the call to `pthread_create` by
+/// `std::thread::spawn`) are skipped, so that the threads are attributed to the program.
+///
+/// # Arguments
+///
+/// - `elf`: A reference to the parsed ELF structure.
+/// - `buffer`: A byte slice containing the binary data.
+/// - `functions`: The analysed functions, keyed by [`FunctionId`].
+/// - `arch`: The architecture of the binary.
+///
+/// # Returns
+///
+/// - The threads found, ordered by caller and address.
+#[must_use]
+pub fn thread_spawns<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    buffer: &[u8],
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+) -> Vec<ThreadSpawn> {
+    let pointers = Pointers::new(elf, buffer);
+    let mut ids: Vec<FunctionId> = functions.keys().copied().collect();
+    ids.sort_unstable();
+
+    let mut spawns = Vec::new();
+    for id in ids {
+        let func = &functions[&id];
+        if is_rust_runtime(&func.name) {
+            continue;
+        }
+        let mut registers = RegisterState::new(arch);
+        for insn in &func.instructions {
+            if let Some(callee) = insn.callee.and_then(|id| functions.get(&id)) {
+                let name = wrapper_name(&callee.name);
+                let bodies = if let Some(&(_, index)) = C_SPAWN_FUNCTIONS
+                    .iter()
+                    .find(|(spawner, _)| *spawner == name)
+                {
+                    registers
+                        .call_arguments()
+                        .get(index)
+                        .copied()
+                        .flatten()
+                        .map(|body| arch.code_address(body))
+                        .filter(|body| functions.contains_key(body))
+                        .into_iter()
+                        .collect()
+                } else if is_rust_spawn(name) {
+                    rust_thread_bodies(elf, &pointers, callee.start_addr, functions, arch)
+                } else {
+                    Vec::new()
+                };
+                spawns.extend(bodies.into_iter().map(|body| ThreadSpawn {
+                    caller: id,
+                    address: insn.address,
+                    spawner: callee.name.clone(),
+                    body,
+                }));
+            }
+            registers.update(insn);
+        }
+    }
+    spawns
+}
+
+/// Attaches to every function the threads it starts, in [`FunctionNode::spawns`], so that
+/// the later stages (e.g., [`discover_roots`](crate::roots::discover_roots)) read them
+/// instead of exploring the spawn functions again.
+///
+/// # Arguments
+///
+/// - `elf`: A reference to the parsed ELF structure.
+/// - `buffer`: A byte slice containing the binary data.
+/// - `functions`: The analysed functions, keyed by [`FunctionId`].
+/// - `arch`: The architecture of the binary.
+///
+/// See [`thread_spawns`] for the spawn functions recognised.
+pub fn detect_thread_spawns<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    buffer: &[u8],
+    functions: &mut HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+) {
+    let spawns = thread_spawns(elf, buffer, functions, arch);
+    for func in functions.values_mut() {
+        func.spawns.clear();
+    }
+    for spawn in spawns {
+        if let Some(func) = functions.get_mut(&spawn.caller) {
+            if !func.spawns.contains(&spawn) {
+                func.spawns.push(spawn);
+            }
+        }
+    }
+}
+
+/// Returns the closures of the threads started by the Rust spawn function `spawner`.
+fn rust_thread_bodies<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    pointers: &Pointers,
+    spawner: FunctionId,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+) -> Vec<FunctionId> {
+    let mut bodies = Vec::new();
+    let mut visited = HashSet::from([spawner]);
+    let mut queue = VecDeque::from([(spawner, 0)]);
+
+    while let Some((id, depth)) = queue.pop_front() {
+        let func = &functions[&id];
+        let start = func.instructions.iter().rev().find_map(|insn| {
+            let callee = functions.get(&insn.callee?)?;
+            is_rust_thread_start(&callee.name).then_some(callee.start_addr)
+        });
+        if let Some(start) = start {
+            let body = rust_closure(start, functions);
+            if !bodies.contains(&body) {
+                bodies.push(body);
+            }
+            continue;
+        }
+        if depth == RUST_SPAWN_DEPTH {
+            continue;
+        }
+        let next = func
+            .children
+            .iter()
+            .copied()
+            .chain(referenced_functions(elf, pointers, func, functions, arch));
+        for next in next {
+            let runtime = functions
+                .get(&next)
+                .is_some_and(|node| !node.import && is_rust_runtime(&node.name));
+            if runtime && visited.insert(next) {
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+    bodies
+}
+
+/// Returns the closure of the program called by the instance `start` of
+/// `__rust_begin_short_backtrace`, or `start` itself if the closure is inlined.
+fn rust_closure<S: ::std::hash::BuildHasher>(
+    start: FunctionId,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+) -> FunctionId {
+    let mut current = start;
+    let mut visited = HashSet::from([start]);
+    while let Some(&child) = functions[&current].children.iter().find(|child| {
+        functions.get(child).is_some_and(|node| {
+            !node.import
+                && (!is_rust_runtime(&node.name) || node.name.starts_with("core::ops::function::"))
+        })
+    }) {
+        if !visited.insert(child) {
+            break;
+        }
+        if !is_rust_runtime(&functions[&child].name) {
+            return child;
+        }
+        current = child;
+    }
+    start
+}
+
+/// Returns the functions whose address is loaded by a function, directly or as one of the
+/// first slots of a table (e.g., the vtable of a boxed closure).
+fn referenced_functions<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    pointers: &Pointers,
+    func: &FunctionNode,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+) -> Vec<FunctionId> {
+    let size: u64 = if elf.is_64 { 8 } else { 4 };
+    let mut referenced = Vec::new();
+    let mut registers = RegisterState::new(arch);
+    for insn in &func.instructions {
+        registers.update(insn);
+        let value = insn
+            .destination
+            .as_deref()
+            .and_then(|destination| registers.get(destination));
+        let Some(value) = value.or(insn.value) else {
+            continue;
+        };
+        if functions.contains_key(&arch.code_address(value)) {
+            referenced.push(arch.code_address(value));
+            continue;
+        }
+        for slot in 0..TABLE_SLOTS {
+            let pointer = value
+                .checked_add(slot * size)
+                .and_then(|slot| pointers.read(slot));
+            let Some(pointer) = pointer else {
+                continue;
+            };
+            let pointer = arch.code_address(pointer);
+            if functions.contains_key(&pointer) {
+                referenced.push(pointer);
+            }
+        }
+    }
+    referenced
+}
+
+/// Whether a function belongs to the Rust standard library.
+fn is_rust_runtime(name: &str) -> bool {
+    // Crates compiled with the v0 mangling are followed by their disambiguator (`std[..]`).
+    let krate = name
+        .trim_start_matches('<')
+        .split("::")
+        .next()
+        .unwrap_or_default();
+    let krate = krate.split_once('[').map_or(krate, |(krate, _)| krate);
+    ["std", "core", "alloc"].contains(&krate)
+}
+
+/// Whether a function, named as in the knowledge base of wrappers, starts a Rust thread.
+fn is_rust_spawn(name: &str) -> bool {
+    name.starts_with("std::thread::")
+        && name
+            .rsplit("::")
+            .next()
+            .is_some_and(|segment| RUST_SPAWN_FUNCTIONS.contains(&segment))
+}
+
+/// Whether a function is the one of the Rust standard library calling the closure of a thread.
+fn is_rust_thread_start(name: &str) -> bool {
+    let name = wrapper_name(name);
+    is_rust_runtime(name) && name.ends_with(RUST_THREAD_START)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::disassemble;
+
+    #[test]
+    fn test_thread_spawns() {
+        let arch = Arch::X86_64;
+        let code = [
+            0x48, 0x8d, 0x15, 0xf9, 0x1f, 0x00, 0x00, // lea rdx, [rip + 0x1ff9] (0x3000)
+            0x31, 0xc9, // xor ecx, ecx
+            0xe8, 0xf2, 0x0f, 0x00, 0x00, // call 0x2000 <pthread_create@GLIBC_2.34>
+            0xe8, 0xed, 0x2f, 0x00, 0x00, // call 0x4000 <std::thread::functions::spawn>
+        ];
+        let mut main = FunctionNode::new("main".to_string(), 0x1000, 0x1013);
+        main.instructions = disassemble(&arch.capstone(false).unwrap(), arch, &code, 0x1000)
+            .unwrap()
+            .into_iter()
+            .map(|mut insn| {
+                insn.callee = insn.target();
+                insn
+            })
+            .collect();
+        main.children = vec![0x2000, 0x4000];
+
+        // std::thread::functions::spawn -> spawn_unchecked::{{closure}} ->
+        // __rust_begin_short_backtrace (hooks), __rust_begin_short_backtrace (closure).
+        let call = |target: u64, address: u64| {
+            let offset = u32::try_from(target - address - 5).unwrap().to_le_bytes();
+            [0xe8, offset[0], offset[1], offset[2], offset[3]]
+        };
+        let mut spawn =
+            FunctionNode::new("std::thread::functions::spawn".to_string(), 0x4000, 0x4005);
+        spawn.children = vec![0x5000];
+        let mut thread_main = FunctionNode::new(
+            "std::thread::lifecycle::spawn_unchecked::{{closure}}".to_string(),
+            0x5000,
+            0x500a,
+        );
+        thread_main.instructions = disassemble(
+            &arch.capstone(false).unwrap(),
+            arch,
+            &[call(0x6000, 0x5000), call(0x6100, 0x5005)].concat(),
+            0x5000,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|mut insn| {
+            insn.callee = insn.target();
+            insn
+        })
+        .collect();
+        thread_main.children = vec![0x6000, 0x6100];
+        let mut closure_start = FunctionNode::new(
+            "std::sys::backtrace::__rust_begin_short_backtrace".to_string(),
+            0x6100,
+            0x6200,
+        );
+        closure_start.children = vec![0x7000];
+
+        let mut functions: HashMap<FunctionId, FunctionNode> = [
+            main,
+            FunctionNode::new("pthread_create@GLIBC_2.34".to_string(), 0x2000, 0x2000),
+            FunctionNode::new("worker".to_string(), 0x3000, 0x3100),
+            spawn,
+            thread_main,
+            FunctionNode::new(
+                "std::sys::backtrace::__rust_begin_short_backtrace".to_string(),
+                0x6000,
+                0x6100,
+            ),
+            closure_start,
+            FunctionNode::new("thr::main::{{closure}}".to_string(), 0x7000, 0x7100),
+        ]
+        .into_iter()
+        .map(|node| (node.start_addr, node))
+        .collect();
+
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = Elf::lazy_parse(header).unwrap();
+        let spawns: Vec<(FunctionId, u64, FunctionId)> = thread_spawns(&elf, &[], &functions, arch)
+            .iter()
+            .map(|spawn| (spawn.caller, spawn.address, spawn.body))
+            .collect();
+        assert_eq!(
+            spawns,
+            vec![(0x1000, 0x1009, 0x3000), (0x1000, 0x100e, 0x7000)]
+        );
+
+        detect_thread_spawns(&elf, &[], &mut functions, arch);
+        assert_eq!(functions[&0x1000].spawns.len(), 2);
+        assert_eq!(
+            functions[&0x1000].spawns[1].spawner,
+            "std::thread::functions::spawn"
+        );
+        assert_eq!(
+            functions[&0x1000].thread_bodies().collect::<Vec<_>>(),
+            vec![0x3000, 0x7000]
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+
+use crate::{
+    error::{Error, Result},
+    manifest::{Manifest, RootManifest, MANIFEST_VERSION},
+    reachability::SyscallReachability,
+    FunctionId, FunctionNode,
+};
+
+/// The kind of a behaviour listed in a [`Manifest`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BehaviourKind {
+    Syscall,
+    Library,
+}
+
+/// A single behaviour of the binary, as declared by the manufacturer or observed by the analysis.
+///
+/// # Fields
+///
+/// - `kind`: Whether the behaviour is a system call or a linked library.
+/// - `name`: The name of the system call or of the library.
+/// - `section`: The manifest section the system call belongs to (`network`, `filesystem`,
+///   `process`), if any.
+/// - `evidence`: For observed behaviours, the call paths proving them, one for each root
+///   function the behaviour is reachable from. Empty for behaviours that were not observed
+///   and for libraries, whose evidence is the `DT_NEEDED` entry itself.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Behaviour {
+    pub kind: BehaviourKind,
+    pub name: String,
+    pub section: Option<String>,
+    pub evidence: Vec<Evidence>,
+}
+
+/// A call path proving that a behaviour is reachable from a root function.
+///
+/// # Fields
+///
+/// - `root`: The name of the root function.
+/// - `call_path`: The functions traversed from `root` (included) to the function performing
+///   the behaviour (included).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Evidence {
+    pub root: String,
+    pub call_path: Vec<String>,
+}
+
+/// Result of the comparison between a declared and an observed [`Manifest`].
+///
+/// # Fields
+///
+/// - `file_name`: The name of the analysed binary.
+/// - `declared_and_observed`: Behaviours both declared by the manufacturer and found by the analysis.
+/// - `declared_not_observed`: Behaviours declared by the manufacturer but never found by the analysis.
+/// - `observed_undeclared`: Behaviours found by the analysis but missing from the declaration.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VerificationReport {
+    pub file_name: String,
+    pub declared_and_observed: Vec<Behaviour>,
+    pub declared_not_observed: Vec<Behaviour>,
+    pub observed_undeclared: Vec<Behaviour>,
+}
+
+type BehaviourKey = (BehaviourKind, String);
+
+/// Loads a manufacturer-declared manifest from a JSON file.
+///
+/// # Arguments
+///
+/// - `manifest_path`: The path to the declared manifest.
+///
+/// # Returns
+///
+/// - A `Result` containing the declared [`Manifest`].
+///
+/// # Errors
+///
+/// - Returns [`Error::ManifestVersion`] if the major version of the manifest differs from
+///   [`MANIFEST_VERSION`].
+/// - Propagates errors related to file I/O or JSON deserialization.
+pub fn load_manifest(manifest_path: &str) -> Result<Manifest> {
+    let data = fs::read_to_string(manifest_path)?;
+    let manifest: Manifest = serde_json::from_str(&data)?;
+
+    if major_version(&manifest.manifest_version) != major_version(MANIFEST_VERSION) {
+        return Err(Error::ManifestVersion(manifest.manifest_version));
+    }
+    Ok(manifest)
+}
+
+/// Verifies a manufacturer-declared manifest against the behaviour detected by the analysis.
+///
+/// # Overview
+///
+/// Every system call and library listed in either manifest is placed in one of three groups:
+/// declared and observed, declared but not observed, and observed but undeclared. Observed
+/// system calls carry, for every root function they are reachable from, the shortest call path
+/// from the root to a function invoking them, the same witness shown in the reports (see
+/// [`SyscallReachability::witness`]). The report is also saved in JSON format.
+///
+/// # Arguments
+///
+/// - `declared`: The manifest declared by the manufacturer (see [`load_manifest`]).
+/// - `observed`: The manifest produced by [`generate_manifest`](crate::manifest::generate_manifest).
+/// - `functions`: The analysed functions, used to reconstruct the call paths.
+/// - `output_path`: Directory path where the `json/verification.json` file is saved.
+///
+/// # Returns
+///
+/// - A `Result` containing the [`VerificationReport`].
+///
+/// # Errors
+///
+/// - Propagates errors related to file I/O or JSON serialization.
+pub fn verify_manifest<S: ::std::hash::BuildHasher>(
+    declared: &Manifest,
+    observed: &Manifest,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    output_path: &str,
+) -> Result<VerificationReport> {
+    let report = compare_manifests(declared, observed, functions);
+
+    let file = File::create(format!("{output_path}/json/verification.json"))?;
+    serde_json::to_writer_pretty(file, &report)?;
+
+    Ok(report)
+}
+
+/// Compares a declared and an observed manifest without touching the file system.
+///
+/// See [`verify_manifest`] for a description of the comparison.
+#[must_use]
+pub fn compare_manifests<S: ::std::hash::BuildHasher>(
+    declared: &Manifest,
+    observed: &Manifest,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+) -> VerificationReport {
+    let declared_set = behaviours(declared);
+    let observed_set = behaviours(observed);
+    let reachability = SyscallReachability::new(functions);
+
+    let mut report = VerificationReport {
+        file_name: observed.binary.file_name.clone(),
+        declared_and_observed: Vec::new(),
+        declared_not_observed: Vec::new(),
+        observed_undeclared: Vec::new(),
+    };
+
+    for (key, section) in &observed_set {
+        let behaviour = Behaviour {
+            kind: key.0,
+            name: key.1.clone(),
+            section: section.clone(),
+            evidence: evidence(key, observed, functions, &reachability),
+        };
+        if declared_set.contains_key(key) {
+            report.declared_and_observed.push(behaviour);
+        } else {
+            report.observed_undeclared.push(behaviour);
+        }
+    }
+
+    for (key, section) in &declared_set {
+        if !observed_set.contains_key(key) {
+            report.declared_not_observed.push(Behaviour {
+                kind: key.0,
+                name: key.1.clone(),
+                section: section.clone(),
+                evidence: Vec::new(),
+            });
+        }
+    }
+
+    report
+}
+
+fn behaviours(manifest: &Manifest) -> BTreeMap<BehaviourKey, Option<String>> {
+    let mut set = BTreeMap::new();
+
+    for library in &manifest.libraries {
+        set.insert((BehaviourKind::Library, library.clone()), None);
+    }
+    for root in &manifest.roots {
+        for (name, section) in root_syscalls(root) {
+            let entry = set
+                .entry((BehaviourKind::Syscall, name.to_string()))
+                .or_insert(None);
+            if entry.is_none() {
+                *entry = section.map(ToString::to_string);
+            }
+        }
+    }
+
+    set
+}
+
+fn root_syscalls(root: &RootManifest) -> Vec<(&str, Option<&str>)> {
+    let sections = [
+        ("network", &root.network),
+        ("filesystem", &root.filesystem),
+        ("process", &root.process),
+    ];
+
+    let mut syscalls: Vec<(&str, Option<&str>)> = sections
+        .iter()
+        .flat_map(|(section, names)| names.iter().map(|name| (name.as_str(), Some(*section))))
+        .collect();
+    syscalls.extend(root.syscalls.iter().map(|name| (name.as_str(), None)));
+    syscalls
+}
+
+fn evidence<S: ::std::hash::BuildHasher>(
+    key: &BehaviourKey,
+    observed: &Manifest,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    reachability: &SyscallReachability,
+) -> Vec<Evidence> {
+    if key.0 != BehaviourKind::Syscall {
+        return Vec::new();
+    }
+
+    observed
+        .roots
+        .iter()
+        .filter(|root| root.syscalls.contains(&key.1))
+        .filter_map(|root| {
+            let call_path = reachability
+                .witness(root.id, &key.1)?
+                .iter()
+                .map(|id| functions.get(id).map(|node| node.name.clone()))
+                .collect::<Option<_>>()?;
+            Some(Evidence {
+                root: root.name.clone(),
+                call_path,
+            })
+        })
+        .collect()
+}
+
+fn major_version(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{function, with_syscalls},
+        manifest::build_manifest,
+        BasicInfo,
+    };
+
+    #[test]
+    fn test_compare_manifests() {
+        let mut functions = HashMap::new();
+        let main = function("main", 0x1000, &[0x1100]);
+        let serve = function("serve", 0x1100, &[0x1200]);
+        let do_bind = with_syscalls(function("do_bind", 0x1200, &[]), &["bind"]);
+        // A static function of another translation unit, sharing the name of the root.
+        let other_main = function("main", 0x800, &[]);
+        for node in [main, serve, do_bind, other_main] {
+            functions.insert(node.start_addr, node);
+        }
+
+        let info = BasicInfo::new("firmware", "Executable");
+        let observed = build_manifest(&info, &["libc.so.6"], "", &functions, &[0x1000]);
+        let mut declared = observed.clone();
+        declared.libraries.clear();
+        declared.roots[0].network = vec!["connect".to_string()];
+        declared.roots[0].syscalls = vec!["bind".to_string(), "connect".to_string()];
+
+        let report = compare_manifests(&declared, &observed, &functions);
+
+        assert_eq!(report.declared_and_observed.len(), 1);
+        let bind = &report.declared_and_observed[0];
+        assert_eq!(bind.name, "bind");
+        assert_eq!(bind.section.as_deref(), Some("network"));
+        assert_eq!(bind.evidence[0].call_path, vec!["main", "serve", "do_bind"]);
+
+        assert_eq!(report.declared_not_observed.len(), 1);
+        assert_eq!(report.declared_not_observed[0].name, "connect");
+
+        assert_eq!(report.observed_undeclared.len(), 1);
+        assert_eq!(report.observed_undeclared[0].kind, BehaviourKind::Library);
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+
+use goblin::elf::Elf;
+
+use crate::{
+    arch::Arch,
+    arguments::{
+        decode_arguments, decode_socket_address, decode_wrapper_arguments, SOCKET_ADDRESS_PREFIX,
+    },
+    error::Result,
+    syscall::{RegisterState, SyscallTable},
+    FunctionId, FunctionNode, SyscallInfo, SyscallSite,
+};
+
+/// Name of the libc function issuing the system call whose number is its first argument.
+const SYSCALL_FUNCTION: &str = "syscall";
+
+/// Knowledge base mapping well-known wrapper functions to the system calls they perform.
+///
+/// # Overview
+///
+/// In dynamically linked binaries the system call instructions sit in the C library, and Rust
+/// binaries reach them through the standard library (e.g., `std::fs::File::open`). A call to
+/// one of these wrappers therefore implies the system calls it performs, even though none of
+/// them is visible in the code of the program.
+///
+/// The bundled knowledge base (see [`Wrappers::bundled`]) covers the exports of glibc and musl
+/// and the common paths of `std::fs`, `std::net`, `std::process` and `std::thread`. It is a
+/// JSON object mapping the name of each wrapper to the names of the system calls it performs.
+/// Since the C libraries pick different system calls depending on the architecture (e.g.,
+/// `mmap2` on 32-bit ARM and `mmap` elsewhere), each name may list alternatives separated by
+/// `|`: the first one found in the syscall table of the architecture is used.
+///
+/// Wrappers are looked up without the symbol version of imported functions
+/// (`write@GLIBC_2.2.5`) and without the hash of Rust symbols (`::h0123456789abcdef`).
+#[derive(Debug, Clone, Default)]
+pub struct Wrappers {
+    wrappers: HashMap<String, Vec<String>>,
+}
+
+impl Wrappers {
+    /// Returns the knowledge base embedded in the crate.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::Json`](crate::error::Error::Json) if the embedded knowledge base is
+    ///   malformed.
+    pub fn bundled() -> Result<Self> {
+        Self::from_json(include_str!("../data/wrappers.json"))
+    }
+
+    /// Parses a knowledge base from a JSON object mapping wrapper names to system call names.
+    ///
+    /// # Errors
+    ///
+    /// - Returns [`Error::Json`](crate::error::Error::Json) if `data` is malformed.
+    pub fn from_json(data: &str) -> Result<Self> {
+        Ok(Self {
+            wrappers: serde_json::from_str(data)?,
+        })
+    }
+
+    /// Returns the names of the system calls performed by the function named `name`, with
+    /// their alternatives, or `None` if it is not a known wrapper.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.wrappers.get(wrapper_name(name)).map(Vec::as_slice)
+    }
+
+    /// Number of wrappers in the knowledge base.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.wrappers.len()
+    }
+
+    /// Whether the knowledge base holds no wrapper.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.wrappers.is_empty()
+    }
+}
+
+/// Attaches to every function the system calls implied by the wrappers it calls.
+///
+/// # Overview
+///
+/// Every call resolved during the analysis (see [`Instruction::callee`](crate::instruction::Instruction::callee))
+/// to a function known to `wrappers` adds one [`SyscallSite`] for each system call the wrapper
+/// performs, marked with the name of the wrapper in [`SyscallSite::implied_by`]. Calls to
+/// `syscall` add the system call whose number is passed as first argument, which is
+/// recovered like the numbers of the system call instructions (see
+/// [`detect_syscalls`](crate::syscall::detect_syscalls)).
+///
+/// The arguments of the calls are recovered like the ones of the system call instructions,
+/// from the registers carrying the arguments of a call (see
+/// [`Arch::call_argument_registers`]), and decoded with the parameters of the wrapper (see
+/// [`decode_wrapper_arguments`]): the arguments of `open` are attached to the `openat` it
+/// performs.
+/// The arguments following the number passed to `syscall` are decoded with the parameters of
+/// the system call.
+///
+/// The implied sites found by a previous detection are replaced, and the system call
+/// instructions are left untouched.
+///
+/// # Arguments
+///
+/// - `elf`: A reference to the parsed ELF structure, used to read the strings passed to the
+///   wrappers.
+/// - `buffer`: A byte slice containing the binary data.
+/// - `functions`: The analysed functions, keyed by [`FunctionId`].
+/// - `arch`: The architecture of the binary.
+/// - `syscall_table`: The table naming the system calls.
+/// - `wrappers`: The knowledge base of wrapper functions (see [`Wrappers::bundled`]).
+pub fn detect_implied_syscalls<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    buffer: &[u8],
+    functions: &mut HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+    syscall_table: &SyscallTable,
+    wrappers: &Wrappers,
+) {
+    let implied: Vec<(FunctionId, Vec<SyscallSite>)> = functions
+        .iter()
+        .map(|(&id, func)| {
+            let sites =
+                implied_syscalls(elf, buffer, func, functions, arch, syscall_table, wrappers);
+            (id, sites)
+        })
+        .collect();
+
+    for (id, sites) in implied {
+        if let Some(func) = functions.get_mut(&id) {
+            func.syscalls.retain(|site| site.implied_by.is_none());
+            func.syscalls.extend(sites);
+        }
+    }
+}
+
+fn implied_syscalls<S: ::std::hash::BuildHasher>(
+    elf: &Elf,
+    buffer: &[u8],
+    func: &FunctionNode,
+    functions: &HashMap<FunctionId, FunctionNode, S>,
+    arch: Arch,
+    syscall_table: &SyscallTable,
+    wrappers: &Wrappers,
+) -> Vec<SyscallSite> {
+    let mut sites = Vec::new();
+    let mut registers = RegisterState::new(arch);
+
+    for insn in &func.instructions {
+        if let Some(callee) = insn.callee.and_then(|id| functions.get(&id)) {
+            let implied_by = Some(callee.name.clone());
+            let name = wrapper_name(&callee.name);
+            let arguments = registers.call_arguments();
+            // The address of the socket address of `bind` and `connect`, following the
+            // number passed to `syscall`.
+            let socket_address = |index: usize| {
+                let register = arch.call_argument_registers().get(index)?;
+                registers.memory(register, SOCKET_ADDRESS_PREFIX)
+            };
+            if name == SYSCALL_FUNCTION {
+                let number = arguments.first().copied().flatten();
+                let info = number
+                    .and_then(|number| syscall_table.get(number))
+                    .map(|info| {
+                        let mut arguments = decode_arguments(
+                            elf,
+                            buffer,
+                            arch,
+                            &info.name,
+                            arguments.get(1..).unwrap_or_default(),
+                        );
+                        arguments.extend(decode_socket_address(
+                            &info.name,
+                            socket_address(2).as_deref(),
+                        ));
+                        SyscallInfo {
+                            arguments,
+                            ..info.clone()
+                        }
+                    });
+                sites.push(SyscallSite {
+                    address: insn.address,
+                    info,
+                    resolved: number.is_some(),
+                    implied_by,
+                });
+            } else if let Some(syscalls) = wrappers.get(&callee.name) {
+                let mut arguments = decode_wrapper_arguments(elf, buffer, arch, name, &arguments);
+                arguments.extend(decode_socket_address(name, socket_address(1).as_deref()));
+                for alternatives in syscalls {
+                    let Some(info) = alternatives
+                        .split('|')
+                        .find_map(|name| syscall_table.find(name))
+                    else {
+                        continue;
+                    };
+                    sites.push(SyscallSite {
+                        address: insn.address,
+                        info: Some(SyscallInfo {
+                            arguments: arguments.clone(),
+                            ..info.clone()
+                        }),
+                        resolved: true,
+                        implied_by: implied_by.clone(),
+                    });
+                }
+            }
+        }
+        registers.update(insn);
+    }
+
+    sites
+}
+
+/// Returns the name under which a function is looked up in the knowledge base.
+pub(crate) fn wrapper_name(name: &str) -> &str {
+    let name = name.split_once('@').map_or(name, |(name, _)| name);
+    match name.rsplit_once("::h") {
+        Some((path, hash)) if hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            path
+        }
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::disassemble;
+
+    #[test]
+    fn test_wrapper_name() {
+        assert_eq!(wrapper_name("write@GLIBC_2.2.5"), "write");
+        assert_eq!(
+            wrapper_name("std::net::tcp::TcpListener::bind::h2b38693290bd2fa7"),
+            "std::net::tcp::TcpListener::bind"
+        );
+        assert_eq!(wrapper_name("std::thread::hooks"), "std::thread::hooks");
+        assert_eq!(wrapper_name("main"), "main");
+    }
+
+    #[test]
+    fn test_detect_implied_syscalls() {
+        let arch = Arch::X86_64;
+        let code = [
+            0xbf, 0x27, 0x00, 0x00, 0x00, // mov $0x27, %edi
+            0xe8, 0xf6, 0x0f, 0x00, 0x00, // call 0x2000 <syscall@GLIBC_2.2.5>
+            0xe8, 0xf1, 0x1f, 0x00, 0x00, // call 0x3000 <std::net::tcp::TcpListener::bind>
+            0xe8, 0xec, 0x2f, 0x00, 0x00, // call 0x4000 <helper>
+            0xbf, 0x0a, 0x00, 0x00, 0x00, // mov $0xa, %edi
+            0xbe, 0x02, 0x00, 0x00, 0x00, // mov $0x2, %esi
+            0x31, 0xd2, // xor %edx, %edx
+            0xe8, 0xdb, 0x3f, 0x00, 0x00, // call 0x5000 <socket@GLIBC_2.2.5>
+        ];
+        let mut main = FunctionNode::new("main".to_string(), 0x1000, 0x1025);
+        main.instructions = disassemble(&arch.capstone(false).unwrap(), arch, &code, 0x1000)
+            .unwrap()
+            .into_iter()
+            .map(|mut insn| {
+                insn.callee = insn.target();
+                insn
+            })
+            .collect();
+
+        let mut functions = HashMap::new();
+        for node in [
+            main,
+            FunctionNode::new("syscall@GLIBC_2.2.5".to_string(), 0x2000, 0x2000),
+            FunctionNode::new(
+                "std::net::tcp::TcpListener::bind::h2b38693290bd2fa7".to_string(),
+                0x3000,
+                0x3100,
+            ),
+            FunctionNode::new("helper".to_string(), 0x4000, 0x4100),
+            FunctionNode::new("socket@GLIBC_2.2.5".to_string(), 0x5000, 0x5000),
+        ] {
+            functions.insert(node.start_addr, node);
+        }
+
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = Elf::lazy_parse(header).unwrap();
+        let table = SyscallTable::bundled(arch).unwrap();
+        let wrappers = Wrappers::bundled().unwrap();
+        detect_implied_syscalls(&elf, &[], &mut functions, arch, &table, &wrappers);
+        // A second detection replaces the implied sites of the first one.
+        detect_implied_syscalls(&elf, &[], &mut functions, arch, &table, &wrappers);
+
+        let main = &functions[&0x1000];
+        let sites: Vec<_> = main
+            .syscalls
+            .iter()
+            .map(|site| (site.address, site.name(), site.implied_by.as_deref()))
+            .collect();
+        let bind = Some("std::net::tcp::TcpListener::bind::h2b38693290bd2fa7");
+        assert_eq!(
+            sites,
+            vec![
+                (0x1005, Some("getpid"), Some("syscall@GLIBC_2.2.5")),
+                (0x100a, Some("socket"), bind),
+                (0x100a, Some("bind"), bind),
+                (0x100a, Some("listen"), bind),
+                (0x1020, Some("socket"), Some("socket@GLIBC_2.2.5")),
+            ]
+        );
+        let socket = main.syscalls[4].info.as_ref().unwrap();
+        let arguments: Vec<_> = socket
+            .arguments
+            .iter()
+            .map(|argument| argument.decoded.as_deref())
+            .collect();
+        assert_eq!(
+            arguments,
+            vec![Some("AF_INET6"), Some("SOCK_DGRAM"), Some("IPPROTO_IP")]
+        );
+        assert!(main.syscalls[1].info.as_ref().unwrap().arguments.is_empty());
+        assert!(functions[&0x4000].syscalls.is_empty());
+    }
+
+    #[test]
+    fn test_detect_clone_wrapper_arguments() {
+        let arch = Arch::X86_64;
+        let code = [
+            0xbf, 0x30, 0x12, 0x42, 0x00, // mov $0x421230, %edi
+            0xbe, 0x00, 0x70, 0x00, 0x00, // mov $0x7000, %esi
+            0xba, 0x00, 0x03, 0x00, 0x00, // mov $0x300, %edx
+            0xe8, 0xec, 0x0f, 0x00, 0x00, // call 0x2000 <clone@GLIBC_2.2.5>
+        ];
+        let mut main = FunctionNode::new("main".to_string(), 0x1000, 0x1014);
+        main.instructions = disassemble(&arch.capstone(false).unwrap(), arch, &code, 0x1000)
+            .unwrap()
+            .into_iter()
+            .map(|mut insn| {
+                insn.callee = insn.target();
+                insn
+            })
+            .collect();
+        let mut functions: HashMap<FunctionId, FunctionNode> = [
+            main,
+            FunctionNode::new("clone@GLIBC_2.2.5".to_string(), 0x2000, 0x2000),
+        ]
+        .into_iter()
+        .map(|node| (node.start_addr, node))
+        .collect();
+
+        let header = goblin::elf::Header::new(goblin::container::Ctx::default());
+        let elf = Elf::lazy_parse(header).unwrap();
+        let table = SyscallTable::bundled(arch).unwrap();
+        let wrappers = Wrappers::bundled().unwrap();
+        detect_implied_syscalls(&elf, &[], &mut functions, arch, &table, &wrappers);
+
+        // The function run by the child is not taken for the flags of the system call.
+        let clone = functions[&0x1000].syscalls[0].info.as_ref().unwrap();
+        assert_eq!(clone.name, "clone");
+        let arguments: Vec<_> = clone
+            .arguments
+            .iter()
+            .map(|argument| (argument.name.as_str(), argument.decoded.as_deref()))
+            .collect();
+        assert_eq!(
+            arguments,
+            vec![
+                ("fn", None),
+                ("stack", None),
+                ("flags", Some("CLONE_VM|CLONE_FS")),
+            ]
+        );
+    }
+}
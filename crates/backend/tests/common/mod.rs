@@ -1,6 +1,10 @@
+// Shared helpers: not every integration test binary uses all of them.
+#![allow(dead_code)]
+
 use insta::{assert_snapshot, with_settings};
 use manifest_producer_backend::{
     analyse::analyse_functions,
+    arch::Arch,
     detect::function_detection,
     entry::find_main,
     inspect::{inspect_binary, parse_elf, read_elf},
@@ -16,12 +20,13 @@ pub fn run_analysis_test(binary_path: &str, test_name: &str) {
 
     let info = inspect_binary(&elf, binary_path, &output_path).unwrap();
 
-    let mut detected_functions = function_detection(&elf, &info.language).unwrap();
+    let mut detected_functions = function_detection(&elf, &elf_buffer, &info.language).unwrap();
     analyse_functions(
         &elf,
         &elf_buffer,
         &mut detected_functions,
         &info.language,
+        None,
         &output_path,
     )
     .unwrap();
@@ -29,7 +34,7 @@ pub fn run_analysis_test(binary_path: &str, test_name: &str) {
     let inspect_json = read_and_sort_json(Path::new(&output_path).join("json/basic_info.json"));
     let analyse_json = read_and_sort_json(Path::new(&output_path).join("json/functions_list.json"));
 
-    let root_nodes = find_main(&detected_functions).unwrap();
+    let root_nodes = find_main(&detected_functions, Arch::from_elf(&elf).unwrap()).unwrap();
     let root_nodes_json = serde_json::to_string_pretty(&root_nodes).unwrap();
 
     with_settings!(
@@ -3,6 +3,7 @@ mod common;
 
 // In view of the changes made to the code and the real light-firmware firmware, recreate the environment for testing
 #[test]
+#[ignore = "placeholder until the light-firmware environment is recreated"]
 fn test_light_firmware() {
     todo!(
         "This test is currently a placeholder. It needs to be implemented to run the analysis on the light-firmware."
@@ -1024,7 +1024,7 @@ fn check_entry_point(elf: &Elf) -> ValidationResult {
     }
     // If entry point is in an executable and writable segment
     else if in_executable && in_writable {
-        return ValidationResult {
+        ValidationResult {
             name: "Entry Point".to_string(),
             status: false,
             description: "The entry point is in an executable and writable segment, which is a potential security risk (W^X violation).".to_string(),
@@ -1035,11 +1035,11 @@ fn check_entry_point(elf: &Elf) -> ValidationResult {
                 "in_writable": in_writable,
                 "section_type": section_type
             })),
-        };
+        }
     }
     // If entry point is not in an executable segment
     else if !in_executable {
-        return ValidationResult {
+        ValidationResult {
             name: "Entry Point".to_string(),
             status: false,
             description: "The entry point is not located in an executable segment, which may indicate an improperly configured ELF file.".to_string(),
@@ -1050,10 +1050,10 @@ fn check_entry_point(elf: &Elf) -> ValidationResult {
                 "in_writable": in_writable,
                 "section_type": section_type
             })),
-        };
+        }
     } else {
         // Entry point is valid
-        return ValidationResult {
+        ValidationResult {
             name: "Entry Point".to_string(),
             status: true,
             description: "The entry point is valid and located in an executable segment."
@@ -1065,7 +1065,7 @@ fn check_entry_point(elf: &Elf) -> ValidationResult {
                 "in_writable": in_writable,
                 "section_type": section_type
             })),
-        };
+        }
     }
 }
 
@@ -4,33 +4,40 @@ use std::{
     io::Write,
 };
 
-use crate::{error::Result, html_builder::sanitize_name, TreeNode};
-use manifest_producer_backend::FunctionNode;
+use crate::{error::Result, html_builder::page_name, EdgeKind, TreeNode};
+use manifest_producer_backend::{categories::CategoryProfile, FunctionId, FunctionNode};
 use serde_json::to_string_pretty;
 
-/// Constructs a function call graph and generates a JSON representation.
+/// Constructs the function call graphs of the root functions and generates their JSON
+/// representations.
 ///
-/// This function scans and processes detected functions to build a tree structure
-/// representing their relationships. The resulting structure is then serialized
-/// into a JSON file for visualization.
+/// This function scans and processes detected functions to build one tree structure
+/// representing their relationships for each root function. Each resulting structure is
+/// then serialized into a JSON file for visualization, named after the root function and its
+/// address. Every node carries the behaviour categories of the system calls issued by its
+/// function (see [`CategoryProfile::of_function`]). The threads started by a function (see
+/// [`FunctionNode::spawns`]) follow its calls as children reached through an
+/// [`EdgeKind::Spawn`] edge, and the functions it only tail-calls (see
+/// [`FunctionNode::tail_calls`]) are reached through an [`EdgeKind::TailCall`] edge.
 ///
 /// # Arguments
 ///
-/// - `detected_functions`: A mutable reference to a map of function names to their [`FunctionNode`] objects.
-/// - `root_function`: The entry point function name used as the root of the graph.
+/// - `detected_functions`: A mutable reference to a map of function identifiers to their [`FunctionNode`] objects.
+/// - `root_functions`: The identifiers of the functions used as the roots of the graphs.
 /// - `output_path`: The directory where the generated JSON file should be saved.
 /// - `max_depth`: An optional depth limit for the function call graph.
 ///
 /// # Workflow
 ///
-/// 1. Scans and collects relevant function nodes.
+/// 1. Scans and collects the function nodes reachable from any root function.
 /// 2. Removes unnecessary nodes.
-/// 3. Builds a hierarchical tree representation.
-/// 4. Serializes the tree structure into JSON.
+/// 3. Builds a hierarchical tree representation for each root function.
+/// 4. Serializes each tree structure into JSON.
 ///
 /// # Returns
 ///
-/// - `Ok(TreeNode)`: If the graph is successfully built and saved.
+/// - `Ok(Vec<TreeNode>)`: The trees of the root functions, in the same order, if the graphs are
+///   successfully built and saved.
 /// - `Err(e)`: If any errors occur during processing.
 ///
 /// # Errors
@@ -40,53 +47,63 @@ use serde_json::to_string_pretty;
 /// - Problems in constructing the tree structure.
 /// - Failures in writing the JSON output file.
 pub fn graph_builder<S: ::std::hash::BuildHasher>(
-    detected_functions: &mut HashMap<String, FunctionNode, S>,
-    root_function: &str,
+    detected_functions: &mut HashMap<FunctionId, FunctionNode, S>,
+    root_functions: &[FunctionId],
     output_path: &str,
     max_depth: Option<usize>,
-) -> Result<TreeNode> {
+) -> Result<Vec<TreeNode>> {
     let mut tree_nodes = HashSet::new();
-    node_scanner(root_function, detected_functions, &mut tree_nodes);
+    for &root_function in root_functions {
+        node_scanner(root_function, detected_functions, &mut tree_nodes);
+    }
     rm_useless_node(detected_functions, &mut tree_nodes);
 
-    let mut active_stack = Vec::new();
-    let mut id_counter = 0;
-    let js_tree = build(
-        root_function,
-        &*detected_functions,
-        &mut id_counter,
-        &mut active_stack,
-        0,
-        max_depth.unwrap_or(15),
-    );
+    let mut js_trees = Vec::with_capacity(root_functions.len());
+    for &root_function in root_functions {
+        let mut active_stack = Vec::new();
+        let mut id_counter = 0;
+        let js_tree = build(
+            root_function,
+            &*detected_functions,
+            &mut id_counter,
+            &mut active_stack,
+            0,
+            max_depth.unwrap_or(15),
+        );
 
-    graph_json(&js_tree, root_function, output_path)?;
+        graph_json(
+            &js_tree,
+            &page_name(&js_tree.text, root_function),
+            output_path,
+        )?;
+        js_trees.push(js_tree);
+    }
 
-    Ok(js_tree)
+    Ok(js_trees)
 }
 
 fn node_scanner<S: ::std::hash::BuildHasher>(
-    function_name: &str,
-    detected_functions: &HashMap<String, FunctionNode, S>,
-    tree_nodes: &mut HashSet<String>,
+    function_id: FunctionId,
+    detected_functions: &HashMap<FunctionId, FunctionNode, S>,
+    tree_nodes: &mut HashSet<FunctionId>,
 ) {
-    if tree_nodes.contains(function_name) {
+    if tree_nodes.contains(&function_id) {
         return;
     }
 
-    if let Some(node) = detected_functions.get(function_name) {
-        tree_nodes.insert(function_name.to_string());
-        for child_name in &node.children {
-            node_scanner(child_name, detected_functions, tree_nodes);
+    if let Some(node) = detected_functions.get(&function_id) {
+        tree_nodes.insert(function_id);
+        for child_id in node.children.iter().copied().chain(node.thread_bodies()) {
+            node_scanner(child_id, detected_functions, tree_nodes);
         }
     }
 }
 
 fn rm_useless_node<S: ::std::hash::BuildHasher>(
-    detected_functions: &mut HashMap<String, FunctionNode, S>,
-    tree_nodes: &mut HashSet<String>,
+    detected_functions: &mut HashMap<FunctionId, FunctionNode, S>,
+    tree_nodes: &mut HashSet<FunctionId>,
 ) {
-    let keys: Vec<String> = detected_functions.keys().cloned().collect();
+    let keys: Vec<FunctionId> = detected_functions.keys().copied().collect();
     for func in keys {
         if tree_nodes.contains(&func) {
             tree_nodes.remove(&func);
@@ -97,33 +114,46 @@ fn rm_useless_node<S: ::std::hash::BuildHasher>(
 }
 
 fn build<S: ::std::hash::BuildHasher>(
-    function_name: &str,
-    detected_functions: &HashMap<String, FunctionNode, S>,
+    function_id: FunctionId,
+    detected_functions: &HashMap<FunctionId, FunctionNode, S>,
     id_counter: &mut usize,
-    active_stack: &mut Vec<String>,
+    active_stack: &mut Vec<FunctionId>,
     depth: usize,
     max_depth: usize,
 ) -> TreeNode {
-    if depth >= max_depth || active_stack.contains(&function_name.to_string()) {
-        let node = TreeNode::new(*id_counter, function_name);
-        *id_counter += 1;
+    let function_name = function_name(function_id, detected_functions);
+    let mut node = TreeNode::new(*id_counter, &function_name);
+    *id_counter += 1;
+    if let Some(function) = detected_functions.get(&function_id) {
+        node.categories = CategoryProfile::of_function(function)
+            .categories()
+            .collect();
+    }
+    if depth >= max_depth || active_stack.contains(&function_id) {
         return node;
     }
 
-    active_stack.push(function_name.to_string());
-    let mut node = TreeNode::new(*id_counter, function_name);
-    *id_counter += 1;
+    active_stack.push(function_id);
 
-    if let Some(call_tree) = detected_functions.get(function_name) {
-        for child_name in &call_tree.children {
-            let child_node = build(
-                child_name,
+    if let Some(call_tree) = detected_functions.get(&function_id) {
+        let children = call_tree.children.iter().map(|&id| {
+            if call_tree.tail_calls.contains(&id) {
+                (id, EdgeKind::TailCall)
+            } else {
+                (id, EdgeKind::Call)
+            }
+        });
+        let spawns = call_tree.thread_bodies().map(|id| (id, EdgeKind::Spawn));
+        for (child_id, edge) in children.chain(spawns) {
+            let mut child_node = build(
+                child_id,
                 detected_functions,
                 id_counter,
                 active_stack,
                 depth + 1,
                 max_depth,
             );
+            child_node.edge = edge;
             node.add_child(child_node);
         }
     }
@@ -132,10 +162,19 @@ fn build<S: ::std::hash::BuildHasher>(
     node
 }
 
-fn graph_json(tree: &TreeNode, root_name: &str, output_path: &str) -> Result<()> {
+/// Returns the name of the function `function_id`, or its address if it is unknown.
+pub(crate) fn function_name<S: ::std::hash::BuildHasher>(
+    function_id: FunctionId,
+    detected_functions: &HashMap<FunctionId, FunctionNode, S>,
+) -> String {
+    detected_functions
+        .get(&function_id)
+        .map_or_else(|| format!("0x{function_id:x}"), |node| node.name.clone())
+}
+
+fn graph_json(tree: &TreeNode, page: &str, output_path: &str) -> Result<()> {
     let json_data = to_string_pretty(tree)?;
-    let safe_name = sanitize_name(root_name);
-    let output_path = format!("{output_path}/json/{safe_name}.json");
+    let output_path = format!("{output_path}/json/{page}.json");
     let mut file = File::create(&output_path)?;
     file.write_all(json_data.as_bytes())?;
 
@@ -1,35 +1,58 @@
-use std::{collections::HashMap, fs::File, io::Write};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Write,
+};
 
-use crate::{error::Result, graph_builder::graph_builder, TreeNode};
-use manifest_producer_backend::{BasicInfo, FunctionNode};
+use crate::{
+    error::Result,
+    graph_builder::{function_name, graph_builder},
+    TreeNode,
+};
+use manifest_producer_backend::{
+    capabilities::{CapabilityEvidence, CapabilityReachability},
+    categories::{CategoryProfile, SyscallCategory},
+    reachability::SyscallReachability,
+    roots::Root,
+    verify::VerificationReport,
+    BasicInfo, FunctionId, FunctionNode,
+};
 
-use minijinja::{context, Environment};
+use minijinja::{context, Environment, Value};
 use serde_json;
 
 /// Generates HTML reports from analysis results.
 ///
 /// This function creates various HTML pages summarizing the results of the analysis, including:
 ///
-/// - An index page with general metadata.
+/// - An index page with general metadata, a summary of the behaviours of the root functions,
+///   from the categories of the system calls reachable from them (see [`CategoryProfile`]),
+///   and the Linux capabilities they need with the call paths proving them (see
+///   [`CapabilityReachability`]).
 /// - A functions page listing detected functions.
-/// - A root page displaying the entry point functions.
-/// - A call graph page visualizing function relationships.
+/// - A root page listing the root functions with their kind and the evidence for them (see
+///   [`discover_roots`](manifest_producer_backend::roots::discover_roots)).
+/// - One call graph page for each root function, visualizing function relationships,
+///   coloured by the categories of the system calls of the functions, together with the
+///   system calls reachable from the root function and a shortest call path to each of them.
 ///
 /// # Arguments
 ///
 /// - `basic_info`: Metadata about the binary being analyzed.
-/// - `detected_functions`: A mutable map of function names to their associated `FunctionNode` objects.
-/// - `root_nodes`: The root function names identified during analysis.
+/// - `detected_functions`: A mutable map of function identifiers to their associated `FunctionNode` objects.
+/// - `roots`: The root functions identified during analysis.
 /// - `output_path`: The directory where the HTML files should be saved.
 /// - `max_depth`: An optional depth limit for the function call graph.
 ///
 /// # Workflow
 ///
-/// 1. Generates the index page using `render_index_page`.
-/// 2. Creates the functions listing with `render_functions_page`.
-/// 3. Renders the root function overview using `render_root_page`.
-/// 4. Builds the function call graph using `graph_builder`.
-/// 5. Produces the tree visualization with `render_tree_page`.
+/// 1. Computes the reachable system calls with [`SyscallReachability`], their categories and
+///    the capabilities they need.
+/// 2. Generates the index page using `render_index_page`.
+/// 3. Creates the functions listing with `render_functions_page`.
+/// 4. Renders the root functions overview using `render_root_page`.
+/// 5. Builds the function call graphs using `graph_builder`.
+/// 6. Produces the tree visualization of each root function with `render_tree_page`.
 ///
 /// # Returns
 ///
@@ -44,22 +67,235 @@ use serde_json;
 /// - Failures in function call graph construction.
 pub fn html_builder<S: ::std::hash::BuildHasher>(
     basic_info: &BasicInfo,
-    detected_functions: &mut HashMap<String, FunctionNode, S>,
-    root_nodes: &str,
+    detected_functions: &mut HashMap<FunctionId, FunctionNode, S>,
+    roots: &[Root],
     output_path: &str,
     max_depth: Option<usize>,
 ) -> Result<()> {
-    let safe_root_name = sanitize_name(root_nodes);
-    render_index_page(basic_info, detected_functions.len(), output_path)?;
+    let root_ids: Vec<FunctionId> = roots.iter().map(|root| root.id).collect();
+    let root_names: Vec<String> = roots.iter().map(|root| root.name.clone()).collect();
+    let reachability = SyscallReachability::new(detected_functions);
+    let capability_reachability = CapabilityReachability::new(detected_functions);
+
+    let mut profile = CategoryProfile::default();
+    let mut evidence = Vec::new();
+    let mut syscalls = Vec::with_capacity(roots.len());
+    for &root_function in &root_ids {
+        let root_profile =
+            CategoryProfile::reachable(&reachability, detected_functions, root_function);
+        syscalls.push(reachable_syscalls(
+            &reachability,
+            &root_profile,
+            root_function,
+            detected_functions,
+        ));
+        for (category, names) in root_profile.syscalls {
+            profile.syscalls.entry(category).or_default().extend(names);
+        }
+        evidence.extend(capability_reachability.evidence(detected_functions, root_function));
+    }
+    // The same call may be reached from several roots: the path from the first one is kept.
+    let mut seen = HashSet::new();
+    evidence.retain(|evidence| {
+        seen.insert((
+            evidence.capability,
+            evidence.address,
+            evidence.syscall.clone(),
+        ))
+    });
+    evidence.sort_by_key(|evidence| evidence.capability);
+    let capabilities = required_capabilities(&evidence, detected_functions);
+
+    render_index_page(
+        basic_info,
+        detected_functions.len(),
+        &root_names,
+        &profile,
+        &capabilities,
+        output_path,
+    )?;
     render_functions_page(detected_functions, output_path)?;
-    render_root_page(&safe_root_name, output_path)?;
+    render_root_page(roots, output_path)?;
 
-    let js_tree = graph_builder(detected_functions, &safe_root_name, output_path, max_depth)?;
-    render_tree_page(&safe_root_name, &js_tree, output_path)?;
+    let js_trees = graph_builder(detected_functions, &root_ids, output_path, max_depth)?;
+    for ((root, js_tree), syscalls) in roots.iter().zip(&js_trees).zip(&syscalls) {
+        render_tree_page(
+            &root.name,
+            &page_name(&root.name, root.id),
+            js_tree,
+            syscalls,
+            output_path,
+        )?;
+    }
     Ok(())
 }
 
-fn render_index_page(basic_info: &BasicInfo, num_func: usize, output_path: &str) -> Result<()> {
+/// Returns the system calls reachable from `root` with their categories and witness paths,
+/// named after the functions they go through.
+fn reachable_syscalls<S: ::std::hash::BuildHasher>(
+    reachability: &SyscallReachability,
+    profile: &CategoryProfile,
+    root: FunctionId,
+    detected_functions: &HashMap<FunctionId, FunctionNode, S>,
+) -> Vec<Value> {
+    reachability
+        .witnesses(root)
+        .into_iter()
+        .map(|witness| {
+            let call_path: Vec<String> = witness
+                .path
+                .iter()
+                .map(|&id| function_name(id, detected_functions))
+                .collect();
+            let categories: Vec<SyscallCategory> = profile
+                .syscalls
+                .iter()
+                .filter(|(_, syscalls)| syscalls.contains(&witness.syscall))
+                .map(|(&category, _)| category)
+                .collect();
+            context! {
+                syscall => witness.syscall,
+                categories => categories,
+                call_path => call_path,
+            }
+        })
+        .collect()
+}
+
+/// Returns the categories of a profile with their labels and system calls.
+fn profile_categories(profile: &CategoryProfile) -> Vec<Value> {
+    profile
+        .syscalls
+        .iter()
+        .map(|(category, syscalls)| {
+            context! {
+                key => category,
+                label => category.label(),
+                syscalls => syscalls,
+            }
+        })
+        .collect()
+}
+
+/// Returns the capabilities needed with their description and evidence, the call paths being
+/// named after the functions they go through.
+fn required_capabilities<S: ::std::hash::BuildHasher>(
+    evidence: &[CapabilityEvidence],
+    detected_functions: &HashMap<FunctionId, FunctionNode, S>,
+) -> Vec<Value> {
+    evidence
+        .chunk_by(|first, second| first.capability == second.capability)
+        .map(|group| {
+            let capability = group[0].capability;
+            let evidence: Vec<Value> = group
+                .iter()
+                .map(|evidence| {
+                    let call_path: Vec<String> = evidence
+                        .path
+                        .iter()
+                        .map(|&id| function_name(id, detected_functions))
+                        .collect();
+                    context! {
+                        call => evidence.call,
+                        address => format!("{:#x}", evidence.address),
+                        implied_by => evidence.implied_by,
+                        call_path => call_path,
+                    }
+                })
+                .collect();
+            context! {
+                name => capability.name(),
+                description => capability.description(),
+                evidence => evidence,
+            }
+        })
+        .collect()
+}
+
+/// Returns every category with its label, for the legends.
+fn category_legend() -> Vec<Value> {
+    SyscallCategory::ALL
+        .iter()
+        .map(|category| {
+            context! {
+                key => category,
+                label => category.label(),
+            }
+        })
+        .collect()
+}
+
+/// Generates the HTML page of a manifest verification.
+///
+/// The page lists the behaviours of the [`VerificationReport`] in three groups: declared and
+/// observed, declared but not observed, and observed but undeclared. Observed behaviours are
+/// shown together with the call paths proving them.
+///
+/// # Arguments
+///
+/// - `report`: The report returned by [`verify_manifest`](manifest_producer_backend::verify::verify_manifest).
+/// - `output_path`: The directory where the `verification.html` file should be saved.
+///
+/// # Returns
+///
+/// - `Ok(())`: If the HTML file is generated successfully.
+/// - `Err(e)`: If any operation fails, an error is returned.
+///
+/// # Errors
+///
+/// Errors may arise from:
+/// - Template rendering failures.
+/// - File I/O failures during HTML generation.
+pub fn verification_builder(report: &VerificationReport, output_path: &str) -> Result<()> {
+    let mut env = Environment::new();
+    env.add_template(
+        "verification.html",
+        include_str!("templates/verification.html"),
+    )?;
+
+    let groups = vec![
+        context! {
+            title => "Declared and observed",
+            description => "Behaviours declared by the manufacturer and confirmed by the analysis.",
+            class => "is-confirmed",
+            icon => "fa-check-circle",
+            behaviours => &report.declared_and_observed,
+        },
+        context! {
+            title => "Declared but not observed",
+            description => "Behaviours declared by the manufacturer that the analysis could not find.",
+            class => "is-missing",
+            icon => "fa-question-circle",
+            behaviours => &report.declared_not_observed,
+        },
+        context! {
+            title => "Observed but undeclared",
+            description => "Behaviours found by the analysis that are missing from the declaration.",
+            class => "is-undeclared",
+            icon => "fa-exclamation-circle",
+            behaviours => &report.observed_undeclared,
+        },
+    ];
+
+    let template = env.get_template("verification.html")?;
+    let rendered = template.render(context! {
+        report => report,
+        groups => groups,
+    })?;
+
+    let mut file = File::create(format!("{output_path}/verification.html"))?;
+    file.write_all(rendered.as_bytes())?;
+    Ok(())
+}
+
+fn render_index_page(
+    basic_info: &BasicInfo,
+    num_func: usize,
+    root_names: &[String],
+    profile: &CategoryProfile,
+    capabilities: &[Value],
+    output_path: &str,
+) -> Result<()> {
     let mut env = Environment::new();
     env.add_template("index.html", include_str!("templates/index.html"))?;
 
@@ -67,6 +303,10 @@ fn render_index_page(basic_info: &BasicInfo, num_func: usize, output_path: &str)
     let rendered = template.render(context! {
         basic_info => basic_info,
         num_func => num_func,
+        root_names => root_names,
+        summary => profile.summary(),
+        categories => profile_categories(profile),
+        capabilities => capabilities,
     })?;
 
     let mut file = File::create(format!("{output_path}/index.html"))?;
@@ -75,7 +315,7 @@ fn render_index_page(basic_info: &BasicInfo, num_func: usize, output_path: &str)
 }
 
 fn render_functions_page<S: ::std::hash::BuildHasher>(
-    detected_functions: &mut HashMap<String, FunctionNode, S>,
+    detected_functions: &mut HashMap<FunctionId, FunctionNode, S>,
     output_path: &str,
 ) -> Result<()> {
     let functions: Vec<FunctionNode> = detected_functions.values().cloned().collect();
@@ -96,7 +336,19 @@ fn render_functions_page<S: ::std::hash::BuildHasher>(
     Ok(())
 }
 
-fn render_root_page(roots: &str, output_path: &str) -> Result<()> {
+fn render_root_page(roots: &[Root], output_path: &str) -> Result<()> {
+    let roots: Vec<Value> = roots
+        .iter()
+        .map(|root| {
+            context! {
+                name => root.name,
+                page => page_name(&root.name, root.id),
+                kind => root.kind.label(),
+                evidence => root.evidence,
+            }
+        })
+        .collect();
+
     let mut env = Environment::new();
     env.add_template(
         "root_functions.html",
@@ -105,7 +357,7 @@ fn render_root_page(roots: &str, output_path: &str) -> Result<()> {
 
     let template = env.get_template("root_functions.html")?;
     let rendered = template.render(context! {
-        root => roots,
+        roots => roots,
     })?;
 
     let mut file = File::create(format!("{output_path}/root_functions.html"))?;
@@ -113,7 +365,13 @@ fn render_root_page(roots: &str, output_path: &str) -> Result<()> {
     Ok(())
 }
 
-fn render_tree_page(root_name: &str, js_tree: &TreeNode, output_path: &str) -> Result<()> {
+fn render_tree_page(
+    root_name: &str,
+    page: &str,
+    js_tree: &TreeNode,
+    syscalls: &[Value],
+    output_path: &str,
+) -> Result<()> {
     let mut env = Environment::new();
     env.add_template("call_tree.html", include_str!("templates/call_tree.html"))?;
 
@@ -124,14 +382,23 @@ fn render_tree_page(root_name: &str, js_tree: &TreeNode, output_path: &str) -> R
     let rendered = template.render(context! {
         root_name => root_name,
         js_tree => js_tree_json,
+        syscalls => syscalls,
+        legend => category_legend(),
     })?;
 
-    let mut file = File::create(format!("{output_path}/call_trees/{root_name}.html"))?;
+    let mut file = File::create(format!("{output_path}/call_trees/{page}.html"))?;
     file.write_all(rendered.as_bytes())?;
 
     Ok(())
 }
 
+/// Returns the name of the call graph page and JSON file of a root function: its name followed
+/// by its address, as several roots may share a name (e.g., `static` functions of different
+/// files, or the closures run by threads).
+pub(crate) fn page_name(name: &str, id: FunctionId) -> String {
+    format!("{}_{id:x}", sanitize_name(name))
+}
+
 pub(crate) fn sanitize_name(name: &str) -> String {
     name.chars()
         .map(|c| match c {
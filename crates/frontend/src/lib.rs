@@ -2,6 +2,8 @@ pub mod error;
 pub mod graph_builder;
 pub mod html_builder;
 
+use manifest_producer_backend::categories::SyscallCategory;
+
 /// Represents a tree structure used for visualizing interactions between functions.
 ///
 /// The `TreeNode` struct is designed to facilitate the hierarchical representation of
@@ -15,6 +17,9 @@ pub mod html_builder;
 /// - `id`: A unique identifier for the node.
 /// - `text`: A label describing the node.
 /// - `children`: An optional vector of child nodes.
+/// - `categories`: The behaviour categories of the system calls issued by the function, which
+///   select the colour of the node.
+/// - `edge`: How the function is reached from its parent, which selects the style of the edge.
 ///
 /// # Example
 ///
@@ -33,6 +38,10 @@ pub struct TreeNode {
     pub text: String,
     #[serde(rename = "children")]
     pub children: Option<Box<Vec<TreeNode>>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<SyscallCategory>,
+    #[serde(default, skip_serializing_if = "EdgeKind::is_call")]
+    pub edge: EdgeKind,
 }
 impl TreeNode {
     /// Creates a new `TreeNode` with no children.
@@ -51,6 +60,8 @@ impl TreeNode {
             id,
             text: text.to_string(),
             children: None,
+            categories: Vec::new(),
+            edge: EdgeKind::Call,
         }
     }
 
@@ -67,3 +78,26 @@ impl TreeNode {
         }
     }
 }
+
+/// How a node of a [`TreeNode`] tree is reached from its parent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EdgeKind {
+    /// The parent calls the function.
+    #[default]
+    Call,
+    /// The parent jumps to the function instead of calling it (see
+    /// [`FunctionNode::tail_calls`](manifest_producer_backend::FunctionNode::tail_calls)).
+    TailCall,
+    /// The parent starts a thread running the function (see
+    /// [`FunctionNode::spawns`](manifest_producer_backend::FunctionNode::spawns)).
+    Spawn,
+}
+
+impl EdgeKind {
+    /// Whether the edge is a plain call, the kind omitted from the JSON output.
+    #[must_use]
+    pub fn is_call(&self) -> bool {
+        *self == Self::Call
+    }
+}
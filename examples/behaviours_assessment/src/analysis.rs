@@ -1,10 +1,15 @@
 use manifest_producer_backend::{
     analyse::analyse_functions,
+    arch::Arch,
     detect::function_detection,
-    entry::find_main,
     inspect::{inspect_binary, parse_elf, read_elf},
+    manifest::generate_manifest,
+    roots::discover_roots,
+    seccomp::generate_seccomp_profile,
+    syscall::SyscallTable,
+    verify::{load_manifest, verify_manifest},
 };
-use manifest_producer_frontend::html_builder::html_builder;
+use manifest_producer_frontend::html_builder::{html_builder, verification_builder};
 
 use crate::error::Result;
 
@@ -15,6 +20,8 @@ use crate::error::Result;
 /// - `elf_path`: A string slice containing the path to the ELF binary.
 /// - `output_path`: A string slice specifying the directory where the analysis results will be saved.
 /// - `max_depth`: An optional depth limit for the function call graph.
+/// - `declared_manifest`: An optional path to a manufacturer-declared manifest to verify.
+/// - `syscall_table`: An optional path to a syscall table replacing the bundled one.
 ///
 /// # Workflow
 ///
@@ -23,8 +30,13 @@ use crate::error::Result;
 /// 3. **Inspect Metadata**: Extracts metadata and high-level details about the binary using [`inspect_binary`].
 /// 4. **Detect Functions**: Identifies functions within the binary with [`function_detection`].
 /// 5. **Analyze Functions**: Performs in-depth analysis of the identified functions using [`analyse_functions`].
-/// 6. **Find Main**: Identifies entry point in the binary with [`find_main`].
-/// 7. **Generate HTML Report**: Produces an interactive HTML-based summary using [`html_builder`].
+/// 6. **Discover Roots**: Identifies the root functions of the binary (main, constructors, signal handlers, ...) with [`discover_roots`].
+/// 7. **Produce Manifest**: Summarises the detected behaviours in a versioned manifest using [`generate_manifest`].
+/// 8. **Generate Seccomp Profile**: Derives a seccomp allowlist of the reachable system calls using [`generate_seccomp_profile`].
+/// 9. **Generate HTML Report**: Produces an interactive HTML-based summary using [`html_builder`].
+///
+/// When a declared manifest is provided, it is compared with the produced one using
+/// [`verify_manifest`] and the result is rendered by [`verification_builder`].
 ///
 /// # Returns
 ///
@@ -39,36 +51,97 @@ use crate::error::Result;
 /// - Analysis errors in downstream function calls.
 /// - HTML generation failures.
 #[allow(clippy::module_name_repetitions)]
-pub fn perform_analysis(elf_path: &str, output_path: &str, max_depth: Option<usize>) -> Result<()> {
-    println!("\n[STEP 1/7] Reading ELF binary from '{elf_path}'");
+pub fn perform_analysis(
+    elf_path: &str,
+    output_path: &str,
+    max_depth: Option<usize>,
+    declared_manifest: Option<&str>,
+    syscall_table: Option<&str>,
+) -> Result<()> {
+    println!("\n[STEP 1/9] Reading ELF binary from '{elf_path}'");
     let buffer = read_elf(elf_path)?;
 
-    println!("[STEP 2/7] Parsing ELF structure...");
+    println!("[STEP 2/9] Parsing ELF structure...");
     let elf = parse_elf(&buffer)?;
 
-    println!("[STEP 3/7] Inspecting binary metadata...");
+    println!("[STEP 3/9] Inspecting binary metadata...");
     let info = inspect_binary(&elf, elf_path, output_path)?;
 
-    println!("[STEP 4/7] Detecting function symbols...");
-    let mut detected_functions = function_detection(&elf, &info.language)?;
+    println!("[STEP 4/9] Detecting function symbols...");
+    let mut detected_functions = function_detection(&elf, &buffer, &info.language)?;
 
-    println!("[STEP 5/7] Analysing function control flow...");
+    println!("[STEP 5/9] Analysing function control flow...");
+    let syscall_table = syscall_table.map(SyscallTable::from_file).transpose()?;
     analyse_functions(
         &elf,
         &buffer,
         &mut detected_functions,
         &info.language,
+        syscall_table.as_ref(),
+        output_path,
+    )?;
+
+    println!("[STEP 6/9] Discovering root functions...");
+    let arch = Arch::from_elf(&elf)?;
+    let roots = discover_roots(&elf, &buffer, &detected_functions, arch)?;
+    for root in &roots {
+        println!(
+            "[ROOT] {} ({}): {}",
+            root.name,
+            root.kind.label(),
+            root.evidence
+        );
+    }
+    let mut root_ids: Vec<_> = roots.iter().map(|root| root.id).collect();
+
+    println!("[STEP 7/9] Producing behaviour manifest...");
+    let manifest = generate_manifest(
+        &info,
+        &elf,
+        &buffer,
+        &detected_functions,
+        &root_ids,
         output_path,
     )?;
 
-    println!("[STEP 6/7] Searching for main function...");
-    let main_name = find_main(&detected_functions)?;
+    if let Some(declared_path) = declared_manifest {
+        println!("[VERIFY] Comparing manifest with '{declared_path}'...");
+        let declared = load_manifest(declared_path)?;
+        let report = verify_manifest(&declared, &manifest, &detected_functions, output_path)?;
+        verification_builder(&report, output_path)?;
+        println!(
+            "[VERIFY] {} declared and observed, {} declared but not observed, {} observed but undeclared",
+            report.declared_and_observed.len(),
+            report.declared_not_observed.len(),
+            report.observed_undeclared.len()
+        );
+    }
+
+    println!("[STEP 8/9] Generating seccomp profile...");
+    let syscall_table = match syscall_table {
+        Some(table) => table,
+        None => SyscallTable::bundled(arch)?,
+    };
+    let entry = arch.code_address(elf.entry);
+    if detected_functions.contains_key(&entry) && !root_ids.contains(&entry) {
+        root_ids.push(entry);
+    }
+    let profile = generate_seccomp_profile(
+        &elf,
+        &detected_functions,
+        &root_ids,
+        &syscall_table,
+        output_path,
+    )?;
+    for warning in &profile.warnings {
+        println!("[WARNING] {warning}");
+    }
 
-    println!("[STEP 7/7] Generating HTML report...");
+    println!("[STEP 9/9] Generating HTML report...");
     html_builder(
         &info,
         &mut detected_functions,
-        &main_name.name,
+        &roots,
         output_path,
         max_depth,
     )?;
@@ -4,13 +4,25 @@ use clap::{Arg, Command};
 ///
 /// # Returns
 ///
-/// - `Ok((elf_path, output_path))`: A tuple containing the path to the ELF binary and the output directory.
-/// - `Err(e)`: If argument parsing fails, an error is returned.
+/// - `(elf_path, output_path, depth, declared_manifest, syscall_table)`: A tuple containing the path
+///   to the ELF binary, the output directory, the optional call graph depth, the optional path to a
+///   declared manifest and the optional path to a syscall table.
 ///
 /// # Arguments Parsed
 ///
 /// - `elf_path` (required): Path to the ELF binary to be analyzed.
-pub fn parse_arguments() -> (String, String, Option<usize>) {
+/// - `depth` (optional): Depth limit for the call graph.
+/// - `--verify <MANIFEST>` (optional): Path to a manufacturer-declared manifest to verify
+///   against the detected behaviour.
+/// - `--syscall-table <TABLE>` (optional): Path to a JSON syscall table replacing the one bundled
+///   for the architecture of the binary.
+pub fn parse_arguments() -> (
+    String,
+    String,
+    Option<usize>,
+    Option<String>,
+    Option<String>,
+) {
     let matches = Command::new("behaviours-assessment")
         .version("0.1.0")
         .author("Giuseppe Marco Bianco <giuseppe.bianco1@uniurb.it>")
@@ -28,6 +40,20 @@ pub fn parse_arguments() -> (String, String, Option<usize>) {
                 .num_args(1)
                 .value_parser(clap::value_parser!(usize)),
         )
+        .arg(
+            Arg::new("verify")
+                .long("verify")
+                .help("Verify a manufacturer-declared manifest against the detected behaviour")
+                .value_name("MANIFEST")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("syscall_table")
+                .long("syscall-table")
+                .help("Name the system calls with a custom JSON syscall table")
+                .value_name("TABLE")
+                .num_args(1),
+        )
         .get_matches();
 
     let elf_path = matches.get_one::<String>("elf_path").unwrap().to_string();
@@ -40,6 +66,14 @@ pub fn parse_arguments() -> (String, String, Option<usize>) {
     output_path.push(name);
 
     let depth = matches.get_one::<usize>("depth").copied();
+    let declared_manifest = matches.get_one::<String>("verify").cloned();
+    let syscall_table = matches.get_one::<String>("syscall_table").cloned();
 
-    (elf_path, output_path.to_string_lossy().into_owned(), depth)
+    (
+        elf_path,
+        output_path.to_string_lossy().into_owned(),
+        depth,
+        declared_manifest,
+        syscall_table,
+    )
 }
@@ -30,13 +30,20 @@ pub fn main() {
 }
 
 fn run() -> Result<()> {
-    let (elf_path, output_path, max_depth) = cli::parse_arguments();
+    let (elf_path, output_path, max_depth, declared_manifest, syscall_table) =
+        cli::parse_arguments();
     println!("Results folder creation: {output_path:?}");
     dirs::setup_output_dir(&output_path)?;
     println!("Performing checks...");
     checker::perform_checks(&elf_path, &output_path)?;
     println!("Performing analysis...");
-    analysis::perform_analysis(&elf_path, &output_path, max_depth)?;
+    analysis::perform_analysis(
+        &elf_path,
+        &output_path,
+        max_depth,
+        declared_manifest.as_deref(),
+        syscall_table.as_deref(),
+    )?;
 
     Ok(())
 }